@@ -4,7 +4,7 @@ use halo2_proofs::plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Sele
 use halo2_proofs::poly::Rotation;
 use std::marker::PhantomData;
 
-// a==b
+// a==b, 只能强制相等; 需要得到比较结果的用 IsZeroChip::is_equal
 /// a | b | sel
 
 #[derive(Clone, Debug)]
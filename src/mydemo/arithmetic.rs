@@ -0,0 +1,347 @@
+use crate::ACell;
+use halo2_proofs::arithmetic::FieldExt;
+use halo2_proofs::circuit::Layouter;
+use halo2_proofs::plonk::{Advice, Column, ConstraintSystem, Error, Fixed, Instance, Selector};
+use halo2_proofs::poly::Rotation;
+use std::fmt::Debug;
+use std::marker::PhantomData;
+
+// 算术运算的指令集, 电路只依赖这个trait, 换一个chip不需要改 synthesize
+pub trait ArithmeticInstructions<F: FieldExt>: Sized {
+    type Config: Clone + Debug;
+    // 电路里的数
+    type Num: Clone;
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config;
+
+    fn construct(config: Self::Config) -> Self;
+
+    fn load_private(
+        &self,
+        layouter: impl Layouter<F>,
+        value: Option<F>,
+    ) -> Result<Self::Num, Error>;
+
+    fn constant(&self, layouter: impl Layouter<F>, value: F) -> Result<Self::Num, Error>;
+
+    fn add(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &Self::Num,
+        b: &Self::Num,
+    ) -> Result<Self::Num, Error>;
+
+    fn sub(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &Self::Num,
+        b: &Self::Num,
+    ) -> Result<Self::Num, Error>;
+
+    fn mul(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &Self::Num,
+        b: &Self::Num,
+    ) -> Result<Self::Num, Error>;
+
+    fn neg(&self, layouter: impl Layouter<F>, a: &Self::Num) -> Result<Self::Num, Error>;
+
+    fn assert_equal(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &Self::Num,
+        b: &Self::Num,
+    ) -> Result<(), Error>;
+
+    fn expose_public(
+        &self,
+        layouter: impl Layouter<F>,
+        num: &Self::Num,
+        row: usize,
+    ) -> Result<(), Error>;
+}
+
+// 在 FiboChip 的基础上加了乘法:
+// a | b | c | s_add | s_mul
+// s_add: a + b = c
+// s_mul: a * b = c
+// sub 和 neg 也用加法的gate: a - b = c 既 b + c = a, -a = c 既 a + c = 0
+#[derive(Clone, Debug)]
+pub struct ArithmeticConfig {
+    pub advice: [Column<Advice>; 3],
+    pub constant: Column<Fixed>,
+    pub s_add: Selector,
+    pub s_mul: Selector,
+    pub instance: Column<Instance>,
+}
+
+pub struct ArithmeticChip<F: FieldExt> {
+    config: ArithmeticConfig,
+    _p: PhantomData<F>,
+}
+
+impl<F: FieldExt> ArithmeticChip<F> {
+    // 一行: inputs 复制到指定的列, output 是新的值
+    // sub / neg 的结果不在最后一列
+    fn assign_row(
+        &self,
+        mut layouter: impl Layouter<F>,
+        selector: Selector,
+        inputs: [(usize, &ACell<F>); 2],
+        output: (usize, Option<F>),
+    ) -> Result<ACell<F>, Error> {
+        layouter.assign_region(
+            || "arithmetic row",
+            |mut region| {
+                selector.enable(&mut region, 0)?;
+                for (column, cell) in inputs {
+                    cell.0
+                        .copy_advice(|| "copy", &mut region, self.config.advice[column], 0)?;
+                }
+                region
+                    .assign_advice(
+                        || "output",
+                        self.config.advice[output.0],
+                        0,
+                        || output.1.ok_or(Error::Synthesis),
+                    )
+                    .map(ACell)
+            },
+        )
+    }
+}
+
+impl<F: FieldExt> ArithmeticInstructions<F> for ArithmeticChip<F> {
+    type Config = ArithmeticConfig;
+    type Num = ACell<F>;
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> ArithmeticConfig {
+        let advice = [(); 3].map(|_| meta.advice_column());
+        let constant = meta.fixed_column();
+        let s_add = meta.selector();
+        let s_mul = meta.selector();
+        let instance = meta.instance_column();
+
+        for column in advice {
+            meta.enable_equality(column);
+        }
+        meta.enable_equality(instance);
+        meta.enable_constant(constant);
+
+        meta.create_gate("add", |meta| {
+            let s = meta.query_selector(s_add);
+            let a = meta.query_advice(advice[0], Rotation::cur());
+            let b = meta.query_advice(advice[1], Rotation::cur());
+            let c = meta.query_advice(advice[2], Rotation::cur());
+            vec![s * (a + b - c)]
+        });
+        meta.create_gate("mul", |meta| {
+            let s = meta.query_selector(s_mul);
+            let a = meta.query_advice(advice[0], Rotation::cur());
+            let b = meta.query_advice(advice[1], Rotation::cur());
+            let c = meta.query_advice(advice[2], Rotation::cur());
+            vec![s * (a * b - c)]
+        });
+
+        ArithmeticConfig {
+            advice,
+            constant,
+            s_add,
+            s_mul,
+            instance,
+        }
+    }
+
+    fn construct(config: ArithmeticConfig) -> Self {
+        Self {
+            config,
+            _p: Default::default(),
+        }
+    }
+
+    fn load_private(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Option<F>,
+    ) -> Result<ACell<F>, Error> {
+        layouter.assign_region(
+            || "load private",
+            |mut region| {
+                region
+                    .assign_advice(
+                        || "private",
+                        self.config.advice[0],
+                        0,
+                        || value.ok_or(Error::Synthesis),
+                    )
+                    .map(ACell)
+            },
+        )
+    }
+
+    fn constant(&self, mut layouter: impl Layouter<F>, value: F) -> Result<ACell<F>, Error> {
+        layouter.assign_region(
+            || "constant",
+            |mut region| {
+                region
+                    .assign_advice_from_constant(|| "constant", self.config.advice[0], 0, value)
+                    .map(ACell)
+            },
+        )
+    }
+
+    fn add(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &ACell<F>,
+        b: &ACell<F>,
+    ) -> Result<ACell<F>, Error> {
+        let c = a.0.value().and_then(|a| b.0.value().map(|b| *a + *b));
+        self.assign_row(layouter, self.config.s_add, [(0, a), (1, b)], (2, c))
+    }
+
+    fn sub(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &ACell<F>,
+        b: &ACell<F>,
+    ) -> Result<ACell<F>, Error> {
+        // b + c = a
+        let c = a.0.value().and_then(|a| b.0.value().map(|b| *a - *b));
+        self.assign_row(layouter, self.config.s_add, [(2, a), (0, b)], (1, c))
+    }
+
+    fn mul(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &ACell<F>,
+        b: &ACell<F>,
+    ) -> Result<ACell<F>, Error> {
+        let c = a.0.value().and_then(|a| b.0.value().map(|b| *a * *b));
+        self.assign_row(layouter, self.config.s_mul, [(0, a), (1, b)], (2, c))
+    }
+
+    fn neg(&self, mut layouter: impl Layouter<F>, a: &ACell<F>) -> Result<ACell<F>, Error> {
+        // a + c = 0
+        let zero = self.constant(layouter.namespace(|| "zero"), F::zero())?;
+        let c = a.0.value().map(|a| -*a);
+        self.assign_row(layouter, self.config.s_add, [(0, a), (2, &zero)], (1, c))
+    }
+
+    fn assert_equal(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &ACell<F>,
+        b: &ACell<F>,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "assert equal",
+            |mut region| {
+                // 复制到一个新的cell里, 空的 region 会让 MockProver 报错时 panic
+                let a =
+                    a.0.copy_advice(|| "copy a", &mut region, self.config.advice[0], 0)?;
+                region.constrain_equal(a.cell(), b.0.cell())
+            },
+        )
+    }
+
+    fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        num: &ACell<F>,
+        row: usize,
+    ) -> Result<(), Error> {
+        layouter.constrain_instance(num.0.cell(), self.config.instance, row)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mydemo::arithmetic::{ArithmeticChip, ArithmeticInstructions};
+    use crate::mydemo::main_gate::MainGateChip;
+    use halo2_proofs::arithmetic::FieldExt;
+    use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner};
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::pairing::bn256::Fr;
+    use halo2_proofs::plonk::{Circuit, ConstraintSystem, Error};
+    use std::marker::PhantomData;
+
+    // out = -(x^3 - x * y + 5), 并且检查 y - x == d
+    // 只依赖 ArithmeticInstructions, chip 是类型参数
+    pub struct PolyCircuit<F: FieldExt, C: ArithmeticInstructions<F>> {
+        x: Option<F>,
+        y: Option<F>,
+        d: Option<F>,
+        _c: PhantomData<C>,
+    }
+
+    impl<F: FieldExt, C: ArithmeticInstructions<F>> Circuit<F> for PolyCircuit<F, C> {
+        type Config = C::Config;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                x: None,
+                y: None,
+                d: None,
+                _c: Default::default(),
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            C::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let chip = C::construct(config);
+            let x = chip.load_private(layouter.namespace(|| "x"), self.x)?;
+            let y = chip.load_private(layouter.namespace(|| "y"), self.y)?;
+            let d = chip.load_private(layouter.namespace(|| "d"), self.d)?;
+            let five = chip.constant(layouter.namespace(|| "5"), F::from(5u64))?;
+
+            let x2 = chip.mul(layouter.namespace(|| "x^2"), &x, &x)?;
+            let x3 = chip.mul(layouter.namespace(|| "x^3"), &x2, &x)?;
+            let xy = chip.mul(layouter.namespace(|| "x * y"), &x, &y)?;
+            let t = chip.sub(layouter.namespace(|| "x^3 - xy"), &x3, &xy)?;
+            let t = chip.add(layouter.namespace(|| "+ 5"), &t, &five)?;
+            let out = chip.neg(layouter.namespace(|| "neg"), &t)?;
+
+            let diff = chip.sub(layouter.namespace(|| "y - x"), &y, &x)?;
+            chip.assert_equal(layouter.namespace(|| "y - x == d"), &diff, &d)?;
+            chip.expose_public(layouter.namespace(|| "out"), &out, 0)
+        }
+    }
+
+    fn run<C: ArithmeticInstructions<Fr>>(x: u64, y: u64, d: u64, out: Fr) -> bool {
+        let circuit = PolyCircuit::<Fr, C> {
+            x: Some(Fr::from(x)),
+            y: Some(Fr::from(y)),
+            d: Some(Fr::from(d)),
+            _c: Default::default(),
+        };
+        let prover = MockProver::run(5, &circuit, vec![vec![out]]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    fn check<C: ArithmeticInstructions<Fr>>() {
+        // -(27 - 3 * 7 + 5) = -11
+        assert!(run::<C>(3, 7, 4, -Fr::from(11u64)));
+        assert!(!run::<C>(3, 7, 4, Fr::from(11u64)));
+        assert!(!run::<C>(3, 7, 5, -Fr::from(11u64)));
+    }
+
+    #[test]
+    pub fn test_arithmetic_chip() {
+        check::<ArithmeticChip<Fr>>();
+    }
+
+    #[test]
+    pub fn test_swap_chip() {
+        check::<MainGateChip<Fr>>();
+    }
+}
@@ -0,0 +1,735 @@
+use crate::mydemo::bigint::native::{self, Limbs, LIMB_BITS};
+use crate::mydemo::boolean::AssignedBool;
+use crate::range_check::chip::{RangeChip, RangeConfig};
+use halo2_proofs::arithmetic::FieldExt;
+use halo2_proofs::circuit::{AssignedCell, Layouter, Region};
+use halo2_proofs::plonk::{Advice, Column, ConstraintSystem, Error, Fixed, Instance, Selector};
+use halo2_proofs::poly::Rotation;
+use std::cmp::Ordering;
+use std::marker::PhantomData;
+
+// 小端序的 limb, 每个 limb 都检查过在 [0, 2^LIMB_BITS) 里
+#[derive(Clone, Debug)]
+pub struct AssignedBigInt<F: FieldExt> {
+    pub limbs: Vec<AssignedCell<F, F>>,
+}
+
+impl<F: FieldExt> AssignedBigInt<F> {
+    pub fn value(&self) -> Option<Limbs> {
+        self.limbs
+            .iter()
+            .map(|limb| limb.value().map(|v| v.get_lower_128() as u64))
+            .collect()
+    }
+
+    pub fn num_limbs(&self) -> usize {
+        self.limbs.len()
+    }
+}
+
+// a | b | c | d
+// mul add: d_next = d + a * b, 用来算乘法每一列的和
+// add:     a + b + c = d + c_next * 2^LIMB_BITS, c 是进位
+// carry:   d + a = b + c * 2^LIMB_BITS, d 是列的和, a 是低一列的进位, b 是结果, c 是进位
+// select:  d = a * (b - c) + c
+// 所有的数都远小于 p, 所以域上的等式也是整数上的等式
+#[derive(Clone, Debug)]
+pub struct BigIntConfig<F: FieldExt> {
+    pub advice: [Column<Advice>; 4],
+    pub constant: Column<Fixed>,
+    pub s_mul_add: Selector,
+    pub s_add: Selector,
+    pub s_carry: Selector,
+    pub s_select: Selector,
+    pub range: RangeConfig<F>,
+    pub instance: Column<Instance>,
+}
+
+pub struct BigIntChip<F: FieldExt> {
+    config: BigIntConfig<F>,
+    range: RangeChip<F>,
+    _p: PhantomData<F>,
+}
+
+fn limb<F: FieldExt>(cell: &AssignedCell<F, F>) -> Option<u64> {
+    cell.value().map(|v| v.get_lower_128() as u64)
+}
+
+fn to_field<F: FieldExt>(limbs: &[u64]) -> F {
+    let base = F::from_u128(1 << LIMB_BITS);
+    limbs
+        .iter()
+        .rev()
+        .fold(F::zero(), |acc, limb| acc * base + F::from(*limb))
+}
+
+// 乘法的进位最多 LIMB_BITS + bits(n) + 1 位, n 是每一列最多的乘积个数
+fn carry_bits(n: usize) -> usize {
+    LIMB_BITS + (usize::BITS - n.leading_zeros()) as usize + 1
+}
+
+impl<F: FieldExt> BigIntChip<F> {
+    pub fn construct(config: BigIntConfig<F>) -> Self {
+        let range = RangeChip::construct(config.range.clone());
+        Self {
+            config,
+            range,
+            _p: Default::default(),
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> BigIntConfig<F> {
+        let advice = [(); 4].map(|_| meta.advice_column());
+        let constant = meta.fixed_column();
+        let s_mul_add = meta.selector();
+        let s_add = meta.selector();
+        let s_carry = meta.selector();
+        let s_select = meta.selector();
+        let instance = meta.instance_column();
+        let range = RangeChip::configure(meta);
+
+        for column in advice {
+            meta.enable_equality(column);
+        }
+        meta.enable_equality(instance);
+        meta.enable_constant(constant);
+
+        let base = F::from_u128(1 << LIMB_BITS);
+        meta.create_gate("bigint mul add", |meta| {
+            let s = meta.query_selector(s_mul_add);
+            let a = meta.query_advice(advice[0], Rotation::cur());
+            let b = meta.query_advice(advice[1], Rotation::cur());
+            let d = meta.query_advice(advice[3], Rotation::cur());
+            let d_next = meta.query_advice(advice[3], Rotation::next());
+            vec![s * (d + a * b - d_next)]
+        });
+        meta.create_gate("bigint add", |meta| {
+            let s = meta.query_selector(s_add);
+            let [a, b, c, d] = advice.map(|c| meta.query_advice(c, Rotation::cur()));
+            let c_next = meta.query_advice(advice[2], Rotation::next());
+            vec![s * (a + b + c - d - c_next * base)]
+        });
+        meta.create_gate("bigint carry", |meta| {
+            let s = meta.query_selector(s_carry);
+            let [a, b, c, d] = advice.map(|c| meta.query_advice(c, Rotation::cur()));
+            vec![s * (d + a - b - c * base)]
+        });
+        meta.create_gate("bigint select", |meta| {
+            let s = meta.query_selector(s_select);
+            let [a, b, c, d] = advice.map(|c| meta.query_advice(c, Rotation::cur()));
+            vec![s * (a * (b - c.clone()) + c - d)]
+        });
+
+        BigIntConfig {
+            advice,
+            constant,
+            s_mul_add,
+            s_add,
+            s_carry,
+            s_select,
+            range,
+            instance,
+        }
+    }
+
+    pub fn load_table(&self, layouter: impl Layouter<F>) -> Result<(), Error> {
+        self.range.load_table(layouter)
+    }
+
+    // 第 i 个 limb, 不够时用常数0
+    fn copy_limb(
+        region: &mut Region<'_, F>,
+        limbs: &[AssignedCell<F, F>],
+        i: usize,
+        column: Column<Advice>,
+        offset: usize,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        match limbs.get(i) {
+            Some(cell) => cell.copy_advice(|| "limb", region, column, offset),
+            None => region.assign_advice_from_constant(|| "zero", column, offset, F::zero()),
+        }
+    }
+
+    fn range_check_all(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cells: &[AssignedCell<F, F>],
+        num_bits: usize,
+    ) -> Result<(), Error> {
+        for cell in cells {
+            self.range
+                .range_check(layouter.namespace(|| "range"), cell, num_bits)?;
+        }
+        Ok(())
+    }
+
+    // 值放不进 num_limbs 个 limb 时返回 Error::Synthesis
+    pub fn load_private(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Option<Limbs>,
+        num_limbs: usize,
+    ) -> Result<AssignedBigInt<F>, Error> {
+        assert!(num_limbs > 0);
+        let value = match value.map(|v| native::resize(&v, num_limbs)) {
+            Some(None) => return Err(Error::Synthesis),
+            value => value.flatten(),
+        };
+        let limbs = layouter.assign_region(
+            || "load bigint",
+            |mut region| {
+                (0..num_limbs)
+                    .map(|i| {
+                        let v = value.as_ref().map(|v| F::from(v[i]));
+                        region.assign_advice(
+                            || "limb",
+                            self.config.advice[0],
+                            i,
+                            || v.ok_or(Error::Synthesis),
+                        )
+                    })
+                    .collect::<Result<Vec<_>, Error>>()
+            },
+        )?;
+        self.range_check_all(layouter.namespace(|| "limbs"), &limbs, LIMB_BITS)?;
+        Ok(AssignedBigInt { limbs })
+    }
+
+    pub fn load_constant(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: &[u64],
+    ) -> Result<AssignedBigInt<F>, Error> {
+        assert!(!value.is_empty());
+        let limbs = layouter.assign_region(
+            || "constant bigint",
+            |mut region| {
+                value
+                    .iter()
+                    .enumerate()
+                    .map(|(i, v)| {
+                        region.assign_advice_from_constant(
+                            || "limb",
+                            self.config.advice[0],
+                            i,
+                            F::from(*v),
+                        )
+                    })
+                    .collect::<Result<Vec<_>, Error>>()
+            },
+        )?;
+        Ok(AssignedBigInt { limbs })
+    }
+
+    // 结果有 max(len) + 1 个 limb, carry_in 为 None 时是0, 否则必须是0或者1
+    fn add_with_carry(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &AssignedBigInt<F>,
+        b: &AssignedBigInt<F>,
+        carry_in: Option<&AssignedCell<F, F>>,
+    ) -> Result<AssignedBigInt<F>, Error> {
+        let n = a.num_limbs().max(b.num_limbs());
+        let (limbs, carries) = layouter.assign_region(
+            || "bigint add",
+            |mut region| {
+                let [col_a, col_b, col_c, col_d] = self.config.advice;
+                let mut carry = match carry_in {
+                    Some(cell) => cell.copy_advice(|| "carry in", &mut region, col_c, 0)?,
+                    None => {
+                        region.assign_advice_from_constant(|| "carry in", col_c, 0, F::zero())?
+                    }
+                };
+                let mut limbs = Vec::with_capacity(n + 1);
+                let mut carries = Vec::with_capacity(n);
+                for i in 0..n {
+                    self.config.s_add.enable(&mut region, i)?;
+                    let x = Self::copy_limb(&mut region, &a.limbs, i, col_a, i)?;
+                    let y = Self::copy_limb(&mut region, &b.limbs, i, col_b, i)?;
+                    let sum = limb(&x).and_then(|x| {
+                        let y = limb(&y)?;
+                        let c = limb(&carry)?;
+                        Some(x as u128 + y as u128 + c as u128)
+                    });
+                    limbs.push(region.assign_advice(
+                        || "sum",
+                        col_d,
+                        i,
+                        || sum.map(|s| F::from(s as u64)).ok_or(Error::Synthesis),
+                    )?);
+                    carry = region.assign_advice(
+                        || "carry",
+                        col_c,
+                        i + 1,
+                        || {
+                            sum.map(|s| F::from((s >> LIMB_BITS) as u64))
+                                .ok_or(Error::Synthesis)
+                        },
+                    )?;
+                    carries.push(carry.clone());
+                }
+                // 最高位的进位就是最高的 limb
+                limbs.push(carry);
+                Ok((limbs, carries))
+            },
+        )?;
+        self.range_check_all(layouter.namespace(|| "sum"), &limbs[..n], LIMB_BITS)?;
+        self.range_check_all(layouter.namespace(|| "carry"), &carries, 1)?;
+        Ok(AssignedBigInt { limbs })
+    }
+
+    pub fn add(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedBigInt<F>,
+        b: &AssignedBigInt<F>,
+    ) -> Result<AssignedBigInt<F>, Error> {
+        self.add_with_carry(layouter, a, b, None)
+    }
+
+    // 结果有 max(len) 个 limb, a < b 时约束不满足
+    pub fn sub(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &AssignedBigInt<F>,
+        b: &AssignedBigInt<F>,
+    ) -> Result<AssignedBigInt<F>, Error> {
+        let n = a.num_limbs().max(b.num_limbs());
+        let diff = a.value().and_then(|a| {
+            let b = b.value()?;
+            Some(native::sub(&a, &b).unwrap_or_else(|| vec![0; n]))
+        });
+        let diff = self.load_private(layouter.namespace(|| "a - b"), diff, n)?;
+        let sum = self.add(layouter.namespace(|| "a - b + b"), &diff, b)?;
+        self.assert_equal(layouter.namespace(|| "a - b + b == a"), &sum, a)?;
+        Ok(diff)
+    }
+
+    // 结果有 a.len() + b.len() 个 limb
+    pub fn mul(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &AssignedBigInt<F>,
+        b: &AssignedBigInt<F>,
+    ) -> Result<AssignedBigInt<F>, Error> {
+        let (na, nb) = (a.num_limbs(), b.num_limbs());
+        let (mut limbs, carries) = layouter.assign_region(
+            || "bigint mul",
+            |mut region| {
+                let [col_a, col_b, col_c, col_d] = self.config.advice;
+                let mut offset = 0;
+                let mut limbs = Vec::with_capacity(na + nb);
+                let mut carries: Vec<AssignedCell<F, F>> = Vec::with_capacity(na + nb - 1);
+                let mut carry = Some(vec![0u64]);
+                for k in 0..na + nb - 1 {
+                    // 第 k 列: sum a_i * b_j, i + j = k, 可能超过 u128, 用 limb 算
+                    let mut acc = Some(vec![0u64]);
+                    region.assign_advice_from_constant(|| "acc", col_d, offset, F::zero())?;
+                    for i in k.saturating_sub(nb - 1)..=k.min(na - 1) {
+                        self.config.s_mul_add.enable(&mut region, offset)?;
+                        let x = a.limbs[i].copy_advice(|| "a", &mut region, col_a, offset)?;
+                        let y = b.limbs[k - i].copy_advice(|| "b", &mut region, col_b, offset)?;
+                        acc = acc.and_then(|acc| {
+                            let prod = native::mul(&[limb(&x)?], &[limb(&y)?]);
+                            Some(native::add(&acc, &prod))
+                        });
+                        offset += 1;
+                        region.assign_advice(
+                            || "acc",
+                            col_d,
+                            offset,
+                            || acc.as_deref().map(to_field::<F>).ok_or(Error::Synthesis),
+                        )?;
+                    }
+
+                    self.config.s_carry.enable(&mut region, offset)?;
+                    match carries.last() {
+                        Some(cell) => {
+                            cell.copy_advice(|| "carry in", &mut region, col_a, offset)?;
+                        }
+                        None => {
+                            region.assign_advice_from_constant(
+                                || "carry in",
+                                col_a,
+                                offset,
+                                F::zero(),
+                            )?;
+                        }
+                    };
+                    let total = acc.zip(carry).map(|(acc, c)| native::add(&acc, &c));
+                    limbs.push(region.assign_advice(
+                        || "out",
+                        col_b,
+                        offset,
+                        || {
+                            total
+                                .as_ref()
+                                .map(|t| F::from(t[0]))
+                                .ok_or(Error::Synthesis)
+                        },
+                    )?);
+                    carry = total.map(|t| t[1..].to_vec());
+                    carries.push(region.assign_advice(
+                        || "carry out",
+                        col_c,
+                        offset,
+                        || carry.as_deref().map(to_field::<F>).ok_or(Error::Synthesis),
+                    )?);
+                    offset += 1;
+                }
+                Ok((limbs, carries))
+            },
+        )?;
+        let (top, carries) = carries.split_last().unwrap();
+        self.range_check_all(layouter.namespace(|| "out"), &limbs, LIMB_BITS)?;
+        self.range_check_all(
+            layouter.namespace(|| "carry"),
+            carries,
+            carry_bits(na.min(nb)),
+        )?;
+        // 乘积放得下 na + nb 个 limb, 最后的进位就是最高的 limb
+        self.range
+            .range_check(layouter.namespace(|| "top"), top, LIMB_BITS)?;
+        limbs.push(top.clone());
+        Ok(AssignedBigInt { limbs })
+    }
+
+    // cond 为1时返回 x, 否则返回 y, cond 必须已经是 bool
+    fn select(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cond: &AssignedCell<F, F>,
+        x: &AssignedBigInt<F>,
+        y: &AssignedBigInt<F>,
+    ) -> Result<AssignedBigInt<F>, Error> {
+        let n = x.num_limbs().max(y.num_limbs());
+        let limbs = layouter.assign_region(
+            || "bigint select",
+            |mut region| {
+                let [col_a, col_b, col_c, col_d] = self.config.advice;
+                (0..n)
+                    .map(|i| {
+                        self.config.s_select.enable(&mut region, i)?;
+                        let c = cond.copy_advice(|| "cond", &mut region, col_a, i)?;
+                        let x = Self::copy_limb(&mut region, &x.limbs, i, col_b, i)?;
+                        let y = Self::copy_limb(&mut region, &y.limbs, i, col_c, i)?;
+                        let out =
+                            c.value()
+                                .and_then(|c| if *c == F::one() { x.value() } else { y.value() });
+                        region.assign_advice(
+                            || "out",
+                            col_d,
+                            i,
+                            || out.copied().ok_or(Error::Synthesis),
+                        )
+                    })
+                    .collect::<Result<Vec<_>, Error>>()
+            },
+        )?;
+        Ok(AssignedBigInt { limbs })
+    }
+
+    // 返回 a < b
+    // lt = 1 时 a + d + 1 = b, lt = 0 时 b + d = a, d >= 0
+    // 写成 select(lt, a, b) + d + lt = select(lt, b, a)
+    pub fn less_than(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &AssignedBigInt<F>,
+        b: &AssignedBigInt<F>,
+    ) -> Result<AssignedBool<F>, Error> {
+        let n = a.num_limbs().max(b.num_limbs());
+        let values = a.value().and_then(|a| b.value().map(|b| (a, b)));
+        let lt = values
+            .as_ref()
+            .map(|(a, b)| native::cmp(a, b) == Ordering::Less);
+        let diff = values.as_ref().zip(lt).map(|((a, b), lt)| {
+            if lt {
+                native::sub(&native::sub(b, a).unwrap(), &[1]).unwrap()
+            } else {
+                native::sub(a, b).unwrap()
+            }
+        });
+
+        let lt = layouter.assign_region(
+            || "less than",
+            |mut region| {
+                region.assign_advice(
+                    || "lt",
+                    self.config.advice[0],
+                    0,
+                    || lt.map(|lt| F::from(lt as u64)).ok_or(Error::Synthesis),
+                )
+            },
+        )?;
+        self.range
+            .range_check(layouter.namespace(|| "lt"), &lt, 1)?;
+        let diff = self.load_private(layouter.namespace(|| "diff"), diff, n)?;
+        let lhs = self.select(layouter.namespace(|| "lhs"), &lt, a, b)?;
+        let rhs = self.select(layouter.namespace(|| "rhs"), &lt, b, a)?;
+        let sum = self.add_with_carry(
+            layouter.namespace(|| "lhs + d + lt"),
+            &lhs,
+            &diff,
+            Some(&lt),
+        )?;
+        self.assert_equal(layouter.namespace(|| "== rhs"), &sum, &rhs)?;
+        Ok(AssignedBool(lt))
+    }
+
+    pub fn assert_less_than(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &AssignedBigInt<F>,
+        b: &AssignedBigInt<F>,
+    ) -> Result<(), Error> {
+        let lt = self.less_than(layouter.namespace(|| "a < b"), a, b)?;
+        layouter.assign_region(
+            || "assert less than",
+            |mut region| {
+                let lt =
+                    lt.0.copy_advice(|| "lt", &mut region, self.config.advice[0], 0)?;
+                let one = region.assign_advice_from_constant(
+                    || "one",
+                    self.config.advice[1],
+                    0,
+                    F::one(),
+                )?;
+                region.constrain_equal(lt.cell(), one.cell())
+            },
+        )
+    }
+
+    // limb 个数不同时, 多出来的 limb 必须是0
+    pub fn assert_equal(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &AssignedBigInt<F>,
+        b: &AssignedBigInt<F>,
+    ) -> Result<(), Error> {
+        let n = a.num_limbs().max(b.num_limbs());
+        layouter.assign_region(
+            || "bigint assert equal",
+            |mut region| {
+                for i in 0..n {
+                    let x = Self::copy_limb(&mut region, &a.limbs, i, self.config.advice[0], i)?;
+                    let y = Self::copy_limb(&mut region, &b.limbs, i, self.config.advice[1], i)?;
+                    region.constrain_equal(x.cell(), y.cell())?;
+                }
+                Ok(())
+            },
+        )
+    }
+
+    // a = q * m + r, r < m; q 有 a.len() 个 limb, r 有 m.len() 个 limb
+    // m = 0 时约束不满足
+    pub fn div_rem(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &AssignedBigInt<F>,
+        m: &AssignedBigInt<F>,
+    ) -> Result<(AssignedBigInt<F>, AssignedBigInt<F>), Error> {
+        let (na, nm) = (a.num_limbs(), m.num_limbs());
+        let qr = a.value().and_then(|a| {
+            let m = m.value()?;
+            Some(native::div_rem(&a, &m).unwrap_or((vec![0; na], vec![0; nm])))
+        });
+        let (q, r) = match qr {
+            Some((q, r)) => (Some(q), Some(r)),
+            None => (None, None),
+        };
+        let q = self.load_private(layouter.namespace(|| "q"), q, na)?;
+        let r = self.load_private(layouter.namespace(|| "r"), r, nm)?;
+        let qm = self.mul(layouter.namespace(|| "q * m"), &q, m)?;
+        let sum = self.add(layouter.namespace(|| "q * m + r"), &qm, &r)?;
+        self.assert_equal(layouter.namespace(|| "q * m + r == a"), &sum, a)?;
+
+        self.assert_less_than(layouter.namespace(|| "r < m"), &r, m)?;
+        Ok((q, r))
+    }
+
+    pub fn reduce(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedBigInt<F>,
+        m: &AssignedBigInt<F>,
+    ) -> Result<AssignedBigInt<F>, Error> {
+        self.div_rem(layouter, a, m).map(|(_, r)| r)
+    }
+
+    // 从 instance 的第 offset 行开始, 每个 limb 一行
+    pub fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: &AssignedBigInt<F>,
+        offset: usize,
+    ) -> Result<(), Error> {
+        for (i, limb) in value.limbs.iter().enumerate() {
+            layouter.constrain_instance(limb.cell(), self.config.instance, offset + i)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mydemo::bigint::chip::{BigIntChip, BigIntConfig};
+    use crate::mydemo::bigint::native::{self, Limbs};
+    use halo2_proofs::arithmetic::FieldExt;
+    use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner};
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::pairing::bn256::Fr;
+    use halo2_proofs::plonk::{Circuit, ConstraintSystem, Error};
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use std::cmp::Ordering;
+
+    // secp256k1 的基域模数
+    fn modulus() -> Limbs {
+        native::from_hex("fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2f")
+    }
+
+    // 依次暴露 a + b, a - b, a * b, a < b, a mod b, a * b mod p
+    pub struct BigIntCircuit<F: FieldExt> {
+        a: Option<Limbs>,
+        b: Option<Limbs>,
+        num_limbs: usize,
+        _p: std::marker::PhantomData<F>,
+    }
+
+    impl<F: FieldExt> Circuit<F> for BigIntCircuit<F> {
+        type Config = BigIntConfig<F>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                a: None,
+                b: None,
+                num_limbs: self.num_limbs,
+                _p: Default::default(),
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            BigIntChip::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let chip = BigIntChip::construct(config);
+            chip.load_table(layouter.namespace(|| "table"))?;
+            let a =
+                chip.load_private(layouter.namespace(|| "a"), self.a.clone(), self.num_limbs)?;
+            let b =
+                chip.load_private(layouter.namespace(|| "b"), self.b.clone(), self.num_limbs)?;
+
+            let sum = chip.add(layouter.namespace(|| "a + b"), &a, &b)?;
+            let diff = chip.sub(layouter.namespace(|| "a - b"), &a, &b)?;
+            let prod = chip.mul(layouter.namespace(|| "a * b"), &a, &b)?;
+            let lt = chip.less_than(layouter.namespace(|| "a < b"), &a, &b)?;
+            let rem = chip.reduce(layouter.namespace(|| "a mod b"), &a, &b)?;
+            let p = chip.load_constant(layouter.namespace(|| "p"), &modulus())?;
+            let prod_mod_p = chip.reduce(layouter.namespace(|| "a * b mod p"), &prod, &p)?;
+
+            let mut offset = 0;
+            for value in [&sum, &diff, &prod] {
+                chip.expose_public(layouter.namespace(|| "out"), value, offset)?;
+                offset += value.num_limbs();
+            }
+            layouter.constrain_instance(lt.0.cell(), chip.config.instance, offset)?;
+            chip.expose_public(layouter.namespace(|| "rem"), &rem, offset + 1)?;
+            offset += 1 + rem.num_limbs();
+            chip.expose_public(layouter.namespace(|| "prod mod p"), &prod_mod_p, offset)
+        }
+    }
+
+    fn expected(a: &Limbs, b: &Limbs) -> Vec<Fr> {
+        let n = a.len();
+        let diff = native::sub(a, b).unwrap_or_else(|| vec![0; n]);
+        let lt = native::cmp(a, b) == Ordering::Less;
+        let rem = native::div_rem(a, b)
+            .map(|(_, r)| r)
+            .unwrap_or_else(|| vec![0; n]);
+        let prod = native::mul(a, b);
+        let prod_mod_p = native::div_rem(&prod, &modulus()).unwrap().1;
+        native::add(a, b)
+            .into_iter()
+            .chain(diff)
+            .chain(prod)
+            .chain([lt as u64])
+            .chain(rem)
+            .chain(prod_mod_p)
+            .map(Fr::from)
+            .collect()
+    }
+
+    fn prove(a: &Limbs, b: &Limbs, instance: Vec<Fr>, k: u32) -> bool {
+        let circuit = BigIntCircuit::<Fr> {
+            a: Some(a.clone()),
+            b: Some(b.clone()),
+            num_limbs: a.len(),
+            _p: Default::default(),
+        };
+        let prover = MockProver::run(k, &circuit, vec![instance]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    fn random(rng: &mut StdRng, num_limbs: usize) -> Limbs {
+        (0..num_limbs).map(|_| rng.gen()).collect()
+    }
+
+    #[test]
+    pub fn test_bigint_256() {
+        let mut rng = StdRng::seed_from_u64(49);
+        let mut b = random(&mut rng, 4);
+        let a = random(&mut rng, 4);
+        b[3] >>= 1;
+        let a = if native::cmp(&a, &b) == Ordering::Less {
+            native::add(&a, &b)[..4].to_vec()
+        } else {
+            a
+        };
+        let instance = expected(&a, &b);
+        assert!(prove(&a, &b, instance.clone(), 12));
+
+        // 每一段输出都改一个
+        for i in [0, 5, 9, 17, 18, 22] {
+            let mut wrong = instance.clone();
+            wrong[i] += Fr::one();
+            assert!(!prove(&a, &b, wrong, 12));
+        }
+
+        // 全是最大值的情况, a == b
+        let max = vec![u64::MAX; 4];
+        assert!(prove(&max, &max, expected(&max, &max), 12));
+    }
+
+    #[test]
+    pub fn test_bigint_invalid() {
+        // a < b 时 a - b 不存在
+        let a = vec![1, 2, 3, 4];
+        let b = vec![1, 2, 3, 5];
+        assert!(!prove(&a, &b, expected(&a, &b), 12));
+        // 模0
+        let zero = vec![0; 4];
+        assert!(!prove(&a, &zero, expected(&a, &zero), 12));
+    }
+
+    #[test]
+    pub fn test_bigint_2048() {
+        let mut rng = StdRng::seed_from_u64(2048);
+        let a = random(&mut rng, 32);
+        let mut b = random(&mut rng, 32);
+        b[31] >>= 3;
+        let instance = expected(&a, &b);
+        assert!(prove(&a, &b, instance.clone(), 14));
+
+        let mut wrong = instance;
+        let last = wrong.len() - 1;
+        wrong[last] += Fr::one();
+        assert!(!prove(&a, &b, wrong, 14));
+    }
+}
@@ -0,0 +1,376 @@
+use crate::mydemo::bigint::chip::{AssignedBigInt, BigIntChip, BigIntConfig};
+use crate::mydemo::bigint::native::{self, Limbs};
+use halo2_proofs::arithmetic::FieldExt;
+use halo2_proofs::circuit::Layouter;
+use halo2_proofs::plonk::{ConstraintSystem, Error};
+use std::cmp::Ordering;
+
+// 外域的模数, 16进制
+pub const SECP256K1_BASE: &str = "fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2f";
+pub const SECP256K1_SCALAR: &str =
+    "fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141";
+pub const BLS12_381_BASE: &str = "1a0111ea397fe69a4b1ba7b6434bacd764774b84f38512bf6730d2a0f6b0f6241eabfffeb153ffffb9feffffffffaaab";
+
+// 外域上的运算, 只用来生成 witness, a 和 b 都要小于 p
+pub fn mod_add(a: &[u64], b: &[u64], p: &[u64]) -> Limbs {
+    native::div_rem(&native::add(a, b), p).unwrap().1
+}
+
+pub fn mod_sub(a: &[u64], b: &[u64], p: &[u64]) -> Limbs {
+    let d = native::sub(&native::add(a, p), b).unwrap();
+    native::div_rem(&d, p).unwrap().1
+}
+
+pub fn mod_mul(a: &[u64], b: &[u64], p: &[u64]) -> Limbs {
+    native::div_rem(&native::mul(a, b), p).unwrap().1
+}
+
+// 费马小定理 a^(p-2), a = 0 时返回 None
+pub fn mod_inv(a: &[u64], p: &[u64]) -> Option<Limbs> {
+    if native::is_zero(a) {
+        return None;
+    }
+    let e = native::sub(p, &[2]).unwrap();
+    let mut out = native::resize(&[1], p.len()).unwrap();
+    for i in (0..e.len() * native::LIMB_BITS).rev() {
+        out = mod_mul(&out, &out, p);
+        if (e[i / native::LIMB_BITS] >> (i % native::LIMB_BITS)) & 1 == 1 {
+            out = mod_mul(&out, a, p);
+        }
+    }
+    Some(out)
+}
+
+// 外域元素, 值一定小于 p
+#[derive(Clone, Debug)]
+pub struct AssignedFieldElement<F: FieldExt>(pub AssignedBigInt<F>);
+
+impl<F: FieldExt> AssignedFieldElement<F> {
+    pub fn value(&self) -> Option<Limbs> {
+        self.0.value()
+    }
+}
+
+// 在 F 上模拟模 p 的运算, 每个元素 num_limbs 个 limb
+// x op y = q * p + r 按 limb 带进位检查整数等式, 再检查 r < p
+// 乘法里 q < p, 加减法里 q <= 1, 所以 q 的 limb 数是固定的
+pub struct NonNativeChip<F: FieldExt> {
+    bigint: BigIntChip<F>,
+    modulus: Limbs,
+}
+
+impl<F: FieldExt> NonNativeChip<F> {
+    pub fn construct(config: BigIntConfig<F>, modulus: &str) -> Self {
+        let modulus = native::from_hex(modulus);
+        assert!(native::cmp(&modulus, &[2]) == Ordering::Greater);
+        Self {
+            bigint: BigIntChip::construct(config),
+            modulus,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> BigIntConfig<F> {
+        BigIntChip::configure(meta)
+    }
+
+    pub fn load_table(&self, layouter: impl Layouter<F>) -> Result<(), Error> {
+        self.bigint.load_table(layouter)
+    }
+
+    pub fn num_limbs(&self) -> usize {
+        self.modulus.len()
+    }
+
+    fn load_modulus(&self, layouter: impl Layouter<F>) -> Result<AssignedBigInt<F>, Error> {
+        self.bigint.load_constant(layouter, &self.modulus)
+    }
+
+    // 值大于等于 p 时约束不满足
+    pub fn load_private(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Option<Limbs>,
+    ) -> Result<AssignedFieldElement<F>, Error> {
+        let x = self
+            .bigint
+            .load_private(layouter.namespace(|| "x"), value, self.num_limbs())?;
+        let p = self.load_modulus(layouter.namespace(|| "p"))?;
+        self.bigint
+            .assert_less_than(layouter.namespace(|| "x < p"), &x, &p)?;
+        Ok(AssignedFieldElement(x))
+    }
+
+    pub fn load_constant(
+        &self,
+        layouter: impl Layouter<F>,
+        value: &[u64],
+    ) -> Result<AssignedFieldElement<F>, Error> {
+        assert!(native::cmp(value, &self.modulus) == Ordering::Less);
+        let value = native::resize(value, self.num_limbs()).unwrap();
+        self.bigint
+            .load_constant(layouter, &value)
+            .map(AssignedFieldElement)
+    }
+
+    // x = q * p + r, q 有 num_q_limbs 个 limb
+    fn reduce(
+        &self,
+        mut layouter: impl Layouter<F>,
+        x: &AssignedBigInt<F>,
+        num_q_limbs: usize,
+    ) -> Result<AssignedFieldElement<F>, Error> {
+        let qr = x
+            .value()
+            .map(|x| native::div_rem(&x, &self.modulus).unwrap());
+        let (q, r) = match qr {
+            Some((q, r)) => (Some(q), Some(r)),
+            None => (None, None),
+        };
+        let q = self
+            .bigint
+            .load_private(layouter.namespace(|| "q"), q, num_q_limbs)?;
+        let r = self.load_private(layouter.namespace(|| "r"), r)?;
+        let p = self.load_modulus(layouter.namespace(|| "p"))?;
+        let qp = self.bigint.mul(layouter.namespace(|| "q * p"), &q, &p)?;
+        let sum = self
+            .bigint
+            .add(layouter.namespace(|| "q * p + r"), &qp, &r.0)?;
+        self.bigint
+            .assert_equal(layouter.namespace(|| "q * p + r == x"), &sum, x)?;
+        Ok(r)
+    }
+
+    pub fn add(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &AssignedFieldElement<F>,
+        b: &AssignedFieldElement<F>,
+    ) -> Result<AssignedFieldElement<F>, Error> {
+        let sum = self
+            .bigint
+            .add(layouter.namespace(|| "a + b"), &a.0, &b.0)?;
+        self.reduce(layouter.namespace(|| "reduce"), &sum, 1)
+    }
+
+    // a + p - b 不会是负数
+    pub fn sub(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &AssignedFieldElement<F>,
+        b: &AssignedFieldElement<F>,
+    ) -> Result<AssignedFieldElement<F>, Error> {
+        let p = self.load_modulus(layouter.namespace(|| "p"))?;
+        let sum = self.bigint.add(layouter.namespace(|| "a + p"), &a.0, &p)?;
+        let diff = self
+            .bigint
+            .sub(layouter.namespace(|| "a + p - b"), &sum, &b.0)?;
+        self.reduce(layouter.namespace(|| "reduce"), &diff, 1)
+    }
+
+    pub fn mul(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &AssignedFieldElement<F>,
+        b: &AssignedFieldElement<F>,
+    ) -> Result<AssignedFieldElement<F>, Error> {
+        let prod = self
+            .bigint
+            .mul(layouter.namespace(|| "a * b"), &a.0, &b.0)?;
+        self.reduce(layouter.namespace(|| "reduce"), &prod, self.num_limbs())
+    }
+
+    // a * inv = 1, a = 0 时约束不满足
+    pub fn inverse(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &AssignedFieldElement<F>,
+    ) -> Result<AssignedFieldElement<F>, Error> {
+        let n = self.num_limbs();
+        let inv = a
+            .value()
+            .map(|a| mod_inv(&a, &self.modulus).unwrap_or_else(|| vec![0; n]));
+        let inv = self.load_private(layouter.namespace(|| "inv"), inv)?;
+        let prod = self.mul(layouter.namespace(|| "a * inv"), a, &inv)?;
+        let one = self.load_constant(layouter.namespace(|| "one"), &[1])?;
+        self.assert_equal(layouter.namespace(|| "a * inv == 1"), &prod, &one)?;
+        Ok(inv)
+    }
+
+    // 两边都小于 p, 表示唯一, 直接比较 limb
+    pub fn assert_equal(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedFieldElement<F>,
+        b: &AssignedFieldElement<F>,
+    ) -> Result<(), Error> {
+        self.bigint.assert_equal(layouter, &a.0, &b.0)
+    }
+
+    pub fn expose_public(
+        &self,
+        layouter: impl Layouter<F>,
+        value: &AssignedFieldElement<F>,
+        offset: usize,
+    ) -> Result<(), Error> {
+        self.bigint.expose_public(layouter, &value.0, offset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mydemo::bigint::chip::BigIntConfig;
+    use crate::mydemo::bigint::field::{
+        mod_add, mod_inv, mod_mul, mod_sub, NonNativeChip, BLS12_381_BASE, SECP256K1_BASE,
+        SECP256K1_SCALAR,
+    };
+    use crate::mydemo::bigint::native::{self, Limbs};
+    use halo2_proofs::arithmetic::FieldExt;
+    use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner};
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::pairing::bn256::Fr;
+    use halo2_proofs::pairing::group::ff::{Field, PrimeField};
+    use halo2_proofs::plonk::{Circuit, ConstraintSystem, Error};
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    // 依次暴露 a + b, a - b, a * b, 1 / a
+    pub struct FieldCircuit<F: FieldExt> {
+        modulus: &'static str,
+        a: Option<Limbs>,
+        b: Option<Limbs>,
+        _p: std::marker::PhantomData<F>,
+    }
+
+    impl<F: FieldExt> Circuit<F> for FieldCircuit<F> {
+        type Config = BigIntConfig<F>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                modulus: self.modulus,
+                a: None,
+                b: None,
+                _p: Default::default(),
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            NonNativeChip::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let chip = NonNativeChip::construct(config, self.modulus);
+            chip.load_table(layouter.namespace(|| "table"))?;
+            let a = chip.load_private(layouter.namespace(|| "a"), self.a.clone())?;
+            let b = chip.load_private(layouter.namespace(|| "b"), self.b.clone())?;
+
+            let sum = chip.add(layouter.namespace(|| "a + b"), &a, &b)?;
+            let diff = chip.sub(layouter.namespace(|| "a - b"), &a, &b)?;
+            let prod = chip.mul(layouter.namespace(|| "a * b"), &a, &b)?;
+            let inv = chip.inverse(layouter.namespace(|| "1 / a"), &a)?;
+
+            // (a - b) + b == a
+            let back = chip.add(layouter.namespace(|| "a - b + b"), &diff, &b)?;
+            chip.assert_equal(layouter.namespace(|| "== a"), &back, &a)?;
+
+            let n = chip.num_limbs();
+            for (i, value) in [&sum, &diff, &prod, &inv].into_iter().enumerate() {
+                chip.expose_public(layouter.namespace(|| "out"), value, i * n)?;
+            }
+            Ok(())
+        }
+    }
+
+    fn expected(a: &Limbs, b: &Limbs, p: &Limbs) -> Vec<Fr> {
+        let inv = mod_inv(a, p).unwrap_or_else(|| vec![0; p.len()]);
+        [mod_add(a, b, p), mod_sub(a, b, p), mod_mul(a, b, p), inv]
+            .into_iter()
+            .flat_map(|v| native::resize(&v, p.len()).unwrap())
+            .map(Fr::from)
+            .collect()
+    }
+
+    fn prove(modulus: &'static str, a: &Limbs, b: &Limbs, instance: Vec<Fr>) -> bool {
+        let circuit = FieldCircuit::<Fr> {
+            modulus,
+            a: Some(a.clone()),
+            b: Some(b.clone()),
+            _p: Default::default(),
+        };
+        let prover = MockProver::run(13, &circuit, vec![instance]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    fn random(rng: &mut StdRng, p: &Limbs) -> Limbs {
+        let x: Limbs = (0..p.len() + 1).map(|_| rng.gen()).collect();
+        native::div_rem(&x, p).unwrap().1
+    }
+
+    fn to_limbs(x: Fr) -> Limbs {
+        x.to_repr()
+            .as_ref()
+            .chunks(8)
+            .map(|chunk| u64::from_le_bytes(chunk.try_into().unwrap()))
+            .collect()
+    }
+
+    #[test]
+    pub fn test_native() {
+        // 用 bn256 的 Fr 对照
+        let p =
+            native::from_hex("30644e72e131a029b85045b68181585d2833e84879b9709143e1f593f0000001");
+        let mut rng = StdRng::seed_from_u64(50);
+        for _ in 0..10 {
+            let (x, y) = (Fr::random(&mut rng), Fr::random(&mut rng));
+            let (a, b) = (to_limbs(x), to_limbs(y));
+            assert_eq!(mod_add(&a, &b, &p), to_limbs(x + y));
+            assert_eq!(mod_sub(&a, &b, &p), to_limbs(x - y));
+            assert_eq!(mod_mul(&a, &b, &p), to_limbs(x * y));
+            assert_eq!(mod_inv(&a, &p).unwrap(), to_limbs(x.invert().unwrap()));
+        }
+        assert_eq!(mod_inv(&[0, 0, 0, 0], &p), None);
+    }
+
+    #[test]
+    pub fn test_non_native() {
+        let mut rng = StdRng::seed_from_u64(50);
+        for modulus in [SECP256K1_BASE, SECP256K1_SCALAR, BLS12_381_BASE] {
+            let p = native::from_hex(modulus);
+            let (a, b) = (random(&mut rng, &p), random(&mut rng, &p));
+            let instance = expected(&a, &b, &p);
+            assert!(prove(modulus, &a, &b, instance.clone()));
+            // 交换之后 a - b 要绕回
+            assert!(prove(modulus, &b, &a, expected(&b, &a, &p)));
+
+            // 每一段输出都改一个
+            for i in 0..4 {
+                let mut wrong = instance.clone();
+                wrong[i * p.len()] += Fr::one();
+                assert!(!prove(modulus, &a, &b, wrong));
+            }
+        }
+    }
+
+    #[test]
+    pub fn test_invalid() {
+        let p = native::from_hex(SECP256K1_BASE);
+        let one = native::resize(&[1], p.len()).unwrap();
+        // p - 1 是最大的元素
+        let max = native::sub(&p, &[1]).unwrap();
+        assert!(prove(SECP256K1_BASE, &max, &one, expected(&max, &one, &p)));
+        // p 本身不是规范的表示
+        assert!(!prove(SECP256K1_BASE, &p, &one, expected(&one, &one, &p)));
+        // 0 没有逆
+        let zero = vec![0; p.len()];
+        assert!(!prove(
+            SECP256K1_BASE,
+            &zero,
+            &one,
+            expected(&zero, &one, &p)
+        ));
+    }
+}
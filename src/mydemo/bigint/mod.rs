@@ -0,0 +1,3 @@
+pub(crate) mod chip;
+pub(crate) mod field;
+pub(crate) mod native;
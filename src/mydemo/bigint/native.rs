@@ -0,0 +1,153 @@
+use std::cmp::Ordering;
+
+// 大整数用小端序的 u64 limb 表示, 高位可以有多余的0
+pub const LIMB_BITS: usize = 64;
+
+pub type Limbs = Vec<u64>;
+
+// 16进制字符串, 可以带 0x 前缀
+pub fn from_hex(hex: &str) -> Limbs {
+    let hex = hex.trim_start_matches("0x");
+    let digits = hex.as_bytes();
+    digits
+        .rchunks(16)
+        .map(|chunk| u64::from_str_radix(std::str::from_utf8(chunk).unwrap(), 16).unwrap())
+        .collect()
+}
+
+// 补0到 n 个limb, 放不下时返回 None
+pub fn resize(a: &[u64], n: usize) -> Option<Limbs> {
+    if a.iter().skip(n).any(|limb| *limb != 0) {
+        return None;
+    }
+    let mut out = a[..a.len().min(n)].to_vec();
+    out.resize(n, 0);
+    Some(out)
+}
+
+pub fn is_zero(a: &[u64]) -> bool {
+    a.iter().all(|limb| *limb == 0)
+}
+
+pub fn cmp(a: &[u64], b: &[u64]) -> Ordering {
+    let n = a.len().max(b.len());
+    (0..n)
+        .rev()
+        .map(|i| {
+            let x = a.get(i).copied().unwrap_or(0);
+            let y = b.get(i).copied().unwrap_or(0);
+            x.cmp(&y)
+        })
+        .find(|o| *o != Ordering::Equal)
+        .unwrap_or(Ordering::Equal)
+}
+
+// 结果有 max(len) + 1 个limb
+pub fn add(a: &[u64], b: &[u64]) -> Limbs {
+    let n = a.len().max(b.len());
+    let mut out = Vec::with_capacity(n + 1);
+    let mut carry = 0u128;
+    for i in 0..n {
+        let sum =
+            a.get(i).copied().unwrap_or(0) as u128 + b.get(i).copied().unwrap_or(0) as u128 + carry;
+        out.push(sum as u64);
+        carry = sum >> LIMB_BITS;
+    }
+    out.push(carry as u64);
+    out
+}
+
+// a < b 时返回 None, 结果有 max(len) 个limb
+pub fn sub(a: &[u64], b: &[u64]) -> Option<Limbs> {
+    if cmp(a, b) == Ordering::Less {
+        return None;
+    }
+    let n = a.len().max(b.len());
+    let mut out = Vec::with_capacity(n);
+    let mut borrow = 0u64;
+    for i in 0..n {
+        let x = a.get(i).copied().unwrap_or(0);
+        let y = b.get(i).copied().unwrap_or(0);
+        let (d, b1) = x.overflowing_sub(y);
+        let (d, b2) = d.overflowing_sub(borrow);
+        out.push(d);
+        borrow = (b1 || b2) as u64;
+    }
+    Some(out)
+}
+
+// 结果有 a.len() + b.len() 个limb
+pub fn mul(a: &[u64], b: &[u64]) -> Limbs {
+    let mut out = vec![0u64; a.len() + b.len()];
+    for (i, x) in a.iter().enumerate() {
+        let mut carry = 0u128;
+        for (j, y) in b.iter().enumerate() {
+            let t = out[i + j] as u128 + *x as u128 * *y as u128 + carry;
+            out[i + j] = t as u64;
+            carry = t >> LIMB_BITS;
+        }
+        out[i + b.len()] = carry as u64;
+    }
+    out
+}
+
+fn bit(a: &[u64], i: usize) -> bool {
+    (a[i / LIMB_BITS] >> (i % LIMB_BITS)) & 1 == 1
+}
+
+// (q, r), q 有 a.len() 个limb, r 有 m.len() 个limb; m = 0 时返回 None
+// 逐位的长除法, 只用来生成 witness
+pub fn div_rem(a: &[u64], m: &[u64]) -> Option<(Limbs, Limbs)> {
+    if is_zero(m) {
+        return None;
+    }
+    let mut q = vec![0u64; a.len()];
+    // r < m, 多留一个limb放左移的结果
+    let mut r = vec![0u64; m.len() + 1];
+    for i in (0..a.len() * LIMB_BITS).rev() {
+        let mut carry = bit(a, i) as u64;
+        for limb in r.iter_mut() {
+            let next = *limb >> (LIMB_BITS - 1);
+            *limb = (*limb << 1) | carry;
+            carry = next;
+        }
+        if cmp(&r, m) != Ordering::Less {
+            r = sub(&r, m).unwrap();
+            q[i / LIMB_BITS] |= 1 << (i % LIMB_BITS);
+        }
+    }
+    Some((q, resize(&r, m.len()).unwrap()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mydemo::bigint::native::{add, cmp, div_rem, from_hex, mul, resize, sub};
+    use std::cmp::Ordering;
+
+    #[test]
+    pub fn test_small() {
+        let a = [u64::MAX, u64::MAX];
+        let b = [1u64];
+        assert_eq!(add(&a, &b), vec![0, 0, 1]);
+        assert_eq!(sub(&add(&a, &b), &b).unwrap(), vec![u64::MAX, u64::MAX, 0]);
+        assert_eq!(sub(&b, &a), None);
+        // (2^128 - 1)^2 = 2^256 - 2^129 + 1
+        assert_eq!(mul(&a, &a), vec![1, 0, u64::MAX - 1, u64::MAX]);
+        assert_eq!(cmp(&[5, 0, 0], &[5]), Ordering::Equal);
+        assert_eq!(cmp(&[0, 1], &[u64::MAX]), Ordering::Greater);
+        assert_eq!(resize(&[1, 2, 0], 2), Some(vec![1, 2]));
+        assert_eq!(resize(&[1, 2], 1), None);
+    }
+
+    #[test]
+    pub fn test_div_rem() {
+        let a = from_hex("0x123456789abcdef0fedcba9876543210deadbeef");
+        let m = from_hex("fffffffffffffffffffffffffffffffefffffc2f");
+        let (q, r) = div_rem(&a, &m).unwrap();
+        assert_eq!(cmp(&r, &m), Ordering::Less);
+        assert_eq!(cmp(&add(&mul(&q, &m), &r), &a), Ordering::Equal);
+        assert_eq!(div_rem(&[100], &[7]), Some((vec![14], vec![2])));
+        assert_eq!(div_rem(&a, &[0, 0]), None);
+        assert_eq!(from_hex("0x10000000000000000"), vec![0, 1]);
+    }
+}
@@ -0,0 +1,485 @@
+use crate::mydemo::boolean::AssignedBool;
+use crate::mydemo::main_gate::Term;
+use halo2_proofs::arithmetic::FieldExt;
+use halo2_proofs::circuit::{AssignedCell, Layouter};
+use halo2_proofs::plonk::{
+    Advice, Column, ConstraintSystem, Error, Expression, Fixed, Instance, Selector,
+};
+use halo2_proofs::poly::Rotation;
+use std::marker::PhantomData;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Endianness {
+    Little,
+    Big,
+}
+
+// 从高位到低位, 每行一个bit:
+// bit | acc | eq | modulus_bit | s_bits | s_canonical
+// s_bits: bit * (1 - bit) = 0, acc_next = 2 * acc + bit
+// 第一行 acc = 0, 最后一行的 acc 就是 value
+//
+// s_canonical: 检查 bits <= p - 1, modulus_bit 是 p - 1 的bit, eq 表示前面的高位都和 p - 1 相同
+// modulus_bit = 0 时 eq * bit = 0, eq_next = eq
+// modulus_bit = 1 时 eq_next = eq * bit
+// 第一行 eq = 1
+#[derive(Clone, Debug)]
+pub struct BitsConfig {
+    pub bit: Column<Advice>,
+    pub acc: Column<Advice>,
+    pub eq: Column<Advice>,
+    pub modulus_bit: Column<Fixed>,
+    pub constant: Column<Fixed>,
+    pub s_bits: Selector,
+    pub s_canonical: Selector,
+    pub instance: Column<Instance>,
+}
+
+// 分配好的bit和它们组合出来的值
+type AssignedBits<F> = (Vec<AssignedBool<F>>, AssignedCell<F, F>);
+
+pub struct BitsChip<F: FieldExt> {
+    config: BitsConfig,
+    _p: PhantomData<F>,
+}
+
+// 小端序的前 num_bits 个bit
+fn to_bits<F: FieldExt>(value: &F, num_bits: usize) -> Vec<bool> {
+    let repr = value.to_repr();
+    let bytes = repr.as_ref();
+    (0..num_bits)
+        .map(|i| i < 8 * bytes.len() && (bytes[i / 8] >> (i % 8)) & 1 == 1)
+        .collect()
+}
+
+impl<F: FieldExt> BitsChip<F> {
+    pub fn construct(config: BitsConfig) -> Self {
+        Self {
+            config,
+            _p: Default::default(),
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> BitsConfig {
+        let bit = meta.advice_column();
+        let acc = meta.advice_column();
+        let eq = meta.advice_column();
+        let modulus_bit = meta.fixed_column();
+        let constant = meta.fixed_column();
+        let s_bits = meta.selector();
+        let s_canonical = meta.selector();
+        let instance = meta.instance_column();
+
+        for column in [bit, acc, eq] {
+            meta.enable_equality(column);
+        }
+        meta.enable_equality(instance);
+        meta.enable_constant(constant);
+
+        let one = Expression::Constant(F::one());
+        meta.create_gate("bits", |meta| {
+            let s = meta.query_selector(s_bits);
+            let b = meta.query_advice(bit, Rotation::cur());
+            let acc_next = meta.query_advice(acc, Rotation::next());
+            let acc = meta.query_advice(acc, Rotation::cur());
+            vec![
+                s.clone() * b.clone() * (one.clone() - b.clone()),
+                s * (acc * F::from(2u64) + b - acc_next),
+            ]
+        });
+
+        meta.create_gate("canonical", |meta| {
+            let s = meta.query_selector(s_canonical);
+            let bit = meta.query_advice(bit, Rotation::cur());
+            let eq_next = meta.query_advice(eq, Rotation::next());
+            let eq = meta.query_advice(eq, Rotation::cur());
+            let m = meta.query_fixed(modulus_bit, Rotation::cur());
+            vec![
+                s.clone() * (one.clone() - m.clone()) * eq.clone() * bit.clone(),
+                s * (eq * (m.clone() * bit + one - m) - eq_next),
+            ]
+        });
+
+        BitsConfig {
+            bit,
+            acc,
+            eq,
+            modulus_bit,
+            constant,
+            s_bits,
+            s_canonical,
+            instance,
+        }
+    }
+
+    pub fn load_private(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Option<F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        layouter.assign_region(
+            || "load private",
+            |mut region| {
+                region.assign_advice(
+                    || "private",
+                    self.config.acc,
+                    0,
+                    || value.ok_or(Error::Synthesis),
+                )
+            },
+        )
+    }
+
+    // bits 是从高位到低位, value 是它们组合出来的值
+    // decompose 时 value 已经分配, bits 是新的; recompose 时反过来
+    fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        bits: &[Term<'_, F>],
+        value: Term<'_, F>,
+        canonical: bool,
+    ) -> Result<AssignedBits<F>, Error> {
+        let modulus = to_bits(&-F::one(), bits.len());
+        layouter.assign_region(
+            || "bits",
+            |mut region| {
+                let config = &self.config;
+                let mut acc = Some(F::zero());
+                let mut eq = Some(true);
+                region.assign_advice_from_constant(|| "acc", config.acc, 0, F::zero())?;
+                if canonical {
+                    region.assign_advice_from_constant(|| "eq", config.eq, 0, F::one())?;
+                }
+
+                let mut assigned = Vec::with_capacity(bits.len());
+                for (row, bit) in bits.iter().enumerate() {
+                    config.s_bits.enable(&mut region, row)?;
+                    let cell = match bit {
+                        Term::Assigned(cell) => {
+                            cell.copy_advice(|| "bit", &mut region, config.bit, row)?
+                        }
+                        Term::Unassigned(value) => region.assign_advice(
+                            || "bit",
+                            config.bit,
+                            row,
+                            || value.ok_or(Error::Synthesis),
+                        )?,
+                    };
+                    let b = bit.value();
+                    acc = acc.and_then(|acc| b.map(|b| acc.double() + b));
+
+                    // 最后一行的 acc 单独处理
+                    if row + 1 < bits.len() {
+                        region.assign_advice(
+                            || "acc",
+                            config.acc,
+                            row + 1,
+                            || acc.ok_or(Error::Synthesis),
+                        )?;
+                    }
+                    if canonical {
+                        let m = modulus[bits.len() - 1 - row];
+                        config.s_canonical.enable(&mut region, row)?;
+                        region.assign_fixed(
+                            || "modulus bit",
+                            config.modulus_bit,
+                            row,
+                            || Ok(F::from(m as u64)),
+                        )?;
+                        eq = eq.and_then(|eq| b.map(|b| eq && (!m || b == F::one())));
+                        region.assign_advice(
+                            || "eq",
+                            config.eq,
+                            row + 1,
+                            || eq.map(|eq| F::from(eq as u64)).ok_or(Error::Synthesis),
+                        )?;
+                    }
+                    assigned.push(AssignedBool(cell));
+                }
+
+                let last = bits.len();
+                let value = match value {
+                    Term::Assigned(cell) => {
+                        cell.copy_advice(|| "value", &mut region, config.acc, last)?
+                    }
+                    Term::Unassigned(_) => region.assign_advice(
+                        || "value",
+                        config.acc,
+                        last,
+                        || acc.ok_or(Error::Synthesis),
+                    )?,
+                };
+                Ok((assigned, value))
+            },
+        )
+    }
+
+    fn order(mut bits: Vec<AssignedBool<F>>, endianness: Endianness) -> Vec<AssignedBool<F>> {
+        if endianness == Endianness::Little {
+            bits.reverse();
+        }
+        bits
+    }
+
+    // value 分解成 num_bits 个bit, value >= 2^num_bits 时约束不满足
+    // num_bits 要小于 F::NUM_BITS, 否则分解不唯一, 这时用 decompose_canonical
+    pub fn decompose(
+        &self,
+        layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+        num_bits: usize,
+        endianness: Endianness,
+    ) -> Result<Vec<AssignedBool<F>>, Error> {
+        assert!(num_bits > 0 && num_bits < F::NUM_BITS as usize);
+        self.decompose_inner(layouter, value, num_bits, false)
+            .map(|bits| Self::order(bits, endianness))
+    }
+
+    // 分解成 F::NUM_BITS 个bit, 并且检查 bits < p, 所以结果是唯一的
+    pub fn decompose_canonical(
+        &self,
+        layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+        endianness: Endianness,
+    ) -> Result<Vec<AssignedBool<F>>, Error> {
+        self.decompose_inner(layouter, value, F::NUM_BITS as usize, true)
+            .map(|bits| Self::order(bits, endianness))
+    }
+
+    fn decompose_inner(
+        &self,
+        layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+        num_bits: usize,
+        canonical: bool,
+    ) -> Result<Vec<AssignedBool<F>>, Error> {
+        let le = value.value().map(|v| to_bits(v, num_bits));
+        let bits = (0..num_bits)
+            .rev()
+            .map(|i| Term::Unassigned(le.as_ref().map(|le| F::from(le[i] as u64))))
+            .collect::<Vec<_>>();
+        self.assign(layouter, &bits, Term::Assigned(value), canonical)
+            .map(|(bits, _)| bits)
+    }
+
+    // 把bit组合成一个域元素, bits 的个数 >= F::NUM_BITS 时结果会模 p
+    pub fn recompose(
+        &self,
+        layouter: impl Layouter<F>,
+        bits: &[AssignedBool<F>],
+        endianness: Endianness,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        assert!(!bits.is_empty());
+        let mut bits = bits
+            .iter()
+            .map(|b| Term::Assigned(&b.0))
+            .collect::<Vec<_>>();
+        if endianness == Endianness::Little {
+            bits.reverse();
+        }
+        self.assign(layouter, &bits, Term::Unassigned(None), false)
+            .map(|(_, value)| value)
+    }
+
+    pub fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: &AssignedCell<F, F>,
+        row: usize,
+    ) -> Result<(), Error> {
+        layouter.constrain_instance(cell.cell(), self.config.instance, row)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mydemo::bits::{to_bits, BitsChip, BitsConfig, Endianness};
+    use crate::mydemo::main_gate::Term;
+    use halo2_proofs::arithmetic::FieldExt;
+    use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner};
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::pairing::bn256::Fr;
+    use halo2_proofs::pairing::group::ff::PrimeField;
+    use halo2_proofs::plonk::{Circuit, ConstraintSystem, Error};
+
+    // 分解 value, 暴露所有bit, 再把bit反过来组合, 暴露组合的结果
+    pub struct DecomposeCircuit<F: FieldExt> {
+        value: Option<F>,
+        num_bits: usize,
+        endianness: Endianness,
+    }
+
+    impl<F: FieldExt> Circuit<F> for DecomposeCircuit<F> {
+        type Config = BitsConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                value: None,
+                num_bits: self.num_bits,
+                endianness: self.endianness,
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            BitsChip::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let chip = BitsChip::construct(config);
+            let value = chip.load_private(layouter.namespace(|| "value"), self.value)?;
+            let bits = if self.num_bits == F::NUM_BITS as usize {
+                chip.decompose_canonical(layouter.namespace(|| "bits"), &value, self.endianness)?
+            } else {
+                chip.decompose(
+                    layouter.namespace(|| "bits"),
+                    &value,
+                    self.num_bits,
+                    self.endianness,
+                )?
+            };
+            for (i, bit) in bits.iter().enumerate() {
+                chip.expose_public(layouter.namespace(|| "bit"), &bit.0, i)?;
+            }
+            // 按相反的顺序组合, 得到的是bit翻转后的值
+            let reversed = match self.endianness {
+                Endianness::Little => Endianness::Big,
+                Endianness::Big => Endianness::Little,
+            };
+            let value = chip.recompose(layouter.namespace(|| "recompose"), &bits, reversed)?;
+            chip.expose_public(layouter.namespace(|| "reversed"), &value, bits.len())
+        }
+    }
+
+    fn prove(value: Fr, num_bits: usize, endianness: Endianness, instance: Vec<Fr>) -> bool {
+        let circuit = DecomposeCircuit {
+            value: Some(value),
+            num_bits,
+            endianness,
+        };
+        let prover = MockProver::run(10, &circuit, vec![instance]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    fn bits_instance(bits: &[u64], reversed: u64) -> Vec<Fr> {
+        bits.iter()
+            .chain([reversed].iter())
+            .map(|b| Fr::from(*b))
+            .collect()
+    }
+
+    #[test]
+    pub fn test_decompose() {
+        // 0b1101 = 13, 翻转之后是 0b1011 = 11
+        let value = Fr::from(13u64);
+        let le = bits_instance(&[1, 0, 1, 1], 11);
+        let be = bits_instance(&[1, 1, 0, 1], 11);
+        assert!(prove(value, 4, Endianness::Little, le.clone()));
+        assert!(prove(value, 4, Endianness::Big, be.clone()));
+        assert!(!prove(value, 4, Endianness::Little, be));
+        assert!(!prove(value, 4, Endianness::Big, le));
+
+        // 16 放不进4个bit
+        assert!(!prove(
+            Fr::from(16u64),
+            4,
+            Endianness::Little,
+            bits_instance(&[0, 0, 0, 0], 0)
+        ));
+    }
+
+    #[test]
+    pub fn test_decompose_canonical() {
+        let num_bits = Fr::NUM_BITS as usize;
+        for value in [Fr::zero(), Fr::from(0xdead_beefu64), -Fr::one()] {
+            let le = to_bits(&value, num_bits);
+            // 翻转后组合的值 (模 p)
+            let reversed = le
+                .iter()
+                .fold(Fr::zero(), |acc, b| acc.double() + Fr::from(*b as u64));
+            let mut instance = le.iter().map(|b| Fr::from(*b as u64)).collect::<Vec<_>>();
+            instance.push(reversed);
+            assert!(prove(value, num_bits, Endianness::Little, instance.clone()));
+
+            instance[0] += Fr::one();
+            assert!(!prove(value, num_bits, Endianness::Little, instance));
+        }
+    }
+
+    // 直接用 p + 1 的bit组合出 1, 只有 canonical 检查能发现
+    pub struct ForgeCircuit {
+        bits: Vec<bool>,
+        canonical: bool,
+    }
+
+    impl Circuit<Fr> for ForgeCircuit {
+        type Config = BitsConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                bits: self.bits.clone(),
+                canonical: self.canonical,
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            BitsChip::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let chip = BitsChip::construct(config);
+            let one = chip.load_private(layouter.namespace(|| "one"), Some(Fr::one()))?;
+            let bits = self
+                .bits
+                .iter()
+                .rev()
+                .map(|b| Term::Unassigned(Some(Fr::from(*b as u64))))
+                .collect::<Vec<_>>();
+            chip.assign(
+                layouter.namespace(|| "forge"),
+                &bits,
+                Term::Assigned(&one),
+                self.canonical,
+            )?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    pub fn test_non_canonical() {
+        // p - 1 加 2 得到 p + 1
+        let mut bytes = (-Fr::one()).to_repr();
+        let mut carry = 2u16;
+        for byte in bytes.iter_mut() {
+            let sum = *byte as u16 + carry;
+            *byte = sum as u8;
+            carry = sum >> 8;
+        }
+        let bits = (0..Fr::NUM_BITS as usize)
+            .map(|i| (bytes[i / 8] >> (i % 8)) & 1 == 1)
+            .collect::<Vec<_>>();
+
+        let circuit = ForgeCircuit {
+            bits: bits.clone(),
+            canonical: false,
+        };
+        let prover = MockProver::run(9, &circuit, vec![vec![]]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        let circuit = ForgeCircuit {
+            bits,
+            canonical: true,
+        };
+        let prover = MockProver::run(9, &circuit, vec![vec![]]).unwrap();
+        assert_ne!(prover.verify(), Ok(()));
+    }
+}
@@ -0,0 +1,230 @@
+use crate::mydemo::blake2s::native::{
+    counter_words, initial_state, padding, BLOCK_BYTES, IV, MIX, ROTATIONS, SIGMA,
+};
+use crate::mydemo::word::{AssignedWord, CsByteHasher, U32Chip, WordConfig};
+use halo2_proofs::arithmetic::FieldExt;
+use halo2_proofs::circuit::Layouter;
+use halo2_proofs::plonk::{ConstraintSystem, Error};
+
+// BLAKE2s-256, 32位的加法, 异或, 循环右移都由 U32Chip 完成
+// 消息长度在生成电路时确定, 所以计数器和最后一个block的标记都是常量
+pub struct Blake2sChip<F: FieldExt> {
+    word: U32Chip<F>,
+}
+
+impl<F: FieldExt> Blake2sChip<F> {
+    pub fn construct(config: WordConfig<F>) -> Self {
+        Self {
+            word: U32Chip::construct(config),
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> WordConfig<F> {
+        U32Chip::configure(meta)
+    }
+
+    pub fn load_tables(&self, layouter: impl Layouter<F>) -> Result<(), Error> {
+        self.word.load_tables(layouter)
+    }
+
+    fn g(
+        &self,
+        mut layouter: impl Layouter<F>,
+        v: &mut [AssignedWord<F>],
+        [a, b, c, d]: [usize; 4],
+        x: &AssignedWord<F>,
+        y: &AssignedWord<F>,
+    ) -> Result<(), Error> {
+        let word = &self.word;
+        for (i, m) in [x, y].into_iter().enumerate() {
+            v[a] = word.add(layouter.namespace(|| "a"), &[&v[a], &v[b], m])?;
+            let da = word.xor(layouter.namespace(|| "d ^ a"), &v[d], &v[a])?;
+            v[d] = word.rotr(layouter.namespace(|| "d"), &da, ROTATIONS[2 * i])?;
+            v[c] = word.add(layouter.namespace(|| "c"), &[&v[c], &v[d]])?;
+            let bc = word.xor(layouter.namespace(|| "b ^ c"), &v[b], &v[c])?;
+            v[b] = word.rotr(layouter.namespace(|| "b"), &bc, ROTATIONS[2 * i + 1])?;
+        }
+        Ok(())
+    }
+
+    pub fn compress(
+        &self,
+        mut layouter: impl Layouter<F>,
+        h: &[AssignedWord<F>],
+        m: &[AssignedWord<F>],
+        counter: u64,
+        last: bool,
+    ) -> Result<Vec<AssignedWord<F>>, Error> {
+        let mut v = h.to_vec();
+        for iv in IV[..4].iter().chain(counter_words(counter, last).iter()) {
+            v.push(
+                self.word
+                    .load_constant(layouter.namespace(|| "v"), *iv as u64)?,
+            );
+        }
+        for (r, s) in SIGMA.iter().enumerate() {
+            for (i, mix) in MIX.iter().enumerate() {
+                self.g(
+                    layouter.namespace(|| format!("round {} G{}", r, i)),
+                    &mut v,
+                    *mix,
+                    &m[s[2 * i]],
+                    &m[s[2 * i + 1]],
+                )?;
+            }
+        }
+        (0..8)
+            .map(|i| {
+                let x = self
+                    .word
+                    .xor(layouter.namespace(|| "v ^ v"), &v[i], &v[i + 8])?;
+                self.word.xor(layouter.namespace(|| "h"), &h[i], &x)
+            })
+            .collect()
+    }
+
+    // 返回8个word, 每个word的 bytes 按顺序拼起来就是32 byte 的 digest
+    pub fn digest(
+        &self,
+        mut layouter: impl Layouter<F>,
+        message: &[Option<u8>],
+    ) -> Result<Vec<AssignedWord<F>>, Error> {
+        let word = &self.word;
+        let mut bytes = word.load_bytes(layouter.namespace(|| "message"), message)?;
+        bytes.extend(
+            word.load_constant_bytes(layouter.namespace(|| "padding"), &padding(message.len()))?,
+        );
+        let blocks = bytes.len() / BLOCK_BYTES;
+
+        let mut h = initial_state()
+            .iter()
+            .map(|h| word.load_constant(layouter.namespace(|| "h"), *h as u64))
+            .collect::<Result<Vec<_>, Error>>()?;
+        for (i, block) in bytes.chunks(BLOCK_BYTES).enumerate() {
+            let mut layouter = layouter.namespace(|| format!("block {}", i));
+            let m = block
+                .chunks(4)
+                .map(|b| word.compose_bytes(layouter.namespace(|| "m"), b))
+                .collect::<Result<Vec<_>, Error>>()?;
+            let last = i + 1 == blocks;
+            let counter = if last {
+                message.len()
+            } else {
+                (i + 1) * BLOCK_BYTES
+            };
+            h = self.compress(
+                layouter.namespace(|| "compress"),
+                &h,
+                &m,
+                counter as u64,
+                last,
+            )?;
+        }
+        Ok(h)
+    }
+}
+
+impl<F: FieldExt> CsByteHasher<F> for Blake2sChip<F> {
+    fn configure(meta: &mut ConstraintSystem<F>) -> WordConfig<F> {
+        Blake2sChip::configure(meta)
+    }
+
+    fn construct(config: WordConfig<F>) -> Self {
+        Blake2sChip::construct(config)
+    }
+
+    fn load_tables(&self, layouter: impl Layouter<F>) -> Result<(), Error> {
+        Blake2sChip::load_tables(self, layouter)
+    }
+
+    fn digest(
+        &self,
+        layouter: impl Layouter<F>,
+        message: &[Option<u8>],
+    ) -> Result<Vec<AssignedWord<F>>, Error> {
+        Blake2sChip::digest(self, layouter, message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mydemo::blake2s::chip::Blake2sChip;
+    use crate::mydemo::blake2s::native::blake2s;
+    use crate::mydemo::word::WordConfig;
+    use halo2_proofs::arithmetic::FieldExt;
+    use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner};
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::pairing::bn256::Fr;
+    use halo2_proofs::plonk::{Circuit, Column, ConstraintSystem, Error, Instance};
+
+    #[derive(Clone, Debug)]
+    pub struct Blake2sConfig<F: FieldExt> {
+        word: WordConfig<F>,
+        instance: Column<Instance>,
+    }
+
+    pub struct Blake2sCircuit {
+        message: Vec<Option<u8>>,
+    }
+
+    impl<F: FieldExt> Circuit<F> for Blake2sCircuit {
+        type Config = Blake2sConfig<F>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                message: vec![None; self.message.len()],
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            Blake2sConfig {
+                word: Blake2sChip::configure(meta),
+                instance,
+            }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let chip = Blake2sChip::construct(config.word);
+            chip.load_tables(layouter.namespace(|| "tables"))?;
+            let digest = chip.digest(layouter.namespace(|| "blake2s"), &self.message)?;
+            for (i, word) in digest.iter().enumerate() {
+                layouter.constrain_instance(word.value.cell(), config.instance, i)?;
+            }
+            Ok(())
+        }
+    }
+
+    fn prove(message: &[u8], digest: [u8; 32]) -> bool {
+        let circuit = Blake2sCircuit {
+            message: message.iter().map(|b| Some(*b)).collect(),
+        };
+        let instance = digest
+            .chunks(4)
+            .map(|w| Fr::from(u32::from_le_bytes(w.try_into().unwrap()) as u64))
+            .collect();
+        let prover = MockProver::run(17, &circuit, vec![instance]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    pub fn test_blake2s() {
+        let long: Vec<u8> = (0..200u32).map(|i| (i * 7 + 3) as u8).collect();
+        for message in [b"".to_vec(), b"abc".to_vec(), long] {
+            assert!(prove(&message, blake2s(&message)), "len {}", message.len());
+        }
+    }
+
+    #[test]
+    pub fn test_wrong_digest() {
+        let mut digest = blake2s(b"abc");
+        digest[0] ^= 1;
+        assert!(!prove(b"abc", digest));
+    }
+}
@@ -0,0 +1,152 @@
+// BLAKE2s-256 (RFC 7693), 不带 key
+pub const IV: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+pub const SIGMA: [[usize; 16]; 10] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+    [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
+    [11, 8, 12, 0, 5, 2, 15, 13, 10, 14, 3, 6, 7, 1, 9, 4],
+    [7, 9, 3, 1, 13, 12, 11, 14, 2, 6, 5, 10, 4, 0, 15, 8],
+    [9, 0, 5, 7, 2, 4, 10, 15, 14, 1, 11, 12, 6, 8, 3, 13],
+    [2, 12, 6, 10, 0, 11, 8, 3, 4, 13, 7, 5, 15, 14, 1, 9],
+    [12, 5, 1, 15, 14, 13, 4, 10, 0, 7, 6, 3, 9, 2, 8, 11],
+    [13, 11, 7, 14, 12, 1, 3, 9, 5, 0, 15, 4, 8, 6, 2, 10],
+    [6, 15, 14, 9, 11, 3, 0, 8, 12, 2, 13, 7, 1, 4, 10, 5],
+    [10, 2, 8, 4, 7, 6, 1, 5, 15, 11, 9, 14, 3, 12, 13, 0],
+];
+
+// 每一轮的8次 G 作用在 v 的哪4个位置上, 前4个是列, 后4个是对角线
+pub const MIX: [[usize; 4]; 8] = [
+    [0, 4, 8, 12],
+    [1, 5, 9, 13],
+    [2, 6, 10, 14],
+    [3, 7, 11, 15],
+    [0, 5, 10, 15],
+    [1, 6, 11, 12],
+    [2, 7, 8, 13],
+    [3, 4, 9, 14],
+];
+
+// G 里面的四次循环右移
+pub const ROTATIONS: [usize; 4] = [16, 12, 8, 7];
+
+pub const BLOCK_BYTES: usize = 64;
+pub const OUT_BYTES: usize = 32;
+
+// 参数块只有第一个word不是0: digest长度 32, key长度 0, fanout 1, depth 1
+pub fn initial_state() -> [u32; 8] {
+    let mut h = IV;
+    h[0] ^= 0x0101_0000 ^ OUT_BYTES as u32;
+    h
+}
+
+// 最后一个block补0, 空消息也有一个block
+pub fn padding(len: usize) -> Vec<u8> {
+    let blocks = len.div_ceil(BLOCK_BYTES).max(1);
+    vec![0u8; blocks * BLOCK_BYTES - len]
+}
+
+// v[12..16] 的初始值: 计数器是到这个block为止的消息长度, 最后一个block的 f0 全为1
+pub fn counter_words(counter: u64, last: bool) -> [u32; 4] {
+    [
+        IV[4] ^ counter as u32,
+        IV[5] ^ (counter >> 32) as u32,
+        IV[6] ^ if last { u32::MAX } else { 0 },
+        IV[7],
+    ]
+}
+
+fn g(v: &mut [u32; 16], [a, b, c, d]: [usize; 4], x: u32, y: u32) {
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(x);
+    v[d] = (v[d] ^ v[a]).rotate_right(ROTATIONS[0] as u32);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(ROTATIONS[1] as u32);
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(y);
+    v[d] = (v[d] ^ v[a]).rotate_right(ROTATIONS[2] as u32);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(ROTATIONS[3] as u32);
+}
+
+pub fn compress(h: [u32; 8], block: &[u8], counter: u64, last: bool) -> [u32; 8] {
+    let m: Vec<u32> = block
+        .chunks(4)
+        .map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+        .collect();
+    let mut v = [0u32; 16];
+    v[..8].copy_from_slice(&h);
+    v[8..12].copy_from_slice(&IV[..4]);
+    v[12..].copy_from_slice(&counter_words(counter, last));
+    for s in SIGMA {
+        for (i, mix) in MIX.iter().enumerate() {
+            g(&mut v, *mix, m[s[2 * i]], m[s[2 * i + 1]]);
+        }
+    }
+    let mut out = h;
+    for i in 0..8 {
+        out[i] ^= v[i] ^ v[i + 8];
+    }
+    out
+}
+
+pub fn blake2s(message: &[u8]) -> [u8; 32] {
+    let mut padded = message.to_vec();
+    padded.extend(padding(message.len()));
+    let blocks = padded.len() / BLOCK_BYTES;
+
+    let mut h = initial_state();
+    for (i, block) in padded.chunks(BLOCK_BYTES).enumerate() {
+        let last = i + 1 == blocks;
+        let counter = if last {
+            message.len()
+        } else {
+            (i + 1) * BLOCK_BYTES
+        };
+        h = compress(h, block, counter as u64, last);
+    }
+
+    let mut digest = [0u8; 32];
+    for (i, word) in h.iter().enumerate() {
+        digest[4 * i..4 * (i + 1)].copy_from_slice(&word.to_le_bytes());
+    }
+    digest
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mydemo::blake2s::native::blake2s;
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    pub fn test_blake2s() {
+        let block: Vec<u8> = (0..136u32).map(|i| i as u8).collect();
+        let long: Vec<u8> = (0..200u32).map(|i| (i * 7 + 3) as u8).collect();
+        let vectors = [
+            (
+                b"".to_vec(),
+                "69217a3079908094e11121d042354a7c1f55b6482ca1a51e1b250dfd1ed0eef9",
+            ),
+            (
+                b"abc".to_vec(),
+                "508c5e8c327c14e2e1a72ba34eeb452f37458b209ed63a294d999b4c86675982",
+            ),
+            (
+                block,
+                "67de25c02a4aaba23bdc973c8bb0b5796d47cc0659d43dff1f97de174963b68e",
+            ),
+            (
+                long,
+                "46c215f5ea995be7077ae5b9e0ef1c28b2111fc213d0867ddc8f1b339362f5fc",
+            ),
+        ];
+        for (message, digest) in vectors {
+            assert_eq!(blake2s(&message).to_vec(), hex(digest));
+        }
+    }
+}
@@ -0,0 +1,404 @@
+use halo2_proofs::arithmetic::FieldExt;
+use halo2_proofs::circuit::{AssignedCell, Layouter};
+use halo2_proofs::plonk::{
+    Advice, Column, ConstraintSystem, Error, Expression, Fixed, Instance, Selector,
+};
+use halo2_proofs::poly::Rotation;
+use std::marker::PhantomData;
+
+// 值一定是0或者1的cell, 只能由 BooleanChip 产生
+#[derive(Clone, Debug)]
+pub struct AssignedBool<F: FieldExt>(pub AssignedCell<F, F>);
+
+impl<F: FieldExt> AssignedBool<F> {
+    pub fn value(&self) -> Option<bool> {
+        self.0.value().map(|v| *v == F::one())
+    }
+}
+
+// 逻辑运算, 输入都已经是bool, 所以输出也一定是bool:
+// a | b | out | s_bool | s_and | s_or | s_xor | s_not | s_nand
+// bool: a * (1 - a) = 0
+// and:  out = a * b
+// or:   out = a + b - a * b
+// xor:  out = a + b - 2 * a * b
+// not:  out = 1 - a
+// nand: out = 1 - a * b
+// out 关于 a, b 的表达式
+type LogicExpr<F> = fn(Expression<F>, Expression<F>) -> Expression<F>;
+
+#[derive(Clone, Debug)]
+pub struct BooleanConfig {
+    pub a: Column<Advice>,
+    pub b: Column<Advice>,
+    pub out: Column<Advice>,
+    pub constant: Column<Fixed>,
+    pub s_bool: Selector,
+    pub s_and: Selector,
+    pub s_or: Selector,
+    pub s_xor: Selector,
+    pub s_not: Selector,
+    pub s_nand: Selector,
+    pub instance: Column<Instance>,
+}
+
+pub struct BooleanChip<F: FieldExt> {
+    config: BooleanConfig,
+    _p: PhantomData<F>,
+}
+
+impl<F: FieldExt> BooleanChip<F> {
+    pub fn construct(config: BooleanConfig) -> Self {
+        Self {
+            config,
+            _p: Default::default(),
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> BooleanConfig {
+        let a = meta.advice_column();
+        let b = meta.advice_column();
+        let out = meta.advice_column();
+        let constant = meta.fixed_column();
+        let instance = meta.instance_column();
+        let [s_bool, s_and, s_or, s_xor, s_not, s_nand] = [(); 6].map(|_| meta.selector());
+
+        for column in [a, b, out] {
+            meta.enable_equality(column);
+        }
+        meta.enable_equality(instance);
+        meta.enable_constant(constant);
+
+        meta.create_gate("bool", |meta| {
+            let s = meta.query_selector(s_bool);
+            let a = meta.query_advice(a, Rotation::cur());
+            vec![s * a.clone() * (Expression::Constant(F::one()) - a)]
+        });
+
+        // 每种运算一个selector, f(a, b) 是 out 的表达式
+        let ops: [(&'static str, Selector, LogicExpr<F>); 5] = [
+            ("and", s_and, |a, b| a * b),
+            ("or", s_or, |a, b| a.clone() + b.clone() - a * b),
+            ("xor", s_xor, |a, b| {
+                a.clone() + b.clone() - Expression::Constant(F::from(2u64)) * a * b
+            }),
+            ("not", s_not, |a, _| Expression::Constant(F::one()) - a),
+            ("nand", s_nand, |a, b| {
+                Expression::Constant(F::one()) - a * b
+            }),
+        ];
+        for (name, selector, f) in ops {
+            meta.create_gate(name, |meta| {
+                let s = meta.query_selector(selector);
+                let a = meta.query_advice(a, Rotation::cur());
+                let b = meta.query_advice(b, Rotation::cur());
+                let out = meta.query_advice(out, Rotation::cur());
+                vec![s * (out - f(a, b))]
+            });
+        }
+
+        BooleanConfig {
+            a,
+            b,
+            out,
+            constant,
+            s_bool,
+            s_and,
+            s_or,
+            s_xor,
+            s_not,
+            s_nand,
+            instance,
+        }
+    }
+
+    pub fn load_private(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Option<bool>,
+    ) -> Result<AssignedBool<F>, Error> {
+        layouter.assign_region(
+            || "load bool",
+            |mut region| {
+                self.config.s_bool.enable(&mut region, 0)?;
+                region
+                    .assign_advice(
+                        || "bool",
+                        self.config.a,
+                        0,
+                        || value.map(|v| F::from(v as u64)).ok_or(Error::Synthesis),
+                    )
+                    .map(AssignedBool)
+            },
+        )
+    }
+
+    pub fn load_constant(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: bool,
+    ) -> Result<AssignedBool<F>, Error> {
+        layouter.assign_region(
+            || "constant bool",
+            |mut region| {
+                region
+                    .assign_advice_from_constant(|| "bool", self.config.a, 0, F::from(value as u64))
+                    .map(AssignedBool)
+            },
+        )
+    }
+
+    // 约束一个已有的cell是0或者1
+    pub fn assert_boolean(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: &AssignedCell<F, F>,
+    ) -> Result<AssignedBool<F>, Error> {
+        layouter.assign_region(
+            || "assert bool",
+            |mut region| {
+                self.config.s_bool.enable(&mut region, 0)?;
+                cell.copy_advice(|| "bool", &mut region, self.config.a, 0)
+                    .map(AssignedBool)
+            },
+        )
+    }
+
+    fn binary(
+        &self,
+        mut layouter: impl Layouter<F>,
+        selector: Selector,
+        a: &AssignedBool<F>,
+        b: &AssignedBool<F>,
+        f: fn(bool, bool) -> bool,
+    ) -> Result<AssignedBool<F>, Error> {
+        layouter.assign_region(
+            || "logic",
+            |mut region| {
+                selector.enable(&mut region, 0)?;
+                a.0.copy_advice(|| "a", &mut region, self.config.a, 0)?;
+                b.0.copy_advice(|| "b", &mut region, self.config.b, 0)?;
+                let out = a.value().and_then(|a| b.value().map(|b| f(a, b)));
+                region
+                    .assign_advice(
+                        || "out",
+                        self.config.out,
+                        0,
+                        || out.map(|v| F::from(v as u64)).ok_or(Error::Synthesis),
+                    )
+                    .map(AssignedBool)
+            },
+        )
+    }
+
+    pub fn and(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedBool<F>,
+        b: &AssignedBool<F>,
+    ) -> Result<AssignedBool<F>, Error> {
+        self.binary(layouter, self.config.s_and, a, b, |a, b| a & b)
+    }
+
+    pub fn or(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedBool<F>,
+        b: &AssignedBool<F>,
+    ) -> Result<AssignedBool<F>, Error> {
+        self.binary(layouter, self.config.s_or, a, b, |a, b| a | b)
+    }
+
+    pub fn xor(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedBool<F>,
+        b: &AssignedBool<F>,
+    ) -> Result<AssignedBool<F>, Error> {
+        self.binary(layouter, self.config.s_xor, a, b, |a, b| a ^ b)
+    }
+
+    pub fn nand(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedBool<F>,
+        b: &AssignedBool<F>,
+    ) -> Result<AssignedBool<F>, Error> {
+        self.binary(layouter, self.config.s_nand, a, b, |a, b| !(a & b))
+    }
+
+    // b 这一列不参与约束, 也复制 a 进去, 避免出现没赋值的cell
+    pub fn not(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedBool<F>,
+    ) -> Result<AssignedBool<F>, Error> {
+        self.binary(layouter, self.config.s_not, a, a, |a, _| !a)
+    }
+
+    // 多个输入的 and / or, 两两依次计算
+    pub fn and_many(
+        &self,
+        mut layouter: impl Layouter<F>,
+        inputs: &[AssignedBool<F>],
+    ) -> Result<AssignedBool<F>, Error> {
+        assert!(!inputs.is_empty());
+        inputs[1..].iter().try_fold(inputs[0].clone(), |acc, x| {
+            self.and(layouter.namespace(|| "and"), &acc, x)
+        })
+    }
+
+    pub fn or_many(
+        &self,
+        mut layouter: impl Layouter<F>,
+        inputs: &[AssignedBool<F>],
+    ) -> Result<AssignedBool<F>, Error> {
+        assert!(!inputs.is_empty());
+        inputs[1..].iter().try_fold(inputs[0].clone(), |acc, x| {
+            self.or(layouter.namespace(|| "or"), &acc, x)
+        })
+    }
+
+    pub fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: &AssignedBool<F>,
+        row: usize,
+    ) -> Result<(), Error> {
+        layouter.constrain_instance(cell.0.cell(), self.config.instance, row)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mydemo::boolean::{BooleanChip, BooleanConfig};
+    use halo2_proofs::arithmetic::FieldExt;
+    use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner};
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::pairing::bn256::Fr;
+    use halo2_proofs::plonk::{Circuit, ConstraintSystem, Error};
+
+    // 暴露: a & b, a | b, a ^ b, !a, !(a & b), and(inputs), or(inputs)
+    #[derive(Default)]
+    pub struct LogicCircuit<F: FieldExt> {
+        a: Option<F>,
+        b: Option<bool>,
+        inputs: Vec<Option<bool>>,
+    }
+
+    impl<F: FieldExt> Circuit<F> for LogicCircuit<F> {
+        type Config = BooleanConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                a: None,
+                b: None,
+                inputs: vec![None; self.inputs.len()],
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            BooleanChip::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let chip = BooleanChip::construct(config);
+            // a 是任意的域元素, 用 assert_boolean 检查
+            let a = layouter.assign_region(
+                || "a",
+                |mut region| {
+                    region.assign_advice(
+                        || "a",
+                        chip.config.b,
+                        0,
+                        || self.a.ok_or(Error::Synthesis),
+                    )
+                },
+            )?;
+            let a = chip.assert_boolean(layouter.namespace(|| "a"), &a)?;
+            let b = chip.load_private(layouter.namespace(|| "b"), self.b)?;
+            let inputs = self
+                .inputs
+                .iter()
+                .map(|v| chip.load_private(layouter.namespace(|| "input"), *v))
+                .collect::<Result<Vec<_>, Error>>()?;
+
+            let outputs = [
+                chip.and(layouter.namespace(|| "and"), &a, &b)?,
+                chip.or(layouter.namespace(|| "or"), &a, &b)?,
+                chip.xor(layouter.namespace(|| "xor"), &a, &b)?,
+                chip.not(layouter.namespace(|| "not"), &a)?,
+                chip.nand(layouter.namespace(|| "nand"), &a, &b)?,
+                chip.and_many(layouter.namespace(|| "and many"), &inputs)?,
+                chip.or_many(layouter.namespace(|| "or many"), &inputs)?,
+            ];
+            for (i, out) in outputs.iter().enumerate() {
+                chip.expose_public(layouter.namespace(|| "out"), out, i)?;
+            }
+            Ok(())
+        }
+    }
+
+    fn run(a: Fr, b: bool, inputs: &[bool], expected: [bool; 7]) -> bool {
+        let circuit = LogicCircuit {
+            a: Some(a),
+            b: Some(b),
+            inputs: inputs.iter().map(|v| Some(*v)).collect(),
+        };
+        let instance = expected.iter().map(|v| Fr::from(*v as u64)).collect();
+        let prover = MockProver::run(6, &circuit, vec![instance]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    pub fn test_truth_table() {
+        for a in [false, true] {
+            for b in [false, true] {
+                for c in [false, true] {
+                    let inputs = [a, b, c, true];
+                    let expected = [
+                        a & b,
+                        a | b,
+                        a ^ b,
+                        !a,
+                        !(a & b),
+                        a & b & c,
+                        a | b | c | true,
+                    ];
+                    assert!(run(Fr::from(a as u64), b, &inputs, expected));
+
+                    let mut wrong = expected;
+                    wrong[(a as usize) * 2 + b as usize] ^= true;
+                    assert!(!run(Fr::from(a as u64), b, &inputs, wrong));
+                }
+            }
+        }
+    }
+
+    #[test]
+    pub fn test_not_boolean() {
+        // a = 2 时, and 的结果 2 * 1 = 2 能满足 and 的约束, 但是 bool 约束不满足
+        let circuit = LogicCircuit {
+            a: Some(Fr::from(2u64)),
+            b: Some(true),
+            inputs: vec![Some(true)],
+        };
+        let two = Fr::from(2u64);
+        let instance = vec![
+            two,
+            Fr::one(),
+            -Fr::one(),
+            -Fr::one(),
+            -Fr::one(),
+            Fr::one(),
+            Fr::one(),
+        ];
+        let prover = MockProver::run(6, &circuit, vec![instance]).unwrap();
+        assert_ne!(prover.verify(), Ok(()));
+    }
+}
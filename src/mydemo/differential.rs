@@ -0,0 +1,334 @@
+use crate::mydemo::mimc::sc_box::{CsHasher, Hasher};
+use crate::mydemo::word::CsByteHasher;
+use crate::range_check::harness::describe;
+use halo2_proofs::arithmetic::{Field, FieldExt};
+use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner};
+use halo2_proofs::dev::MockProver;
+use halo2_proofs::pairing::bn256::Fr;
+use halo2_proofs::plonk::{Circuit, Column, ConstraintSystem, Error, Instance};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::fmt::Debug;
+use std::marker::PhantomData;
+
+// 哈希电路和原生实现的差分测试:
+// 1. 随机生成输入, 用原生实现算出结果, 作为 instance
+// 2. MockProver 必须通过
+// 3. 把结果的其中一个改掉, 必须失败, 否则说明输出没有被约束
+// 失败时打印输入和种子, 方便复现
+pub struct DifferentialHarness<I, C, N, B, G>
+where
+    I: Debug,
+    C: Circuit<Fr>,
+    N: Fn(&I) -> Vec<Fr>,
+    B: Fn(&I) -> C,
+    G: Fn(&mut StdRng) -> I,
+{
+    pub name: &'static str,
+    pub k: u32,
+    // 原生实现, 返回暴露为 instance 的值
+    pub native: N,
+    // 根据输入构造电路
+    pub build: B,
+    // 随机生成一个输入
+    pub generate: G,
+}
+
+impl<I, C, N, B, G> DifferentialHarness<I, C, N, B, G>
+where
+    I: Debug,
+    C: Circuit<Fr>,
+    N: Fn(&I) -> Vec<Fr>,
+    B: Fn(&I) -> C,
+    G: Fn(&mut StdRng) -> I,
+{
+    pub fn new(name: &'static str, k: u32, native: N, build: B, generate: G) -> Self {
+        Self {
+            name,
+            k,
+            native,
+            build,
+            generate,
+        }
+    }
+
+    // 返回失败的原因, wrong 是要改掉的输出的下标
+    pub fn check(&self, input: &I, wrong: usize) -> Result<(), String> {
+        let expected = (self.native)(input);
+        let circuit = (self.build)(input);
+        let prover = MockProver::run(self.k, &circuit, vec![expected.clone()])
+            .map_err(|e| format!("synthesis failed: {:?}", e))?;
+        prover.verify().map_err(|failures| {
+            format!(
+                "circuit rejected the native output {:?}:\n{}",
+                expected,
+                describe(&failures)
+            )
+        })?;
+
+        let wrong = wrong % expected.len();
+        let mut instance = expected;
+        instance[wrong] += Fr::one();
+        let prover = MockProver::run(self.k, &circuit, vec![instance])
+            .map_err(|e| format!("synthesis failed: {:?}", e))?;
+        match prover.verify() {
+            Ok(()) => Err(format!("output {} is not constrained", wrong)),
+            Err(_) => Ok(()),
+        }
+    }
+
+    pub fn check_inputs(&self, inputs: &[I]) {
+        for (i, input) in inputs.iter().enumerate() {
+            if let Err(reason) = self.check(input, i) {
+                panic!("{}: failed for input {:?}\n{}", self.name, input, reason);
+            }
+        }
+    }
+
+    // 固定种子, 失败时可以复现
+    pub fn run(&self, seed: u64, samples: usize) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        for i in 0..samples {
+            let input = (self.generate)(&mut rng);
+            if let Err(reason) = self.check(&input, i) {
+                panic!(
+                    "{}: sample {} (seed {:#x}) failed for input {:?}\n{}",
+                    self.name, i, seed, input, reason
+                );
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct HashConfig<C> {
+    pub hasher: C,
+    pub instance: Column<Instance>,
+}
+
+// 哈希是类型参数, 把 H(inputs) 暴露为instance
+pub struct HashCircuit<F: FieldExt, H: CsHasher<F>> {
+    inputs: Vec<Option<F>>,
+    _h: PhantomData<H>,
+}
+
+impl<F: FieldExt, H: CsHasher<F>> HashCircuit<F, H> {
+    pub fn new(inputs: &[F]) -> Self {
+        Self {
+            inputs: inputs.iter().map(|v| Some(*v)).collect(),
+            _h: Default::default(),
+        }
+    }
+}
+
+impl<F: FieldExt, H: CsHasher<F>> Circuit<F> for HashCircuit<F, H> {
+    type Config = HashConfig<H::Config>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            inputs: vec![None; self.inputs.len()],
+            _h: Default::default(),
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        HashConfig {
+            hasher: H::configure(meta),
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = H::construct(config.hasher);
+        let inputs = self
+            .inputs
+            .iter()
+            .map(|v| chip.load_private(layouter.namespace(|| "input"), *v))
+            .collect::<Result<Vec<_>, Error>>()?;
+        let out = chip.assign_hash(layouter.namespace(|| "hash"), &inputs)?;
+        layouter.constrain_instance(out.cell(), config.instance, 0)
+    }
+}
+
+// 按byte输入的哈希, 把 digest 的每个 word 依次暴露为instance
+pub struct ByteHashCircuit<F: FieldExt, H: CsByteHasher<F>> {
+    message: Vec<Option<u8>>,
+    _p: PhantomData<(F, H)>,
+}
+
+impl<F: FieldExt, H: CsByteHasher<F>> ByteHashCircuit<F, H> {
+    pub fn new(message: &[u8]) -> Self {
+        Self {
+            message: message.iter().map(|b| Some(*b)).collect(),
+            _p: Default::default(),
+        }
+    }
+}
+
+impl<F: FieldExt, H: CsByteHasher<F>> Circuit<F> for ByteHashCircuit<F, H> {
+    type Config = HashConfig<crate::mydemo::word::WordConfig<F>>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            message: vec![None; self.message.len()],
+            _p: Default::default(),
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        HashConfig {
+            hasher: H::configure(meta),
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = H::construct(config.hasher);
+        chip.load_tables(layouter.namespace(|| "tables"))?;
+        let digest = chip.digest(layouter.namespace(|| "digest"), &self.message)?;
+        for (i, word) in digest.iter().enumerate() {
+            layouter.constrain_instance(word.value.cell(), config.instance, i)?;
+        }
+        Ok(())
+    }
+}
+
+// 闭包都装箱, 方便写出类型
+pub type BoxedHarness<I, C> = DifferentialHarness<
+    I,
+    C,
+    Box<dyn Fn(&I) -> Vec<Fr>>,
+    Box<dyn Fn(&I) -> C>,
+    Box<dyn Fn(&mut StdRng) -> I>,
+>;
+
+// 输入是 [0, max_inputs] 个随机的域元素
+pub fn field_hash<H: CsHasher<Fr> + 'static>(
+    name: &'static str,
+    k: u32,
+    native: impl Hasher<Fr> + 'static,
+    max_inputs: usize,
+) -> BoxedHarness<Vec<Fr>, HashCircuit<Fr, H>> {
+    DifferentialHarness::new(
+        name,
+        k,
+        Box::new(move |inputs| vec![native.hash(inputs)]),
+        Box::new(|inputs| HashCircuit::new(inputs)),
+        Box::new(move |rng| {
+            let n = rng.gen_range(0..=max_inputs);
+            (0..n).map(|_| Fr::random(&mut *rng)).collect()
+        }),
+    )
+}
+
+// 输入是长度为 [0, max_len] 的随机byte串, native 返回 digest 的各个 word
+pub fn byte_hash<H: CsByteHasher<Fr> + 'static>(
+    name: &'static str,
+    native: impl Fn(&[u8]) -> Vec<Fr> + 'static,
+    max_len: usize,
+) -> BoxedHarness<Vec<u8>, ByteHashCircuit<Fr, H>> {
+    // 查表需要 2^16 行
+    DifferentialHarness::new(
+        name,
+        17,
+        Box::new(move |message: &Vec<u8>| native(message)),
+        Box::new(|message: &Vec<u8>| ByteHashCircuit::new(message)),
+        Box::new(move |rng| {
+            let len = rng.gen_range(0..=max_len);
+            (0..len).map(|_| rng.gen()).collect()
+        }),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mydemo::blake2s::chip::Blake2sChip;
+    use crate::mydemo::blake2s::native::blake2s;
+    use crate::mydemo::differential::{byte_hash, field_hash};
+    use crate::mydemo::keccak::chip::KeccakChip;
+    use crate::mydemo::keccak::native::keccak256;
+    use crate::mydemo::mimc::sc_box::{QuinticInverseSBoxChip, QuinticSBoxChip};
+    use crate::mydemo::mimc::sponge::{MiMCSponge, MiMCSpongeChip};
+    use crate::mydemo::poseidon::permutation::{Poseidon, PoseidonChip};
+    use crate::mydemo::rescue::permutation::{Rescue, RescueChip};
+    use crate::mydemo::sha256::chip::Sha256Chip;
+    use crate::mydemo::sha256::native::sha256;
+    use halo2_proofs::pairing::bn256::Fr;
+
+    fn le_words(digest: &[u8], size: usize) -> Vec<Fr> {
+        digest
+            .chunks(size)
+            .map(|w| Fr::from(w.iter().rev().fold(0u64, |acc, b| (acc << 8) | *b as u64)))
+            .collect()
+    }
+
+    #[test]
+    pub fn test_field_hashes() {
+        let edge = vec![vec![], vec![Fr::zero()], vec![-Fr::one(), Fr::one()]];
+
+        let mimc = field_hash::<MiMCSpongeChip<Fr, 7, 2, 1>>(
+            "mimc",
+            13,
+            MiMCSponge::<Fr, 7, 2, 1>::new(),
+            3,
+        );
+        mimc.check_inputs(&edge);
+        mimc.run(1, 3);
+
+        let poseidon = field_hash::<PoseidonChip<Fr, QuinticSBoxChip<Fr>, 3>>(
+            "poseidon",
+            12,
+            Poseidon::bn256(3),
+            4,
+        );
+        poseidon.check_inputs(&edge);
+        poseidon.run(2, 4);
+
+        let rescue = field_hash::<RescueChip<Fr, QuinticSBoxChip<Fr>, QuinticInverseSBoxChip<Fr>, 3>>(
+            "rescue",
+            11,
+            Rescue::bn256(3),
+            4,
+        );
+        rescue.check_inputs(&edge);
+        rescue.run(3, 4);
+    }
+
+    #[test]
+    pub fn test_sha256() {
+        let harness = byte_hash::<Sha256Chip<Fr>>(
+            "sha256",
+            |m| sha256(m).iter().map(|w| Fr::from(*w as u64)).collect(),
+            130,
+        );
+        harness.run(4, 2);
+    }
+
+    #[test]
+    pub fn test_keccak256() {
+        let harness = byte_hash::<KeccakChip<Fr>>("keccak256", |m| le_words(&keccak256(m), 8), 150);
+        harness.run(5, 2);
+    }
+
+    #[test]
+    pub fn test_blake2s() {
+        let harness = byte_hash::<Blake2sChip<Fr>>("blake2s", |m| le_words(&blake2s(m), 4), 130);
+        // 刚好一个 block 时没有 padding
+        harness.check_inputs(&[vec![0u8; 64]]);
+        harness.run(6, 2);
+    }
+}
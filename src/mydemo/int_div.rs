@@ -0,0 +1,291 @@
+use crate::range_check::chip::{RangeChip, RangeConfig};
+use halo2_proofs::arithmetic::FieldExt;
+use halo2_proofs::circuit::{AssignedCell, Layouter};
+use halo2_proofs::plonk::{
+    Advice, Column, ConstraintSystem, Error, Expression, Instance, Selector,
+};
+use halo2_proofs::poly::Rotation;
+use std::marker::PhantomData;
+
+// 整数除法, a 和 b 都是 num_bits 位的无符号整数
+// a | b | q | r | d | s_div
+// a = q * b + r, d = b - r - 1
+// q, r, d 都在 [0, 2^num_bits) 里, d >= 0 所以 r < b, 也就排除了 b = 0
+// num_bits <= 126 时 q * b + r < 2^252 < p, 不会在域上溢出
+#[derive(Clone, Debug)]
+pub struct IntDivConfig<F: FieldExt> {
+    pub advice: [Column<Advice>; 5],
+    pub s_div: Selector,
+    pub range: RangeConfig<F>,
+    pub instance: Column<Instance>,
+}
+
+pub struct IntDivChip<F: FieldExt> {
+    config: IntDivConfig<F>,
+    range: RangeChip<F>,
+    _p: PhantomData<F>,
+}
+
+pub const MAX_BITS: usize = 126;
+
+// (商, 余数)
+pub type QuotientRemainder<F> = (AssignedCell<F, F>, AssignedCell<F, F>);
+
+// 商和余数
+pub fn div_rem(a: u128, b: u128) -> Option<(u128, u128)> {
+    (b != 0).then(|| (a / b, a % b))
+}
+
+impl<F: FieldExt> IntDivChip<F> {
+    pub fn construct(config: IntDivConfig<F>) -> Self {
+        let range = RangeChip::construct(config.range.clone());
+        Self {
+            config,
+            range,
+            _p: Default::default(),
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> IntDivConfig<F> {
+        let advice = [(); 5].map(|_| meta.advice_column());
+        let s_div = meta.selector();
+        let instance = meta.instance_column();
+        let range = RangeChip::configure(meta);
+
+        for column in advice {
+            meta.enable_equality(column);
+        }
+        meta.enable_equality(instance);
+
+        meta.create_gate("int div", |meta| {
+            let s = meta.query_selector(s_div);
+            let [a, b, q, r, d] = advice.map(|c| meta.query_advice(c, Rotation::cur()));
+            vec![
+                s.clone() * (q * b.clone() + r.clone() - a),
+                s * (b - r - Expression::Constant(F::one()) - d),
+            ]
+        });
+
+        IntDivConfig {
+            advice,
+            s_div,
+            range,
+            instance,
+        }
+    }
+
+    pub fn load_table(&self, layouter: impl Layouter<F>) -> Result<(), Error> {
+        self.range.load_table(layouter)
+    }
+
+    // 加载一个 num_bits 位的整数, 并检查范围
+    pub fn load_private(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Option<u128>,
+        num_bits: usize,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let cell = layouter.assign_region(
+            || "load private",
+            |mut region| {
+                region.assign_advice(
+                    || "private",
+                    self.config.advice[0],
+                    0,
+                    || value.map(F::from_u128).ok_or(Error::Synthesis),
+                )
+            },
+        )?;
+        self.range
+            .range_check(layouter.namespace(|| "range"), &cell, num_bits)?;
+        Ok(cell)
+    }
+
+    // 直接指定 q 和 r, b = 0 时上层取 q = 0, r = a, 约束不会满足
+    fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+        q: Option<F>,
+        r: Option<F>,
+        num_bits: usize,
+    ) -> Result<QuotientRemainder<F>, Error> {
+        assert!(num_bits <= MAX_BITS);
+        let (q, r, d) = layouter.assign_region(
+            || "int div",
+            |mut region| {
+                let [a_col, b_col, q_col, r_col, d_col] = self.config.advice;
+                self.config.s_div.enable(&mut region, 0)?;
+                a.copy_advice(|| "a", &mut region, a_col, 0)?;
+                b.copy_advice(|| "b", &mut region, b_col, 0)?;
+                let q = region.assign_advice(|| "q", q_col, 0, || q.ok_or(Error::Synthesis))?;
+                let r = region.assign_advice(|| "r", r_col, 0, || r.ok_or(Error::Synthesis))?;
+                let d = b
+                    .value()
+                    .and_then(|b| r.value().map(|r| *b - *r - F::one()));
+                let d = region.assign_advice(|| "d", d_col, 0, || d.ok_or(Error::Synthesis))?;
+                Ok((q, r, d))
+            },
+        )?;
+        for (name, cell) in [("q", &q), ("r", &r), ("b - r - 1", &d)] {
+            self.range
+                .range_check(layouter.namespace(|| name), cell, num_bits)?;
+        }
+        Ok((q, r))
+    }
+
+    // 返回 (a / b, a % b)
+    pub fn div_rem(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+        num_bits: usize,
+    ) -> Result<QuotientRemainder<F>, Error> {
+        let to_u128 = |cell: &AssignedCell<F, F>| cell.value().map(|v| v.get_lower_128());
+        let qr = to_u128(a).and_then(|a| to_u128(b).map(|b| div_rem(a, b).unwrap_or((0, a))));
+        let q = qr.map(|(q, _)| F::from_u128(q));
+        let r = qr.map(|(_, r)| F::from_u128(r));
+        self.assign(layouter, a, b, q, r, num_bits)
+    }
+
+    pub fn div(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+        num_bits: usize,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        self.div_rem(layouter, a, b, num_bits).map(|(q, _)| q)
+    }
+
+    pub fn rem(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+        num_bits: usize,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        self.div_rem(layouter, a, b, num_bits).map(|(_, r)| r)
+    }
+
+    pub fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: &AssignedCell<F, F>,
+        row: usize,
+    ) -> Result<(), Error> {
+        layouter.constrain_instance(cell.cell(), self.config.instance, row)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mydemo::int_div::{div_rem, IntDivChip, IntDivConfig};
+    use halo2_proofs::arithmetic::FieldExt;
+    use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner};
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::pairing::bn256::Fr;
+    use halo2_proofs::pairing::group::ff::Field;
+    use halo2_proofs::plonk::{Circuit, ConstraintSystem, Error};
+
+    // 暴露 a / b 和 a % b; forged 时用给定的 q, r
+    #[derive(Default)]
+    pub struct DivCircuit<F: FieldExt> {
+        a: Option<u128>,
+        b: Option<u128>,
+        forged: Option<(F, F)>,
+        num_bits: usize,
+    }
+
+    impl<F: FieldExt> Circuit<F> for DivCircuit<F> {
+        type Config = IntDivConfig<F>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                num_bits: self.num_bits,
+                ..Default::default()
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            IntDivChip::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let chip = IntDivChip::construct(config);
+            chip.load_table(layouter.namespace(|| "table"))?;
+            let a = chip.load_private(layouter.namespace(|| "a"), self.a, self.num_bits)?;
+            let b = chip.load_private(layouter.namespace(|| "b"), self.b, self.num_bits)?;
+            let (q, r) = match self.forged {
+                Some((q, r)) => chip.assign(
+                    layouter.namespace(|| "forged"),
+                    &a,
+                    &b,
+                    Some(q),
+                    Some(r),
+                    self.num_bits,
+                )?,
+                None => chip.div_rem(layouter.namespace(|| "div"), &a, &b, self.num_bits)?,
+            };
+            chip.expose_public(layouter.namespace(|| "q"), &q, 0)?;
+            chip.expose_public(layouter.namespace(|| "r"), &r, 1)
+        }
+    }
+
+    fn prove(a: u128, b: u128, forged: Option<(Fr, Fr)>, num_bits: usize, q: Fr, r: Fr) -> bool {
+        let circuit = DivCircuit {
+            a: Some(a),
+            b: Some(b),
+            forged,
+            num_bits,
+        };
+        let prover = MockProver::run(9, &circuit, vec![vec![q, r]]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    pub fn test_div_rem() {
+        let cases = [
+            (32, 7, 32),
+            (0, 5, 32),
+            (6, 7, 32),
+            (u32::MAX as u128, 1, 32),
+            (u32::MAX as u128, u32::MAX as u128, 32),
+            (1_000_000_007, 1000, 32),
+            (u64::MAX as u128, 3, 64),
+            ((1 << 126) - 1, (1 << 100) + 12345, 126),
+        ];
+        for (a, b, num_bits) in cases {
+            let (q, r) = div_rem(a, b).unwrap();
+            let (q, r) = (Fr::from_u128(q), Fr::from_u128(r));
+            assert!(prove(a, b, None, num_bits, q, r));
+            assert!(!prove(a, b, None, num_bits, q + Fr::one(), r));
+            assert!(!prove(a, b, None, num_bits, q, r + Fr::one()));
+        }
+    }
+
+    #[test]
+    pub fn test_div_by_zero() {
+        assert!(!prove(7, 0, None, 32, Fr::zero(), Fr::from(7u64)));
+    }
+
+    #[test]
+    pub fn test_forged() {
+        // 7 = 1 * 3 + 4, 但 r >= b
+        let (q, r) = (Fr::one(), Fr::from(4u64));
+        assert!(!prove(7, 3, Some((q, r)), 32, q, r));
+        // 7 = (7 / 2) * 2 + 0, 在域上成立, 但 q 不在范围内
+        let q = Fr::from(7u64) * Fr::from(2u64).invert().unwrap();
+        assert!(!prove(7, 2, Some((q, Fr::zero())), 32, q, Fr::zero()));
+        // 正确的 q, r 走同一条路径可以通过
+        let (q, r) = (Fr::from(3u64), Fr::one());
+        assert!(prove(7, 2, Some((q, r)), 32, q, r));
+    }
+}
@@ -0,0 +1,266 @@
+use crate::range_check::chip::{RangeChip, RangeConfig};
+use halo2_proofs::arithmetic::FieldExt;
+use halo2_proofs::circuit::{AssignedCell, Layouter};
+use halo2_proofs::plonk::{Advice, Column, ConstraintSystem, Error, Instance, Selector};
+use halo2_proofs::poly::Rotation;
+use std::marker::PhantomData;
+
+// s = floor(sqrt(x)), x 是 num_bits 位的无符号整数
+// x | s | lo | hi | s_sqrt
+// lo = x - s^2, hi = (s + 1)^2 - x - 1 = s^2 + 2s - x
+// s 在 [0, 2^ceil(num_bits / 2)) 里, lo 和 hi 在 [0, 2^(num_bits + 1)) 里
+// 这样 s^2 <= x < (s + 1)^2, 数都很小不会在域上绕回
+#[derive(Clone, Debug)]
+pub struct IntSqrtConfig<F: FieldExt> {
+    pub advice: [Column<Advice>; 4],
+    pub s_sqrt: Selector,
+    pub range: RangeConfig<F>,
+    pub instance: Column<Instance>,
+}
+
+pub struct IntSqrtChip<F: FieldExt> {
+    config: IntSqrtConfig<F>,
+    range: RangeChip<F>,
+    _p: PhantomData<F>,
+}
+
+pub const MAX_BITS: usize = 126;
+
+// 逐位确定 s 的每个bit, 从高位开始
+pub fn isqrt(x: u128) -> u128 {
+    (0..64).rev().fold(0u128, |s, i| {
+        let t = s | (1 << i);
+        if t * t <= x {
+            t
+        } else {
+            s
+        }
+    })
+}
+
+impl<F: FieldExt> IntSqrtChip<F> {
+    pub fn construct(config: IntSqrtConfig<F>) -> Self {
+        let range = RangeChip::construct(config.range.clone());
+        Self {
+            config,
+            range,
+            _p: Default::default(),
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> IntSqrtConfig<F> {
+        let advice = [(); 4].map(|_| meta.advice_column());
+        let s_sqrt = meta.selector();
+        let instance = meta.instance_column();
+        let range = RangeChip::configure(meta);
+
+        for column in advice {
+            meta.enable_equality(column);
+        }
+        meta.enable_equality(instance);
+
+        meta.create_gate("int sqrt", |meta| {
+            let sel = meta.query_selector(s_sqrt);
+            let [x, s, lo, hi] = advice.map(|c| meta.query_advice(c, Rotation::cur()));
+            let s2 = s.clone() * s.clone();
+            vec![
+                sel.clone() * (x.clone() - s2.clone() - lo),
+                sel * (s2 + s * F::from(2u64) - x - hi),
+            ]
+        });
+
+        IntSqrtConfig {
+            advice,
+            s_sqrt,
+            range,
+            instance,
+        }
+    }
+
+    pub fn load_table(&self, layouter: impl Layouter<F>) -> Result<(), Error> {
+        self.range.load_table(layouter)
+    }
+
+    // 加载一个 num_bits 位的整数, 并检查范围
+    pub fn load_private(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Option<u128>,
+        num_bits: usize,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let cell = layouter.assign_region(
+            || "load private",
+            |mut region| {
+                region.assign_advice(
+                    || "private",
+                    self.config.advice[0],
+                    0,
+                    || value.map(F::from_u128).ok_or(Error::Synthesis),
+                )
+            },
+        )?;
+        self.range
+            .range_check(layouter.namespace(|| "range"), &cell, num_bits)?;
+        Ok(cell)
+    }
+
+    fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        x: &AssignedCell<F, F>,
+        s: Option<F>,
+        num_bits: usize,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        assert!(num_bits > 0 && num_bits <= MAX_BITS);
+        let (s, lo, hi) = layouter.assign_region(
+            || "int sqrt",
+            |mut region| {
+                let [x_col, s_col, lo_col, hi_col] = self.config.advice;
+                self.config.s_sqrt.enable(&mut region, 0)?;
+                let x = x.copy_advice(|| "x", &mut region, x_col, 0)?;
+                let s = region.assign_advice(|| "s", s_col, 0, || s.ok_or(Error::Synthesis))?;
+                let lo = x.value().and_then(|x| s.value().map(|s| *x - s.square()));
+                let hi = x
+                    .value()
+                    .and_then(|x| s.value().map(|s| s.square() + s.double() - *x));
+                let lo = region.assign_advice(|| "lo", lo_col, 0, || lo.ok_or(Error::Synthesis))?;
+                let hi = region.assign_advice(|| "hi", hi_col, 0, || hi.ok_or(Error::Synthesis))?;
+                Ok((s, lo, hi))
+            },
+        )?;
+        self.range
+            .range_check(layouter.namespace(|| "s"), &s, num_bits.div_ceil(2))?;
+        self.range
+            .range_check(layouter.namespace(|| "lo"), &lo, num_bits + 1)?;
+        self.range
+            .range_check(layouter.namespace(|| "hi"), &hi, num_bits + 1)?;
+        Ok(s)
+    }
+
+    pub fn sqrt(
+        &self,
+        layouter: impl Layouter<F>,
+        x: &AssignedCell<F, F>,
+        num_bits: usize,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let s = x.value().map(|x| F::from_u128(isqrt(x.get_lower_128())));
+        self.assign(layouter, x, s, num_bits)
+    }
+
+    pub fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: &AssignedCell<F, F>,
+        row: usize,
+    ) -> Result<(), Error> {
+        layouter.constrain_instance(cell.cell(), self.config.instance, row)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mydemo::int_sqrt::{isqrt, IntSqrtChip, IntSqrtConfig};
+    use halo2_proofs::arithmetic::FieldExt;
+    use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner};
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::pairing::bn256::Fr;
+    use halo2_proofs::plonk::{Circuit, ConstraintSystem, Error};
+
+    // 暴露 floor(sqrt(x)); forged 时用给定的 s
+    #[derive(Default)]
+    pub struct SqrtCircuit<F: FieldExt> {
+        x: Option<u128>,
+        forged: Option<F>,
+        num_bits: usize,
+    }
+
+    impl<F: FieldExt> Circuit<F> for SqrtCircuit<F> {
+        type Config = IntSqrtConfig<F>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                num_bits: self.num_bits,
+                ..Default::default()
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            IntSqrtChip::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let chip = IntSqrtChip::construct(config);
+            chip.load_table(layouter.namespace(|| "table"))?;
+            let x = chip.load_private(layouter.namespace(|| "x"), self.x, self.num_bits)?;
+            let s = match self.forged {
+                Some(s) => {
+                    chip.assign(layouter.namespace(|| "forged"), &x, Some(s), self.num_bits)?
+                }
+                None => chip.sqrt(layouter.namespace(|| "sqrt"), &x, self.num_bits)?,
+            };
+            chip.expose_public(layouter.namespace(|| "s"), &s, 0)
+        }
+    }
+
+    fn prove(x: u128, forged: Option<Fr>, num_bits: usize, s: Fr) -> bool {
+        let circuit = SqrtCircuit {
+            x: Some(x),
+            forged,
+            num_bits,
+        };
+        let prover = MockProver::run(9, &circuit, vec![vec![s]]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    pub fn test_isqrt() {
+        for x in 0..10_000u128 {
+            let s = isqrt(x);
+            assert!(s * s <= x && x < (s + 1) * (s + 1));
+        }
+        assert_eq!(isqrt(u128::MAX), u64::MAX as u128);
+        assert_eq!(isqrt((1 << 126) - 1), (1 << 63) - 1);
+    }
+
+    #[test]
+    pub fn test_sqrt() {
+        // 完全平方数和它两边的数
+        let cases = [
+            (0, 8),
+            (1, 8),
+            (15, 8),
+            (16, 8),
+            (17, 8),
+            (255, 8),
+            (1, 1),
+            (3, 2),
+            (u32::MAX as u128, 32),
+            (65536 * 65536 - 1, 32),
+            ((1 << 126) - 1, 126),
+            (((1 << 63) - 1) * ((1 << 63) - 1), 126),
+        ];
+        for (x, num_bits) in cases {
+            let s = Fr::from_u128(isqrt(x));
+            assert!(prove(x, None, num_bits, s));
+            assert!(!prove(x, None, num_bits, s + Fr::one()));
+        }
+    }
+
+    #[test]
+    pub fn test_forged() {
+        // 16 的平方根不能是 3 或 5
+        let four = Fr::from(4u64);
+        assert!(prove(16, Some(four), 8, four));
+        for s in [3u64, 5] {
+            let s = Fr::from(s);
+            assert!(!prove(16, Some(s), 8, s));
+        }
+        // -4 的平方也是 16
+        assert!(!prove(16, Some(-four), 8, -four));
+    }
+}
@@ -0,0 +1,333 @@
+use crate::mydemo::boolean::AssignedBool;
+use halo2_proofs::arithmetic::FieldExt;
+use halo2_proofs::circuit::{AssignedCell, Layouter};
+use halo2_proofs::plonk::{
+    Advice, Column, ConstraintSystem, Error, Expression, Instance, Selector,
+};
+use halo2_proofs::poly::Rotation;
+use std::marker::PhantomData;
+
+// x | inv | z | a | out
+// s_inv:         x * inv = 1
+// s_inv_checked: x * inv = 1 - z, x * z = 0, inv * z = 0
+//                x != 0 时 z = 0, inv = 1 / x; x = 0 时 z = 1, inv = 0
+// s_div:         out = a * inv, 和上面两个中的一个一起用
+#[derive(Clone, Debug)]
+pub struct InverseConfig {
+    pub x: Column<Advice>,
+    pub inv: Column<Advice>,
+    pub z: Column<Advice>,
+    pub a: Column<Advice>,
+    pub out: Column<Advice>,
+    pub s_inv: Selector,
+    pub s_inv_checked: Selector,
+    pub s_div: Selector,
+    pub instance: Column<Instance>,
+}
+
+// 结果和 checked 时的 is_zero
+type Quotient<F> = (AssignedCell<F, F>, Option<AssignedBool<F>>);
+
+pub struct InverseChip<F: FieldExt> {
+    config: InverseConfig,
+    _p: PhantomData<F>,
+}
+
+impl<F: FieldExt> InverseChip<F> {
+    pub fn construct(config: InverseConfig) -> Self {
+        Self {
+            config,
+            _p: Default::default(),
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> InverseConfig {
+        let x = meta.advice_column();
+        let inv = meta.advice_column();
+        let z = meta.advice_column();
+        let a = meta.advice_column();
+        let out = meta.advice_column();
+        let s_inv = meta.selector();
+        let s_inv_checked = meta.selector();
+        let s_div = meta.selector();
+        let instance = meta.instance_column();
+
+        for column in [x, inv, z, a, out] {
+            meta.enable_equality(column);
+        }
+        meta.enable_equality(instance);
+
+        let one = Expression::Constant(F::one());
+        meta.create_gate("inv", |meta| {
+            let s = meta.query_selector(s_inv);
+            let x = meta.query_advice(x, Rotation::cur());
+            let inv = meta.query_advice(inv, Rotation::cur());
+            vec![s * (x * inv - one.clone())]
+        });
+        meta.create_gate("inv checked", |meta| {
+            let s = meta.query_selector(s_inv_checked);
+            let x = meta.query_advice(x, Rotation::cur());
+            let inv = meta.query_advice(inv, Rotation::cur());
+            let z = meta.query_advice(z, Rotation::cur());
+            vec![
+                s.clone() * (x.clone() * inv.clone() + z.clone() - one.clone()),
+                s.clone() * x * z.clone(),
+                s * inv * z,
+            ]
+        });
+        meta.create_gate("div", |meta| {
+            let s = meta.query_selector(s_div);
+            let inv = meta.query_advice(inv, Rotation::cur());
+            let a = meta.query_advice(a, Rotation::cur());
+            let out = meta.query_advice(out, Rotation::cur());
+            vec![s * (a * inv - out)]
+        });
+
+        InverseConfig {
+            x,
+            inv,
+            z,
+            a,
+            out,
+            s_inv,
+            s_inv_checked,
+            s_div,
+            instance,
+        }
+    }
+
+    pub fn load_private(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Option<F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        layouter.assign_region(
+            || "load private",
+            |mut region| {
+                region.assign_advice(
+                    || "private",
+                    self.config.x,
+                    0,
+                    || value.ok_or(Error::Synthesis),
+                )
+            },
+        )
+    }
+
+    // a 为 None 时只求逆, 返回 inv; 否则返回 a * inv
+    // checked 时 x = 0 不会报错, 返回 0 和 z = 1
+    fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        x: &AssignedCell<F, F>,
+        a: Option<&AssignedCell<F, F>>,
+        checked: bool,
+    ) -> Result<Quotient<F>, Error> {
+        layouter.assign_region(
+            || if a.is_some() { "div" } else { "inv" },
+            |mut region| {
+                let config = &self.config;
+                let x = x.copy_advice(|| "x", &mut region, config.x, 0)?;
+                let inv = x.value().map(|x| Option::<F>::from(x.invert()));
+                let inv = if checked {
+                    config.s_inv_checked.enable(&mut region, 0)?;
+                    inv.map(|inv| inv.unwrap_or_else(F::zero))
+                } else {
+                    config.s_inv.enable(&mut region, 0)?;
+                    // 0 没有逆, 约束一定不能满足
+                    match inv {
+                        Some(None) => return Err(Error::Synthesis),
+                        inv => inv.flatten(),
+                    }
+                };
+                let inv_cell = region.assign_advice(
+                    || "inv",
+                    config.inv,
+                    0,
+                    || inv.ok_or(Error::Synthesis),
+                )?;
+
+                let z = if checked {
+                    let z = x
+                        .value()
+                        .map(|x| if *x == F::zero() { F::one() } else { F::zero() });
+                    let z =
+                        region.assign_advice(|| "z", config.z, 0, || z.ok_or(Error::Synthesis))?;
+                    Some(AssignedBool(z))
+                } else {
+                    None
+                };
+
+                let result = match a {
+                    Some(a) => {
+                        config.s_div.enable(&mut region, 0)?;
+                        let a = a.copy_advice(|| "a", &mut region, config.a, 0)?;
+                        let out = a.value().and_then(|a| inv.map(|inv| *a * inv));
+                        region.assign_advice(
+                            || "out",
+                            config.out,
+                            0,
+                            || out.ok_or(Error::Synthesis),
+                        )?
+                    }
+                    None => inv_cell,
+                };
+                Ok((result, z))
+            },
+        )
+    }
+
+    // x = 0 时返回 Error::Synthesis
+    pub fn inv(
+        &self,
+        layouter: impl Layouter<F>,
+        x: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        self.assign(layouter, x, None, false).map(|(inv, _)| inv)
+    }
+
+    // b = 0 时返回 Error::Synthesis
+    pub fn div(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        self.assign(layouter, b, Some(a), false).map(|(out, _)| out)
+    }
+
+    // 返回 (inv, x == 0), x = 0 时 inv = 0
+    pub fn inv_checked(
+        &self,
+        layouter: impl Layouter<F>,
+        x: &AssignedCell<F, F>,
+    ) -> Result<(AssignedCell<F, F>, AssignedBool<F>), Error> {
+        self.assign(layouter, x, None, true)
+            .map(|(inv, z)| (inv, z.unwrap()))
+    }
+
+    // 返回 (a / b, b == 0), b = 0 时结果是 0
+    pub fn div_checked(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+    ) -> Result<(AssignedCell<F, F>, AssignedBool<F>), Error> {
+        self.assign(layouter, b, Some(a), true)
+            .map(|(out, z)| (out, z.unwrap()))
+    }
+
+    pub fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: &AssignedCell<F, F>,
+        row: usize,
+    ) -> Result<(), Error> {
+        layouter.constrain_instance(cell.cell(), self.config.instance, row)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mydemo::inverse::{InverseChip, InverseConfig};
+    use halo2_proofs::arithmetic::FieldExt;
+    use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner};
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::pairing::bn256::Fr;
+    use halo2_proofs::pairing::group::ff::Field;
+    use halo2_proofs::plonk::{Circuit, ConstraintSystem, Error};
+
+    // checked 时暴露 1 / b, b == 0, a / b, b == 0; 否则暴露 1 / b, a / b
+    #[derive(Default)]
+    pub struct DivCircuit<F: FieldExt> {
+        a: Option<F>,
+        b: Option<F>,
+        checked: bool,
+    }
+
+    impl<F: FieldExt> Circuit<F> for DivCircuit<F> {
+        type Config = InverseConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                a: None,
+                b: None,
+                checked: self.checked,
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            InverseChip::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let chip = InverseChip::construct(config);
+            let a = chip.load_private(layouter.namespace(|| "a"), self.a)?;
+            let b = chip.load_private(layouter.namespace(|| "b"), self.b)?;
+            let outputs = if self.checked {
+                let (inv, z0) = chip.inv_checked(layouter.namespace(|| "1 / b"), &b)?;
+                let (out, z1) = chip.div_checked(layouter.namespace(|| "a / b"), &a, &b)?;
+                vec![inv, z0.0, out, z1.0]
+            } else {
+                let inv = chip.inv(layouter.namespace(|| "1 / b"), &b)?;
+                let out = chip.div(layouter.namespace(|| "a / b"), &a, &b)?;
+                vec![inv, out]
+            };
+            for (i, cell) in outputs.iter().enumerate() {
+                chip.expose_public(layouter.namespace(|| "out"), cell, i)?;
+            }
+            Ok(())
+        }
+    }
+
+    fn run(a: Fr, b: Fr, checked: bool, instance: Vec<Fr>) -> Option<bool> {
+        let circuit = DivCircuit {
+            a: Some(a),
+            b: Some(b),
+            checked,
+        };
+        // 生成 witness 失败时返回 None
+        let prover = MockProver::run(4, &circuit, vec![instance]).ok()?;
+        Some(prover.verify().is_ok())
+    }
+
+    #[test]
+    pub fn test_inv_div() {
+        let a = Fr::from(21u64);
+        let b = Fr::from(7u64);
+        let inv = b.invert().unwrap();
+        assert_eq!(run(a, b, false, vec![inv, Fr::from(3u64)]), Some(true));
+        assert_eq!(run(a, b, false, vec![inv, Fr::from(4u64)]), Some(false));
+        assert_eq!(run(a, b, false, vec![b, Fr::from(3u64)]), Some(false));
+        // 除以0
+        assert!(run(a, Fr::zero(), false, vec![Fr::zero(), Fr::zero()]).is_none());
+    }
+
+    #[test]
+    pub fn test_checked() {
+        let a = Fr::from(21u64);
+        let b = Fr::from(7u64);
+        let inv = b.invert().unwrap();
+        let (zero, one) = (Fr::zero(), Fr::one());
+        assert_eq!(
+            run(a, b, true, vec![inv, zero, Fr::from(3u64), zero]),
+            Some(true)
+        );
+        assert_eq!(
+            run(a, b, true, vec![inv, one, Fr::from(3u64), one]),
+            Some(false)
+        );
+
+        assert_eq!(run(a, zero, true, vec![zero, one, zero, one]), Some(true));
+        assert_eq!(
+            run(a, zero, true, vec![zero, zero, zero, zero]),
+            Some(false)
+        );
+        assert_eq!(run(a, zero, true, vec![one, one, a, one]), Some(false));
+    }
+}
@@ -0,0 +1,236 @@
+use crate::mydemo::boolean::AssignedBool;
+use halo2_proofs::arithmetic::FieldExt;
+use halo2_proofs::circuit::{AssignedCell, Layouter};
+use halo2_proofs::plonk::{
+    Advice, Column, ConstraintSystem, Error, Expression, Fixed, Instance, Selector,
+};
+use halo2_proofs::poly::Rotation;
+use std::marker::PhantomData;
+
+// 比较 a 和 b, 结果是一个bool, 不像 AEqBChip 那样直接约束 a == b
+// a | b | inv | out | s_is_equal
+// out = 1 - (a - b) * inv
+// (a - b) * out = 0
+// a != b 时 out 只能是0, 这时 inv = 1 / (a - b) 才能满足第一个约束;
+// a == b 时第一个约束直接得到 out = 1
+// is_zero 就是 b 取常数0
+#[derive(Clone, Debug)]
+pub struct IsZeroConfig {
+    pub a: Column<Advice>,
+    pub b: Column<Advice>,
+    pub inv: Column<Advice>,
+    pub out: Column<Advice>,
+    pub constant: Column<Fixed>,
+    pub s_is_equal: Selector,
+    pub instance: Column<Instance>,
+}
+
+pub struct IsZeroChip<F: FieldExt> {
+    config: IsZeroConfig,
+    _p: PhantomData<F>,
+}
+
+impl<F: FieldExt> IsZeroChip<F> {
+    pub fn construct(config: IsZeroConfig) -> Self {
+        Self {
+            config,
+            _p: Default::default(),
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> IsZeroConfig {
+        let a = meta.advice_column();
+        let b = meta.advice_column();
+        let inv = meta.advice_column();
+        let out = meta.advice_column();
+        let constant = meta.fixed_column();
+        let s_is_equal = meta.selector();
+        let instance = meta.instance_column();
+
+        for column in [a, b, out] {
+            meta.enable_equality(column);
+        }
+        meta.enable_equality(instance);
+        meta.enable_constant(constant);
+
+        meta.create_gate("is equal", |meta| {
+            let s = meta.query_selector(s_is_equal);
+            let a = meta.query_advice(a, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::cur());
+            let inv = meta.query_advice(inv, Rotation::cur());
+            let out = meta.query_advice(out, Rotation::cur());
+            let diff = a - b;
+            vec![
+                s.clone() * (out.clone() + diff.clone() * inv - Expression::Constant(F::one())),
+                s * diff * out,
+            ]
+        });
+
+        IsZeroConfig {
+            a,
+            b,
+            inv,
+            out,
+            constant,
+            s_is_equal,
+            instance,
+        }
+    }
+
+    pub fn load_private(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Option<F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        layouter.assign_region(
+            || "load private",
+            |mut region| {
+                region.assign_advice(
+                    || "private",
+                    self.config.a,
+                    0,
+                    || value.ok_or(Error::Synthesis),
+                )
+            },
+        )
+    }
+
+    // b 为 None 时用常数0
+    fn compare(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: Option<&AssignedCell<F, F>>,
+    ) -> Result<AssignedBool<F>, Error> {
+        layouter.assign_region(
+            || "is equal",
+            |mut region| {
+                self.config.s_is_equal.enable(&mut region, 0)?;
+                a.copy_advice(|| "a", &mut region, self.config.a, 0)?;
+                let b = match b {
+                    Some(b) => b.copy_advice(|| "b", &mut region, self.config.b, 0)?,
+                    None => region.assign_advice_from_constant(
+                        || "zero",
+                        self.config.b,
+                        0,
+                        F::zero(),
+                    )?,
+                };
+
+                let diff = a.value().and_then(|a| b.value().map(|b| *a - *b));
+                // 0 没有逆, inv 随便取, 这里取0
+                let inv = diff.map(|d| d.invert().unwrap_or(F::zero()));
+                let out = diff.map(|d| if d == F::zero() { F::one() } else { F::zero() });
+                region.assign_advice(
+                    || "inv",
+                    self.config.inv,
+                    0,
+                    || inv.ok_or(Error::Synthesis),
+                )?;
+                region
+                    .assign_advice(|| "out", self.config.out, 0, || out.ok_or(Error::Synthesis))
+                    .map(AssignedBool)
+            },
+        )
+    }
+
+    pub fn is_zero(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+    ) -> Result<AssignedBool<F>, Error> {
+        self.compare(layouter, a, None)
+    }
+
+    pub fn is_equal(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+    ) -> Result<AssignedBool<F>, Error> {
+        self.compare(layouter, a, Some(b))
+    }
+
+    pub fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: &AssignedBool<F>,
+        row: usize,
+    ) -> Result<(), Error> {
+        layouter.constrain_instance(cell.0.cell(), self.config.instance, row)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mydemo::is_zero::{IsZeroChip, IsZeroConfig};
+    use halo2_proofs::arithmetic::FieldExt;
+    use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner};
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::pairing::bn256::Fr;
+    use halo2_proofs::plonk::{Circuit, ConstraintSystem, Error};
+
+    // 暴露 a == 0, a == b
+    #[derive(Default)]
+    pub struct CompareCircuit<F: FieldExt> {
+        a: Option<F>,
+        b: Option<F>,
+    }
+
+    impl<F: FieldExt> Circuit<F> for CompareCircuit<F> {
+        type Config = IsZeroConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            IsZeroChip::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let chip = IsZeroChip::construct(config);
+            let a = chip.load_private(layouter.namespace(|| "a"), self.a)?;
+            let b = chip.load_private(layouter.namespace(|| "b"), self.b)?;
+            let is_zero = chip.is_zero(layouter.namespace(|| "a == 0"), &a)?;
+            let is_equal = chip.is_equal(layouter.namespace(|| "a == b"), &a, &b)?;
+            chip.expose_public(layouter.namespace(|| "is zero"), &is_zero, 0)?;
+            chip.expose_public(layouter.namespace(|| "is equal"), &is_equal, 1)
+        }
+    }
+
+    fn prove(a: Fr, b: Fr, is_zero: bool, is_equal: bool) -> bool {
+        let circuit = CompareCircuit {
+            a: Some(a),
+            b: Some(b),
+        };
+        let instance = vec![Fr::from(is_zero as u64), Fr::from(is_equal as u64)];
+        let prover = MockProver::run(4, &circuit, vec![instance]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    pub fn test_is_zero() {
+        assert!(prove(Fr::zero(), Fr::one(), true, false));
+        assert!(prove(Fr::one(), Fr::one(), false, true));
+        assert!(prove(-Fr::one(), Fr::zero(), false, false));
+        assert!(prove(Fr::zero(), Fr::zero(), true, true));
+
+        assert!(!prove(Fr::zero(), Fr::one(), false, false));
+        assert!(!prove(Fr::one(), Fr::one(), true, true));
+    }
+
+    #[test]
+    pub fn test_is_equal() {
+        let a = Fr::from(12345u64);
+        assert!(prove(a, a, false, true));
+        assert!(prove(a, a + Fr::one(), false, false));
+        assert!(!prove(a, a + Fr::one(), false, true));
+        assert!(!prove(a, a, false, false));
+    }
+}
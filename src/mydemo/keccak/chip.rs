@@ -0,0 +1,245 @@
+use crate::mydemo::keccak::native::{padding, pi, RATE, RC, ROTATIONS};
+use crate::mydemo::word::{AssignedWord, CsByteHasher, U64Chip, WordConfig};
+use halo2_proofs::arithmetic::FieldExt;
+use halo2_proofs::circuit::Layouter;
+use halo2_proofs::plonk::{ConstraintSystem, Error};
+
+// Keccak-f[1600] 和 Keccak-256, lane 是 U64Chip 的 word
+// theta / chi 的异或, 与, 取反都是按byte查 xor table, rho 的循环移位查 split table
+// 消息的长度在生成电路时确定, 不同长度的消息对应不同的电路
+pub struct KeccakChip<F: FieldExt> {
+    word: U64Chip<F>,
+}
+
+impl<F: FieldExt> KeccakChip<F> {
+    pub fn construct(config: WordConfig<F>) -> Self {
+        Self {
+            word: U64Chip::construct(config),
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> WordConfig<F> {
+        U64Chip::configure(meta)
+    }
+
+    pub fn load_tables(&self, layouter: impl Layouter<F>) -> Result<(), Error> {
+        self.word.load_tables(layouter)
+    }
+
+    fn round(
+        &self,
+        mut layouter: impl Layouter<F>,
+        state: Vec<AssignedWord<F>>,
+        rc: &AssignedWord<F>,
+    ) -> Result<Vec<AssignedWord<F>>, Error> {
+        let word = &self.word;
+
+        // theta
+        let mut c = Vec::with_capacity(5);
+        for x in 0..5 {
+            let mut acc = state[x].clone();
+            for y in 1..5 {
+                acc = word.xor(layouter.namespace(|| "c"), &acc, &state[x + 5 * y])?;
+            }
+            c.push(acc);
+        }
+        let mut d = Vec::with_capacity(5);
+        for x in 0..5 {
+            let rot = word.rotl(layouter.namespace(|| "rotl c"), &c[(x + 1) % 5], 1)?;
+            d.push(word.xor(layouter.namespace(|| "d"), &c[(x + 4) % 5], &rot)?);
+        }
+        let state = state
+            .iter()
+            .enumerate()
+            .map(|(i, lane)| word.xor(layouter.namespace(|| "theta"), lane, &d[i % 5]))
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        // rho + pi
+        let mut b = state.clone();
+        for x in 0..5 {
+            for y in 0..5 {
+                let lane = &state[x + 5 * y];
+                b[pi(x, y)] = match ROTATIONS[x][y] {
+                    0 => lane.clone(),
+                    r => word.rotl(layouter.namespace(|| "rho"), lane, r)?,
+                };
+            }
+        }
+
+        // chi
+        let mut state = Vec::with_capacity(25);
+        for y in 0..5 {
+            for x in 0..5 {
+                let not = word.not(layouter.namespace(|| "!b"), &b[(x + 1) % 5 + 5 * y])?;
+                let and = word.and(
+                    layouter.namespace(|| "!b & c"),
+                    &not,
+                    &b[(x + 2) % 5 + 5 * y],
+                )?;
+                state.push(word.xor(layouter.namespace(|| "chi"), &b[x + 5 * y], &and)?);
+            }
+        }
+
+        // iota
+        state[0] = word.xor(layouter.namespace(|| "iota"), &state[0], rc)?;
+        Ok(state)
+    }
+
+    pub fn permute(
+        &self,
+        mut layouter: impl Layouter<F>,
+        state: Vec<AssignedWord<F>>,
+        rc: &[AssignedWord<F>],
+    ) -> Result<Vec<AssignedWord<F>>, Error> {
+        assert_eq!(state.len(), 25);
+        rc.iter().enumerate().try_fold(state, |state, (i, rc)| {
+            self.round(layouter.namespace(|| format!("round {}", i)), state, rc)
+        })
+    }
+
+    pub fn load_round_constants(
+        &self,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<Vec<AssignedWord<F>>, Error> {
+        RC.iter()
+            .map(|rc| self.word.load_constant(layouter.namespace(|| "rc"), *rc))
+            .collect()
+    }
+
+    // 返回4个 lane, 每个 lane 的 bytes 按顺序拼起来就是32 byte 的 digest
+    pub fn digest(
+        &self,
+        mut layouter: impl Layouter<F>,
+        message: &[Option<u8>],
+    ) -> Result<Vec<AssignedWord<F>>, Error> {
+        let word = &self.word;
+        let mut bytes = word.load_bytes(layouter.namespace(|| "message"), message)?;
+        bytes.extend(
+            word.load_constant_bytes(layouter.namespace(|| "padding"), &padding(message.len()))?,
+        );
+        let rc = self.load_round_constants(layouter.namespace(|| "round constants"))?;
+
+        let zero = word.load_constant(layouter.namespace(|| "zero"), 0)?;
+        let mut state = vec![zero; 25];
+        for (i, block) in bytes.chunks(RATE).enumerate() {
+            let mut layouter = layouter.namespace(|| format!("block {}", i));
+            // lane 是小端序, 和 word 的 bytes 顺序一致
+            for (j, lane) in block.chunks(8).enumerate() {
+                let lane = word.compose_bytes(layouter.namespace(|| "lane"), lane)?;
+                state[j] = word.xor(layouter.namespace(|| "absorb"), &state[j], &lane)?;
+            }
+            state = self.permute(layouter.namespace(|| "keccak-f"), state, &rc)?;
+        }
+        state.truncate(4);
+        Ok(state)
+    }
+}
+
+impl<F: FieldExt> CsByteHasher<F> for KeccakChip<F> {
+    fn configure(meta: &mut ConstraintSystem<F>) -> WordConfig<F> {
+        KeccakChip::configure(meta)
+    }
+
+    fn construct(config: WordConfig<F>) -> Self {
+        KeccakChip::construct(config)
+    }
+
+    fn load_tables(&self, layouter: impl Layouter<F>) -> Result<(), Error> {
+        KeccakChip::load_tables(self, layouter)
+    }
+
+    fn digest(
+        &self,
+        layouter: impl Layouter<F>,
+        message: &[Option<u8>],
+    ) -> Result<Vec<AssignedWord<F>>, Error> {
+        KeccakChip::digest(self, layouter, message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mydemo::keccak::chip::KeccakChip;
+    use crate::mydemo::keccak::native::keccak256;
+    use crate::mydemo::word::WordConfig;
+    use halo2_proofs::arithmetic::FieldExt;
+    use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner};
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::pairing::bn256::Fr;
+    use halo2_proofs::plonk::{Circuit, Column, ConstraintSystem, Error, Instance};
+
+    #[derive(Clone, Debug)]
+    pub struct KeccakConfig<F: FieldExt> {
+        word: WordConfig<F>,
+        instance: Column<Instance>,
+    }
+
+    pub struct KeccakCircuit {
+        message: Vec<Option<u8>>,
+    }
+
+    impl<F: FieldExt> Circuit<F> for KeccakCircuit {
+        type Config = KeccakConfig<F>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                message: vec![None; self.message.len()],
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            KeccakConfig {
+                word: KeccakChip::configure(meta),
+                instance,
+            }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let chip = KeccakChip::construct(config.word);
+            chip.load_tables(layouter.namespace(|| "tables"))?;
+            let digest = chip.digest(layouter.namespace(|| "keccak256"), &self.message)?;
+            for (i, lane) in digest.iter().enumerate() {
+                layouter.constrain_instance(lane.value.cell(), config.instance, i)?;
+            }
+            Ok(())
+        }
+    }
+
+    fn prove(message: &[u8], digest: [u8; 32]) -> bool {
+        let circuit = KeccakCircuit {
+            message: message.iter().map(|b| Some(*b)).collect(),
+        };
+        let instance = digest
+            .chunks(8)
+            .map(|lane| Fr::from(u64::from_le_bytes(lane.try_into().unwrap())))
+            .collect();
+        let prover = MockProver::run(17, &circuit, vec![instance]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    pub fn test_keccak256() {
+        let long: Vec<u8> = (0..200u32).map(|i| (i * 7 + 3) as u8).collect();
+        for message in [b"".to_vec(), b"abc".to_vec(), long] {
+            assert!(
+                prove(&message, keccak256(&message)),
+                "len {}",
+                message.len()
+            );
+        }
+    }
+
+    #[test]
+    pub fn test_wrong_digest() {
+        let mut digest = keccak256(b"abc");
+        digest[31] ^= 0x80;
+        assert!(!prove(b"abc", digest));
+    }
+}
@@ -0,0 +1,153 @@
+// Keccak-f[1600] 和以太坊用的 Keccak-256 (padding 是 0x01, 不是 SHA3 的 0x06)
+// state 是 5x5 个 u64, 下标是 x + 5 * y
+pub const ROUNDS: usize = 24;
+
+// rate = 1600 - 2 * 256 bits
+pub const RATE: usize = 136;
+
+pub const RC: [u64; ROUNDS] = [
+    0x0000000000000001,
+    0x0000000000008082,
+    0x800000000000808a,
+    0x8000000080008000,
+    0x000000000000808b,
+    0x0000000080000001,
+    0x8000000080008081,
+    0x8000000000008009,
+    0x000000000000008a,
+    0x0000000000000088,
+    0x0000000080008009,
+    0x000000008000000a,
+    0x000000008000808b,
+    0x800000000000008b,
+    0x8000000000008089,
+    0x8000000000008003,
+    0x8000000000008002,
+    0x8000000000000080,
+    0x000000000000800a,
+    0x800000008000000a,
+    0x8000000080008081,
+    0x8000000000008080,
+    0x0000000080000001,
+    0x8000000080008008,
+];
+
+// rho 的循环左移位数, ROTATIONS[x][y]
+pub const ROTATIONS: [[usize; 5]; 5] = [
+    [0, 36, 3, 41, 18],
+    [1, 44, 10, 45, 2],
+    [62, 6, 43, 15, 61],
+    [28, 55, 25, 21, 56],
+    [27, 20, 39, 8, 14],
+];
+
+// pi: (x, y) 移到 (y, 2x + 3y)
+pub fn pi(x: usize, y: usize) -> usize {
+    y + 5 * ((2 * x + 3 * y) % 5)
+}
+
+pub fn keccak_f(state: &mut [u64; 25]) {
+    for rc in RC {
+        // theta
+        let c: Vec<u64> = (0..5)
+            .map(|x| (0..5).fold(0, |acc, y| acc ^ state[x + 5 * y]))
+            .collect();
+        for x in 0..5 {
+            let d = c[(x + 4) % 5] ^ c[(x + 1) % 5].rotate_left(1);
+            for y in 0..5 {
+                state[x + 5 * y] ^= d;
+            }
+        }
+        // rho + pi
+        let mut b = [0u64; 25];
+        for x in 0..5 {
+            for y in 0..5 {
+                b[pi(x, y)] = state[x + 5 * y].rotate_left(ROTATIONS[x][y] as u32);
+            }
+        }
+        // chi
+        for x in 0..5 {
+            for y in 0..5 {
+                state[x + 5 * y] =
+                    b[x + 5 * y] ^ (!b[(x + 1) % 5 + 5 * y] & b[(x + 2) % 5 + 5 * y]);
+            }
+        }
+        // iota
+        state[0] ^= rc;
+    }
+}
+
+// pad10*1: 补 0x01 ... 0x80, 只差一个byte时就是 0x81
+pub fn padding(len: usize) -> Vec<u8> {
+    let mut padding = vec![0u8; RATE - len % RATE];
+    padding[0] |= 0x01;
+    *padding.last_mut().unwrap() |= 0x80;
+    padding
+}
+
+pub fn keccak256(message: &[u8]) -> [u8; 32] {
+    let mut padded = message.to_vec();
+    padded.extend(padding(message.len()));
+
+    let mut state = [0u64; 25];
+    for block in padded.chunks(RATE) {
+        for (lane, bytes) in state.iter_mut().zip(block.chunks(8)) {
+            *lane ^= u64::from_le_bytes(bytes.try_into().unwrap());
+        }
+        keccak_f(&mut state);
+    }
+
+    let mut digest = [0u8; 32];
+    for (i, lane) in state[..4].iter().enumerate() {
+        digest[8 * i..8 * (i + 1)].copy_from_slice(&lane.to_le_bytes());
+    }
+    digest
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mydemo::keccak::native::{keccak256, keccak_f};
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    pub fn test_keccak_f() {
+        // 全0 state 的 Keccak-f[1600] 第一个 lane
+        let mut state = [0u64; 25];
+        keccak_f(&mut state);
+        assert_eq!(state[0], 0xf1258f7940e1dde7);
+        assert_eq!(state[24], 0xeaf1ff7b5ceca249);
+    }
+
+    #[test]
+    pub fn test_keccak256() {
+        let block: Vec<u8> = (0..136u32).map(|i| i as u8).collect();
+        let long: Vec<u8> = (0..200u32).map(|i| (i * 7 + 3) as u8).collect();
+        let vectors = [
+            (
+                b"".to_vec(),
+                "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470",
+            ),
+            (
+                b"abc".to_vec(),
+                "4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45",
+            ),
+            (
+                block,
+                "7ce759f1ab7f9ce437719970c26b0a66ff11fe3e38e17df89cf5d29c7d7f807e",
+            ),
+            (
+                long,
+                "66d2cdf3ab4c5bd3c75add9b60b14ac5b7789534fa2da3f348853b847359a3a0",
+            ),
+        ];
+        for (message, digest) in vectors {
+            assert_eq!(keccak256(&message).to_vec(), hex(digest));
+        }
+    }
+}
@@ -0,0 +1,450 @@
+use crate::mydemo::arithmetic::ArithmeticInstructions;
+use halo2_proofs::arithmetic::FieldExt;
+use halo2_proofs::circuit::{AssignedCell, Layouter};
+use halo2_proofs::plonk::{Advice, Column, ConstraintSystem, Error, Fixed, Instance};
+use halo2_proofs::poly::Rotation;
+use std::marker::PhantomData;
+
+// 标准的 PLONK gate:
+// q_l * a + q_r * b + q_m * a * b + q_o * c + q_c = 0
+// a | b | c | q_l | q_r | q_m | q_o | q_c
+// 系数都是0的行没有约束, 所以不需要 selector
+// 新的运算只需要换一组系数, 不用再写 create_gate
+#[derive(Clone, Debug)]
+pub struct MainGateConfig {
+    pub a: Column<Advice>,
+    pub b: Column<Advice>,
+    pub c: Column<Advice>,
+    pub q_l: Column<Fixed>,
+    pub q_r: Column<Fixed>,
+    pub q_m: Column<Fixed>,
+    pub q_o: Column<Fixed>,
+    pub q_c: Column<Fixed>,
+    pub instance: Column<Instance>,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Coefficients<F: FieldExt> {
+    pub l: F,
+    pub r: F,
+    pub m: F,
+    pub o: F,
+    pub c: F,
+}
+
+impl<F: FieldExt> Default for Coefficients<F> {
+    fn default() -> Self {
+        Self {
+            l: F::zero(),
+            r: F::zero(),
+            m: F::zero(),
+            o: F::zero(),
+            c: F::zero(),
+        }
+    }
+}
+
+// 一行里的 a, b, c: 已经赋值过的cell会被复制过来, 否则是新的witness
+#[derive(Clone, Copy, Debug)]
+pub enum Term<'a, F: FieldExt> {
+    Assigned(&'a AssignedCell<F, F>),
+    Unassigned(Option<F>),
+}
+
+impl<'a, F: FieldExt> Term<'a, F> {
+    pub fn value(&self) -> Option<F> {
+        match self {
+            Term::Assigned(cell) => cell.value().cloned(),
+            Term::Unassigned(value) => *value,
+        }
+    }
+
+    pub fn zero() -> Self {
+        Term::Unassigned(Some(F::zero()))
+    }
+}
+
+pub struct MainGateChip<F: FieldExt> {
+    config: MainGateConfig,
+    _p: PhantomData<F>,
+}
+
+impl<F: FieldExt> MainGateChip<F> {
+    pub fn construct(config: MainGateConfig) -> Self {
+        Self {
+            config,
+            _p: Default::default(),
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> MainGateConfig {
+        let a = meta.advice_column();
+        let b = meta.advice_column();
+        let c = meta.advice_column();
+        let q_l = meta.fixed_column();
+        let q_r = meta.fixed_column();
+        let q_m = meta.fixed_column();
+        let q_o = meta.fixed_column();
+        let q_c = meta.fixed_column();
+        let instance = meta.instance_column();
+
+        for column in [a, b, c] {
+            meta.enable_equality(column);
+        }
+        meta.enable_equality(instance);
+
+        meta.create_gate("main gate", |meta| {
+            let a = meta.query_advice(a, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::cur());
+            let c = meta.query_advice(c, Rotation::cur());
+            let q_l = meta.query_fixed(q_l, Rotation::cur());
+            let q_r = meta.query_fixed(q_r, Rotation::cur());
+            let q_m = meta.query_fixed(q_m, Rotation::cur());
+            let q_o = meta.query_fixed(q_o, Rotation::cur());
+            let q_c = meta.query_fixed(q_c, Rotation::cur());
+            vec![q_l * a.clone() + q_r * b.clone() + q_m * a * b + q_o * c + q_c]
+        });
+
+        MainGateConfig {
+            a,
+            b,
+            c,
+            q_l,
+            q_r,
+            q_m,
+            q_o,
+            q_c,
+            instance,
+        }
+    }
+
+    // 用一组系数约束一行, 返回这一行的 a, b, c
+    pub fn apply(
+        &self,
+        mut layouter: impl Layouter<F>,
+        terms: [Term<'_, F>; 3],
+        coeffs: Coefficients<F>,
+    ) -> Result<[AssignedCell<F, F>; 3], Error> {
+        layouter.assign_region(
+            || "main gate",
+            |mut region| {
+                let config = &self.config;
+                let fixed = [
+                    (config.q_l, coeffs.l),
+                    (config.q_r, coeffs.r),
+                    (config.q_m, coeffs.m),
+                    (config.q_o, coeffs.o),
+                    (config.q_c, coeffs.c),
+                ];
+                for (column, value) in fixed {
+                    region.assign_fixed(|| "coefficient", column, 0, || Ok(value))?;
+                }
+
+                let mut cells = Vec::with_capacity(3);
+                for (term, column) in terms.iter().zip([config.a, config.b, config.c]) {
+                    let cell = match term {
+                        Term::Assigned(cell) => {
+                            cell.copy_advice(|| "copy", &mut region, column, 0)?
+                        }
+                        Term::Unassigned(value) => region.assign_advice(
+                            || "witness",
+                            column,
+                            0,
+                            || value.ok_or(Error::Synthesis),
+                        )?,
+                    };
+                    cells.push(cell);
+                }
+                let c = cells.pop().unwrap();
+                let b = cells.pop().unwrap();
+                let a = cells.pop().unwrap();
+                Ok([a, b, c])
+            },
+        )
+    }
+
+    // 没有约束, 只是赋值
+    pub fn load_private(
+        &self,
+        layouter: impl Layouter<F>,
+        value: Option<F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let [a, _, _] = self.apply(
+            layouter,
+            [Term::Unassigned(value), Term::zero(), Term::zero()],
+            Coefficients::default(),
+        )?;
+        Ok(a)
+    }
+
+    // a - v = 0
+    pub fn load_constant(
+        &self,
+        layouter: impl Layouter<F>,
+        value: F,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let [a, _, _] = self.apply(
+            layouter,
+            [Term::Unassigned(Some(value)), Term::zero(), Term::zero()],
+            Coefficients {
+                l: F::one(),
+                c: -value,
+                ..Default::default()
+            },
+        )?;
+        Ok(a)
+    }
+
+    // a + b - c = 0
+    pub fn add(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let c = a.value().and_then(|a| b.value().map(|b| *a + *b));
+        let [_, _, c] = self.apply(
+            layouter,
+            [Term::Assigned(a), Term::Assigned(b), Term::Unassigned(c)],
+            Coefficients {
+                l: F::one(),
+                r: F::one(),
+                o: -F::one(),
+                ..Default::default()
+            },
+        )?;
+        Ok(c)
+    }
+
+    // a - b - c = 0
+    pub fn sub(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let c = a.value().and_then(|a| b.value().map(|b| *a - *b));
+        let [_, _, c] = self.apply(
+            layouter,
+            [Term::Assigned(a), Term::Assigned(b), Term::Unassigned(c)],
+            Coefficients {
+                l: F::one(),
+                r: -F::one(),
+                o: -F::one(),
+                ..Default::default()
+            },
+        )?;
+        Ok(c)
+    }
+
+    // a * b - c = 0
+    pub fn mul(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let c = a.value().and_then(|a| b.value().map(|b| *a * *b));
+        let [_, _, c] = self.apply(
+            layouter,
+            [Term::Assigned(a), Term::Assigned(b), Term::Unassigned(c)],
+            Coefficients {
+                m: F::one(),
+                o: -F::one(),
+                ..Default::default()
+            },
+        )?;
+        Ok(c)
+    }
+
+    // a - b = 0
+    pub fn assert_equal(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+    ) -> Result<(), Error> {
+        self.apply(
+            layouter,
+            [Term::Assigned(a), Term::Assigned(b), Term::zero()],
+            Coefficients {
+                l: F::one(),
+                r: -F::one(),
+                ..Default::default()
+            },
+        )?;
+        Ok(())
+    }
+
+    pub fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: &AssignedCell<F, F>,
+        row: usize,
+    ) -> Result<(), Error> {
+        layouter.constrain_instance(cell.cell(), self.config.instance, row)
+    }
+}
+
+impl<F: FieldExt> ArithmeticInstructions<F> for MainGateChip<F> {
+    type Config = MainGateConfig;
+    type Num = AssignedCell<F, F>;
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> MainGateConfig {
+        MainGateChip::configure(meta)
+    }
+
+    fn construct(config: MainGateConfig) -> Self {
+        MainGateChip::construct(config)
+    }
+
+    fn load_private(
+        &self,
+        layouter: impl Layouter<F>,
+        value: Option<F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        MainGateChip::load_private(self, layouter, value)
+    }
+
+    fn constant(&self, layouter: impl Layouter<F>, value: F) -> Result<AssignedCell<F, F>, Error> {
+        self.load_constant(layouter, value)
+    }
+
+    fn add(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        MainGateChip::add(self, layouter, a, b)
+    }
+
+    fn sub(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        MainGateChip::sub(self, layouter, a, b)
+    }
+
+    fn mul(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        MainGateChip::mul(self, layouter, a, b)
+    }
+
+    // -a - c = 0
+    fn neg(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let c = a.value().map(|a| -*a);
+        let [_, _, c] = self.apply(
+            layouter,
+            [Term::Assigned(a), Term::zero(), Term::Unassigned(c)],
+            Coefficients {
+                l: -F::one(),
+                o: -F::one(),
+                ..Default::default()
+            },
+        )?;
+        Ok(c)
+    }
+
+    fn assert_equal(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+    ) -> Result<(), Error> {
+        MainGateChip::assert_equal(self, layouter, a, b)
+    }
+
+    fn expose_public(
+        &self,
+        layouter: impl Layouter<F>,
+        num: &AssignedCell<F, F>,
+        row: usize,
+    ) -> Result<(), Error> {
+        MainGateChip::expose_public(self, layouter, num, row)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mydemo::main_gate::{MainGateChip, MainGateConfig};
+    use halo2_proofs::arithmetic::FieldExt;
+    use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner};
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::pairing::bn256::Fr;
+    use halo2_proofs::plonk::{Circuit, ConstraintSystem, Error};
+
+    // out = (a + b) * a - 3, 并且检查 b - a == d
+    #[derive(Default)]
+    pub struct MainGateCircuit<F: FieldExt> {
+        a: Option<F>,
+        b: Option<F>,
+        d: Option<F>,
+    }
+
+    impl<F: FieldExt> Circuit<F> for MainGateCircuit<F> {
+        type Config = MainGateConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            MainGateChip::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let chip = MainGateChip::construct(config);
+            let a = chip.load_private(layouter.namespace(|| "a"), self.a)?;
+            let b = chip.load_private(layouter.namespace(|| "b"), self.b)?;
+            let d = chip.load_private(layouter.namespace(|| "d"), self.d)?;
+            let three = chip.load_constant(layouter.namespace(|| "3"), F::from(3u64))?;
+
+            let sum = chip.add(layouter.namespace(|| "a + b"), &a, &b)?;
+            let product = chip.mul(layouter.namespace(|| "(a + b) * a"), &sum, &a)?;
+            let out = chip.sub(layouter.namespace(|| "- 3"), &product, &three)?;
+            let diff = chip.sub(layouter.namespace(|| "b - a"), &b, &a)?;
+            chip.assert_equal(layouter.namespace(|| "b - a == d"), &diff, &d)?;
+            chip.expose_public(layouter.namespace(|| "out"), &out, 0)
+        }
+    }
+
+    fn run(a: u64, b: u64, d: u64, out: Fr) -> bool {
+        let circuit = MainGateCircuit::<Fr> {
+            a: Some(Fr::from(a)),
+            b: Some(Fr::from(b)),
+            d: Some(Fr::from(d)),
+        };
+        let prover = MockProver::run(5, &circuit, vec![vec![out]]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    pub fn test_main_gate() {
+        // (2 + 5) * 2 - 3 = 11
+        assert!(run(2, 5, 3, Fr::from(11u64)));
+        // 结果可以是负数
+        assert!(run(0, 1, 1, -Fr::from(3u64)));
+    }
+
+    #[test]
+    pub fn test_wrong() {
+        assert!(!run(2, 5, 3, Fr::from(12u64)));
+        assert!(!run(2, 5, 4, Fr::from(11u64)));
+    }
+}
@@ -0,0 +1,358 @@
+use crate::mydemo::mimc::sc_box::CsHasher;
+use halo2_proofs::arithmetic::FieldExt;
+use halo2_proofs::circuit::{AssignedCell, Layouter};
+use halo2_proofs::plonk::{
+    Advice, Column, ConstraintSystem, Error, Expression, Instance, Selector,
+};
+use halo2_proofs::poly::Rotation;
+
+// cur | sibling | direction | left | right | s
+// direction 为1时交换:
+// direction * (1 - direction) = 0
+// left = cur + direction * (sibling - cur)
+// right = sibling + direction * (cur - sibling)
+#[derive(Clone, Debug)]
+pub struct MerkleConfig<C> {
+    pub cur: Column<Advice>,
+    pub sibling: Column<Advice>,
+    pub direction: Column<Advice>,
+    pub left: Column<Advice>,
+    pub right: Column<Advice>,
+    pub s: Selector,
+    pub instance: Column<Instance>,
+    pub hasher: C,
+}
+
+// 按顺序排好的左右两个孩子
+pub type Children<F> = (AssignedCell<F, F>, AssignedCell<F, F>);
+
+// 电路里的merkle path, 同一条path可以用来算多个root, 比如更新前后的root
+#[derive(Clone, Debug)]
+pub struct AssignedPath<F: FieldExt> {
+    pub siblings: Vec<AssignedCell<F, F>>,
+    pub directions: Vec<AssignedCell<F, F>>,
+}
+
+// 验证merkle path, 在电路里从叶子算出root
+// 哈希是类型参数 H, 比如 PoseidonChip 或者 MiMCSpongeChip
+pub struct MerkleChip<F: FieldExt, H: CsHasher<F>> {
+    config: MerkleConfig<H::Config>,
+    hasher: H,
+}
+
+impl<F: FieldExt, H: CsHasher<F>> MerkleChip<F, H> {
+    pub fn construct(config: MerkleConfig<H::Config>) -> Self {
+        Self {
+            hasher: H::construct(config.hasher.clone()),
+            config,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> MerkleConfig<H::Config> {
+        let cur = meta.advice_column();
+        let sibling = meta.advice_column();
+        let direction = meta.advice_column();
+        let left = meta.advice_column();
+        let right = meta.advice_column();
+        let s = meta.selector();
+        let instance = meta.instance_column();
+
+        meta.enable_equality(cur);
+        meta.enable_equality(sibling);
+        meta.enable_equality(direction);
+        meta.enable_equality(left);
+        meta.enable_equality(right);
+        meta.enable_equality(instance);
+
+        meta.create_gate("merkle swap", |meta| {
+            let cur = meta.query_advice(cur, Rotation::cur());
+            let sibling = meta.query_advice(sibling, Rotation::cur());
+            let direction = meta.query_advice(direction, Rotation::cur());
+            let left = meta.query_advice(left, Rotation::cur());
+            let right = meta.query_advice(right, Rotation::cur());
+            let s = meta.query_selector(s);
+            let one = Expression::Constant(F::one());
+            vec![
+                s.clone() * direction.clone() * (one - direction.clone()),
+                s.clone()
+                    * (cur.clone() + direction.clone() * (sibling.clone() - cur.clone()) - left),
+                s * (sibling.clone() + direction * (cur - sibling) - right),
+            ]
+        });
+
+        MerkleConfig {
+            cur,
+            sibling,
+            direction,
+            left,
+            right,
+            s,
+            instance,
+            hasher: H::configure(meta),
+        }
+    }
+
+    pub fn load_leaf(
+        &self,
+        layouter: impl Layouter<F>,
+        leaf: Option<F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        self.hasher.load_private(layouter, leaf)
+    }
+
+    // directions 是 MerklePath::direction_values, 在 swap 的时候会检查是0或者1
+    pub fn load_path(
+        &self,
+        mut layouter: impl Layouter<F>,
+        siblings: &[Option<F>],
+        directions: &[Option<F>],
+    ) -> Result<AssignedPath<F>, Error> {
+        assert_eq!(siblings.len(), directions.len());
+        layouter.assign_region(
+            || "load path",
+            |mut region| {
+                let siblings = siblings
+                    .iter()
+                    .enumerate()
+                    .map(|(i, v)| {
+                        region.assign_advice(
+                            || "sibling",
+                            self.config.sibling,
+                            i,
+                            || v.ok_or(Error::Synthesis),
+                        )
+                    })
+                    .collect::<Result<Vec<_>, Error>>()?;
+                let directions = directions
+                    .iter()
+                    .enumerate()
+                    .map(|(i, v)| {
+                        region.assign_advice(
+                            || "direction",
+                            self.config.direction,
+                            i,
+                            || v.ok_or(Error::Synthesis),
+                        )
+                    })
+                    .collect::<Result<Vec<_>, Error>>()?;
+                Ok(AssignedPath {
+                    siblings,
+                    directions,
+                })
+            },
+        )
+    }
+
+    // 按 direction 排好左右两个孩子
+    fn swap(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cur: &AssignedCell<F, F>,
+        sibling: &AssignedCell<F, F>,
+        direction: &AssignedCell<F, F>,
+    ) -> Result<Children<F>, Error> {
+        layouter.assign_region(
+            || "merkle swap",
+            |mut region| {
+                self.config.s.enable(&mut region, 0)?;
+                cur.copy_advice(|| "copy cur", &mut region, self.config.cur, 0)?;
+                sibling.copy_advice(|| "copy sibling", &mut region, self.config.sibling, 0)?;
+                direction.copy_advice(
+                    || "copy direction",
+                    &mut region,
+                    self.config.direction,
+                    0,
+                )?;
+
+                let swapped = cur.value().and_then(|cur| {
+                    sibling.value().and_then(|sibling| {
+                        direction.value().map(|d| {
+                            (
+                                *cur + *d * (*sibling - cur),
+                                *sibling + *d * (*cur - sibling),
+                            )
+                        })
+                    })
+                });
+                let left = region.assign_advice(
+                    || "left",
+                    self.config.left,
+                    0,
+                    || swapped.map(|v| v.0).ok_or(Error::Synthesis),
+                )?;
+                let right = region.assign_advice(
+                    || "right",
+                    self.config.right,
+                    0,
+                    || swapped.map(|v| v.1).ok_or(Error::Synthesis),
+                )?;
+                Ok((left, right))
+            },
+        )
+    }
+
+    // 从叶子往上算, 返回root
+    pub fn compute_root(
+        &self,
+        mut layouter: impl Layouter<F>,
+        leaf: &AssignedCell<F, F>,
+        siblings: &[Option<F>],
+        directions: &[Option<F>],
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let path = self.load_path(layouter.namespace(|| "path"), siblings, directions)?;
+        self.compute_root_from_path(layouter.namespace(|| "root"), leaf, &path)
+    }
+
+    pub fn compute_root_from_path(
+        &self,
+        mut layouter: impl Layouter<F>,
+        leaf: &AssignedCell<F, F>,
+        path: &AssignedPath<F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let mut cur = leaf.clone();
+        for (level, (sibling, direction)) in
+            path.siblings.iter().zip(path.directions.iter()).enumerate()
+        {
+            let mut layouter = layouter.namespace(|| format!("level {}", level));
+            let (left, right) =
+                self.swap(layouter.namespace(|| "swap"), &cur, sibling, direction)?;
+            cur = self
+                .hasher
+                .assign_hash(layouter.namespace(|| "hash"), &[left, right])?;
+        }
+        Ok(cur)
+    }
+
+    pub fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: &AssignedCell<F, F>,
+        row: usize,
+    ) -> Result<(), Error> {
+        layouter.constrain_instance(cell.cell(), self.config.instance, row)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mydemo::merkle::chip::{MerkleChip, MerkleConfig};
+    use crate::mydemo::merkle::tree::{MerklePath, MerkleTree};
+    use crate::mydemo::mimc::sc_box::{CsHasher, Hasher, QuinticSBoxChip};
+    use crate::mydemo::mimc::sponge::{MiMCSponge, MiMCSpongeChip};
+    use crate::mydemo::poseidon::permutation::{Poseidon, PoseidonChip};
+    use halo2_proofs::arithmetic::FieldExt;
+    use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner};
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::pairing::bn256::Fr;
+    use halo2_proofs::plonk::{Circuit, ConstraintSystem, Error};
+    use std::marker::PhantomData;
+
+    type PoseidonHasher<F> = PoseidonChip<F, QuinticSBoxChip<F>, 3>;
+
+    // 验证 leaf 在树里, root 暴露为instance
+    pub struct MerkleCircuit<F: FieldExt, H: CsHasher<F>> {
+        leaf: Option<F>,
+        siblings: Vec<Option<F>>,
+        directions: Vec<Option<F>>,
+        _h: PhantomData<H>,
+    }
+
+    impl<F: FieldExt, H: CsHasher<F>> MerkleCircuit<F, H> {
+        fn new(path: &MerklePath<F>) -> Self {
+            Self {
+                leaf: Some(path.leaf),
+                siblings: path.siblings.iter().map(|v| Some(*v)).collect(),
+                directions: path.direction_values().into_iter().map(Some).collect(),
+                _h: Default::default(),
+            }
+        }
+    }
+
+    impl<F: FieldExt, H: CsHasher<F>> Circuit<F> for MerkleCircuit<F, H> {
+        type Config = MerkleConfig<H::Config>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                leaf: None,
+                siblings: vec![None; self.siblings.len()],
+                directions: vec![None; self.directions.len()],
+                _h: Default::default(),
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            MerkleChip::<F, H>::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let chip = MerkleChip::<F, H>::construct(config);
+            let leaf = chip.load_leaf(layouter.namespace(|| "leaf"), self.leaf)?;
+            let root = chip.compute_root(
+                layouter.namespace(|| "root"),
+                &leaf,
+                &self.siblings,
+                &self.directions,
+            )?;
+            chip.expose_public(layouter.namespace(|| "expose root"), &root, 0)
+        }
+    }
+
+    fn tree<H: Hasher<Fr>>(hasher: H, depth: usize) -> MerkleTree<Fr, H> {
+        let leaves: Vec<Fr> = (0..(1u64 << depth)).map(|i| Fr::from(i * 7 + 3)).collect();
+        MerkleTree::new(hasher, depth, &leaves)
+    }
+
+    fn verify<H: CsHasher<Fr>>(k: u32, circuit: &MerkleCircuit<Fr, H>, root: Fr) -> bool {
+        let prover = MockProver::run(k, circuit, vec![vec![root]]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    pub fn test_poseidon_membership() {
+        let tree = tree(Poseidon::bn256(3), 4);
+        for index in [0, 5, 10, 15] {
+            let circuit = MerkleCircuit::<Fr, PoseidonHasher<Fr>>::new(&tree.path(index));
+            assert!(verify(12, &circuit, tree.root()));
+        }
+    }
+
+    #[test]
+    pub fn test_mimc_membership() {
+        let tree = tree(MiMCSponge::<Fr, 7, 2, 1>::new(), 2);
+        let circuit = MerkleCircuit::<Fr, MiMCSpongeChip<Fr, 7, 2, 1>>::new(&tree.path(2));
+        assert!(verify(13, &circuit, tree.root()));
+        assert!(!verify(13, &circuit, tree.root() + Fr::one()));
+    }
+
+    #[test]
+    pub fn test_invalid_paths() {
+        let tree = tree(Poseidon::bn256(3), 4);
+        let path = tree.path(6);
+
+        // 错误的root
+        let circuit = MerkleCircuit::<Fr, PoseidonHasher<Fr>>::new(&path);
+        assert!(!verify(12, &circuit, tree.root() + Fr::one()));
+
+        // 不在树里的叶子
+        let mut wrong = path.clone();
+        wrong.leaf += Fr::one();
+        let circuit = MerkleCircuit::<Fr, PoseidonHasher<Fr>>::new(&wrong);
+        assert!(!verify(12, &circuit, tree.root()));
+
+        // 换一个方向
+        let mut wrong = path.clone();
+        wrong.directions[1] = !wrong.directions[1];
+        let circuit = MerkleCircuit::<Fr, PoseidonHasher<Fr>>::new(&wrong);
+        assert!(!verify(12, &circuit, tree.root()));
+
+        // direction 不是0或者1
+        let mut circuit = MerkleCircuit::<Fr, PoseidonHasher<Fr>>::new(&path);
+        circuit.directions[0] = Some(Fr::from(2u64));
+        assert!(!verify(12, &circuit, tree.root()));
+    }
+}
@@ -0,0 +1,4 @@
+mod chip;
+mod sparse_chip;
+mod sparse_tree;
+mod tree;
@@ -0,0 +1,429 @@
+use crate::mydemo::merkle::chip::{AssignedPath, MerkleChip, MerkleConfig};
+use crate::mydemo::mimc::sc_box::CsHasher;
+use halo2_proofs::arithmetic::FieldExt;
+use halo2_proofs::circuit::{AssignedCell, Layouter};
+use halo2_proofs::plonk::{Advice, Column, ConstraintSystem, Error, Expression, Fixed, Selector};
+use halo2_proofs::poly::Rotation;
+
+// 在 MerkleChip 的基础上, path 的方向由 key 的bit决定
+// acc | bit | s_key
+// 从高位往低位累加: acc_0 = 0, acc_{j+1} = 2 * acc_j + bit_j, 最后 acc_depth = key
+// bit_j 和 path.directions[depth - 1 - j] 是同一个cell
+#[derive(Clone, Debug)]
+pub struct SparseMerkleConfig<C> {
+    pub merkle: MerkleConfig<C>,
+    pub acc: Column<Advice>,
+    pub bit: Column<Advice>,
+    pub constant: Column<Fixed>,
+    pub s_key: Selector,
+}
+
+// 更新前后的root: (old_root, new_root)
+pub type RootTransition<F> = (AssignedCell<F, F>, AssignedCell<F, F>);
+
+// sparse merkle tree 的电路, 和 SparseMerkleTree 对应
+// 值为0的叶子表示key不存在
+pub struct SparseMerkleChip<F: FieldExt, H: CsHasher<F>> {
+    config: SparseMerkleConfig<H::Config>,
+    merkle: MerkleChip<F, H>,
+}
+
+impl<F: FieldExt, H: CsHasher<F>> SparseMerkleChip<F, H> {
+    pub fn construct(config: SparseMerkleConfig<H::Config>) -> Self {
+        Self {
+            merkle: MerkleChip::construct(config.merkle.clone()),
+            config,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> SparseMerkleConfig<H::Config> {
+        let acc = meta.advice_column();
+        let bit = meta.advice_column();
+        let constant = meta.fixed_column();
+        let s_key = meta.selector();
+
+        meta.enable_equality(acc);
+        meta.enable_equality(bit);
+        meta.enable_constant(constant);
+
+        // bit 是否为0或者1由 merkle swap 检查
+        meta.create_gate("key bits", |meta| {
+            let acc_cur = meta.query_advice(acc, Rotation::cur());
+            let acc_next = meta.query_advice(acc, Rotation::next());
+            let bit = meta.query_advice(bit, Rotation::cur());
+            let s = meta.query_selector(s_key);
+            let two = Expression::Constant(F::from(2u64));
+            vec![s * (acc_next - two * acc_cur - bit)]
+        });
+
+        SparseMerkleConfig {
+            merkle: MerkleChip::<F, H>::configure(meta),
+            acc,
+            bit,
+            constant,
+            s_key,
+        }
+    }
+
+    pub fn load_private(
+        &self,
+        layouter: impl Layouter<F>,
+        value: Option<F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        self.merkle.load_leaf(layouter, value)
+    }
+
+    fn load_zero(&self, mut layouter: impl Layouter<F>) -> Result<AssignedCell<F, F>, Error> {
+        layouter.assign_region(
+            || "zero",
+            |mut region| {
+                region.assign_advice_from_constant(|| "zero", self.config.acc, 0, F::zero())
+            },
+        )
+    }
+
+    // path 的方向取 key 的低 depth 个bit, 并且约束 key = sum directions[i] * 2^i
+    // key >= 2^depth 时约束不能满足
+    pub fn load_path(
+        &self,
+        mut layouter: impl Layouter<F>,
+        key: &AssignedCell<F, F>,
+        siblings: &[Option<F>],
+    ) -> Result<AssignedPath<F>, Error> {
+        let depth = siblings.len();
+        let directions: Vec<Option<F>> = (0..depth)
+            .map(|i| {
+                key.value().map(|key| {
+                    let repr = key.to_repr();
+                    let bit = (repr.as_ref()[i / 8] >> (i % 8)) & 1;
+                    F::from(bit as u64)
+                })
+            })
+            .collect();
+        let path = self
+            .merkle
+            .load_path(layouter.namespace(|| "path"), siblings, &directions)?;
+
+        layouter.assign_region(
+            || "key bits",
+            |mut region| {
+                let mut acc = region.assign_advice_from_constant(
+                    || "acc 0",
+                    self.config.acc,
+                    0,
+                    F::zero(),
+                )?;
+                for (j, direction) in path.directions.iter().rev().enumerate() {
+                    self.config.s_key.enable(&mut region, j)?;
+                    let bit =
+                        direction.copy_advice(|| "copy bit", &mut region, self.config.bit, j)?;
+                    let next = acc
+                        .value()
+                        .and_then(|acc| bit.value().map(|bit| acc.double() + bit));
+                    acc = region.assign_advice(
+                        || "acc",
+                        self.config.acc,
+                        j + 1,
+                        || next.ok_or(Error::Synthesis),
+                    )?;
+                }
+                region.constrain_equal(acc.cell(), key.cell())
+            },
+        )?;
+        Ok(path)
+    }
+
+    // key 不在树里: key 对应的叶子为0, 返回root
+    pub fn non_membership(
+        &self,
+        mut layouter: impl Layouter<F>,
+        key: &AssignedCell<F, F>,
+        siblings: &[Option<F>],
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let path = self.load_path(layouter.namespace(|| "load path"), key, siblings)?;
+        let zero = self.load_zero(layouter.namespace(|| "empty leaf"))?;
+        self.merkle
+            .compute_root_from_path(layouter.namespace(|| "root"), &zero, &path)
+    }
+
+    // key 对应的值为 value, 返回root
+    pub fn membership(
+        &self,
+        mut layouter: impl Layouter<F>,
+        key: &AssignedCell<F, F>,
+        value: &AssignedCell<F, F>,
+        siblings: &[Option<F>],
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let path = self.load_path(layouter.namespace(|| "load path"), key, siblings)?;
+        self.merkle
+            .compute_root_from_path(layouter.namespace(|| "root"), value, &path)
+    }
+
+    // 把 key 的值从 old_value 改成 new_value, 返回 (old_root, new_root)
+    // 两个root用的是同一条path, 所以树的其它部分没有变化
+    pub fn update(
+        &self,
+        mut layouter: impl Layouter<F>,
+        key: &AssignedCell<F, F>,
+        old_value: &AssignedCell<F, F>,
+        new_value: &AssignedCell<F, F>,
+        siblings: &[Option<F>],
+    ) -> Result<RootTransition<F>, Error> {
+        let path = self.load_path(layouter.namespace(|| "load path"), key, siblings)?;
+        let old_root = self.merkle.compute_root_from_path(
+            layouter.namespace(|| "old root"),
+            old_value,
+            &path,
+        )?;
+        let new_root = self.merkle.compute_root_from_path(
+            layouter.namespace(|| "new root"),
+            new_value,
+            &path,
+        )?;
+        Ok((old_root, new_root))
+    }
+
+    // 插入: 之前 key 不存在, 既 old_value 为0
+    pub fn insert(
+        &self,
+        mut layouter: impl Layouter<F>,
+        key: &AssignedCell<F, F>,
+        value: &AssignedCell<F, F>,
+        siblings: &[Option<F>],
+    ) -> Result<RootTransition<F>, Error> {
+        let zero = self.load_zero(layouter.namespace(|| "empty leaf"))?;
+        self.update(layouter.namespace(|| "update"), key, &zero, value, siblings)
+    }
+
+    pub fn expose_public(
+        &self,
+        layouter: impl Layouter<F>,
+        cell: &AssignedCell<F, F>,
+        row: usize,
+    ) -> Result<(), Error> {
+        self.merkle.expose_public(layouter, cell, row)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mydemo::merkle::sparse_chip::{SparseMerkleChip, SparseMerkleConfig};
+    use crate::mydemo::merkle::sparse_tree::{SparseMerkleTree, SparseMerkleUpdate};
+    use crate::mydemo::mimc::sc_box::{CsHasher, PowerSBox, QuinticSBoxChip};
+    use crate::mydemo::poseidon::permutation::{Poseidon, PoseidonChip};
+    use halo2_proofs::arithmetic::FieldExt;
+    use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner};
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::pairing::bn256::Fr;
+    use halo2_proofs::plonk::{Circuit, ConstraintSystem, Error};
+    use std::marker::PhantomData;
+
+    type PoseidonHasher<F> = PoseidonChip<F, QuinticSBoxChip<F>, 3>;
+
+    const DEPTH: usize = 4;
+    const K: u32 = 13;
+
+    // old_value 为 None 时是插入, 否则是更新
+    // instance: old_root | new_root | key
+    pub struct UpdateCircuit<F: FieldExt, H: CsHasher<F>> {
+        key: Option<F>,
+        old_value: Option<Option<F>>,
+        new_value: Option<F>,
+        siblings: Vec<Option<F>>,
+        _h: PhantomData<H>,
+    }
+
+    impl<F: FieldExt, H: CsHasher<F>> Circuit<F> for UpdateCircuit<F, H> {
+        type Config = SparseMerkleConfig<H::Config>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                key: None,
+                old_value: self.old_value.map(|_| None),
+                new_value: None,
+                siblings: vec![None; self.siblings.len()],
+                _h: Default::default(),
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            SparseMerkleChip::<F, H>::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let chip = SparseMerkleChip::<F, H>::construct(config);
+            let key = chip.load_private(layouter.namespace(|| "key"), self.key)?;
+            let new_value =
+                chip.load_private(layouter.namespace(|| "new value"), self.new_value)?;
+            let (old_root, new_root) = match self.old_value {
+                None => chip.insert(
+                    layouter.namespace(|| "insert"),
+                    &key,
+                    &new_value,
+                    &self.siblings,
+                )?,
+                Some(old_value) => {
+                    let old_value =
+                        chip.load_private(layouter.namespace(|| "old value"), old_value)?;
+                    chip.update(
+                        layouter.namespace(|| "update"),
+                        &key,
+                        &old_value,
+                        &new_value,
+                        &self.siblings,
+                    )?
+                }
+            };
+            chip.expose_public(layouter.namespace(|| "old root"), &old_root, 0)?;
+            chip.expose_public(layouter.namespace(|| "new root"), &new_root, 1)?;
+            chip.expose_public(layouter.namespace(|| "key"), &key, 2)
+        }
+    }
+
+    // instance: root | key
+    pub struct NonMembershipCircuit<F: FieldExt, H: CsHasher<F>> {
+        key: Option<F>,
+        siblings: Vec<Option<F>>,
+        _h: PhantomData<H>,
+    }
+
+    impl<F: FieldExt, H: CsHasher<F>> Circuit<F> for NonMembershipCircuit<F, H> {
+        type Config = SparseMerkleConfig<H::Config>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                key: None,
+                siblings: vec![None; self.siblings.len()],
+                _h: Default::default(),
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            SparseMerkleChip::<F, H>::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let chip = SparseMerkleChip::<F, H>::construct(config);
+            let key = chip.load_private(layouter.namespace(|| "key"), self.key)?;
+            let root =
+                chip.non_membership(layouter.namespace(|| "absent"), &key, &self.siblings)?;
+            chip.expose_public(layouter.namespace(|| "root"), &root, 0)?;
+            chip.expose_public(layouter.namespace(|| "key"), &key, 1)
+        }
+    }
+
+    fn some(values: &[Fr]) -> Vec<Option<Fr>> {
+        values.iter().map(|v| Some(*v)).collect()
+    }
+
+    fn tree() -> SparseMerkleTree<Fr, Poseidon<Fr, PowerSBox<Fr, 5>>> {
+        let mut smt = SparseMerkleTree::new(Poseidon::bn256(3), DEPTH);
+        smt.update(3, Fr::from(30u64));
+        smt.update(9, Fr::from(90u64));
+        smt
+    }
+
+    fn update_circuit(
+        update: &SparseMerkleUpdate<Fr>,
+        insert: bool,
+    ) -> UpdateCircuit<Fr, PoseidonHasher<Fr>> {
+        UpdateCircuit {
+            key: Some(Fr::from(update.key)),
+            old_value: if insert {
+                None
+            } else {
+                Some(Some(update.old_value))
+            },
+            new_value: Some(update.new_value),
+            siblings: some(&update.siblings),
+            _h: Default::default(),
+        }
+    }
+
+    fn verify<C: Circuit<Fr>>(circuit: &C, instance: Vec<Fr>) -> bool {
+        MockProver::run(K, circuit, vec![instance])
+            .unwrap()
+            .verify()
+            .is_ok()
+    }
+
+    #[test]
+    pub fn test_non_membership() {
+        let smt = tree();
+        let root = smt.root();
+        let circuit = |key: u64| NonMembershipCircuit::<Fr, PoseidonHasher<Fr>> {
+            key: Some(Fr::from(key)),
+            siblings: some(&smt.path(key).siblings),
+            _h: Default::default(),
+        };
+        assert!(verify(&circuit(4), vec![root, Fr::from(4u64)]));
+        assert!(verify(&circuit(15), vec![root, Fr::from(15u64)]));
+
+        // 存在的key不能证明不存在
+        assert!(!verify(&circuit(3), vec![root, Fr::from(3u64)]));
+        // 用别的key的path
+        assert!(!verify(&circuit(4), vec![root, Fr::from(5u64)]));
+    }
+
+    #[test]
+    pub fn test_insert_and_update() {
+        let mut smt = tree();
+
+        let insert = smt.update(6, Fr::from(60u64));
+        let circuit = update_circuit(&insert, true);
+        let instance = vec![insert.old_root, insert.new_root, Fr::from(6u64)];
+        assert!(verify(&circuit, instance));
+
+        let update = smt.update(9, Fr::from(99u64));
+        let circuit = update_circuit(&update, false);
+        assert!(verify(
+            &circuit,
+            vec![update.old_root, update.new_root, Fr::from(9u64)]
+        ));
+
+        // 已经存在的key不能再插入
+        let update = smt.update(3, Fr::from(31u64));
+        let circuit = update_circuit(&update, true);
+        assert!(!verify(
+            &circuit,
+            vec![update.old_root, update.new_root, Fr::from(3u64)]
+        ));
+    }
+
+    #[test]
+    pub fn test_invalid_update() {
+        let mut smt = tree();
+        let update = smt.update(9, Fr::from(99u64));
+        let circuit = update_circuit(&update, false);
+
+        // new_root 不对
+        let wrong = vec![update.old_root, update.new_root + Fr::one(), Fr::from(9u64)];
+        assert!(!verify(&circuit, wrong));
+
+        // old_value 不对
+        let mut circuit = update_circuit(&update, false);
+        circuit.old_value = Some(Some(Fr::from(91u64)));
+        let instance = vec![update.old_root, update.new_root, Fr::from(9u64)];
+        assert!(!verify(&circuit, instance.clone()));
+
+        // key 超出范围, 低位bit相同也不行
+        let mut circuit = update_circuit(&update, false);
+        circuit.key = Some(Fr::from(9u64 + (1 << DEPTH)));
+        let instance = vec![
+            update.old_root,
+            update.new_root,
+            Fr::from(9u64 + (1 << DEPTH)),
+        ];
+        assert!(!verify(&circuit, instance));
+    }
+}
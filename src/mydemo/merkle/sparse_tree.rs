@@ -0,0 +1,184 @@
+use crate::mydemo::merkle::tree::MerklePath;
+use crate::mydemo::mimc::sc_box::Hasher;
+use halo2_proofs::arithmetic::FieldExt;
+use std::collections::HashMap;
+
+// sparse merkle tree, 深度为 depth, key 的范围是 [0, 2^depth)
+// key 的第i个bit决定第i层往左还是往右 (第0层是叶子)
+// 值为0的叶子表示这个key不存在, 所以插入0等价于删除
+// 空子树的hash是预先算好的: empty[0] = 0, empty[i+1] = H(empty[i], empty[i])
+// 只保存非空的节点
+pub struct SparseMerkleTree<F: FieldExt, H: Hasher<F>> {
+    hasher: H,
+    depth: usize,
+    empty: Vec<F>,
+    // (level, index) -> hash
+    nodes: HashMap<(usize, u64), F>,
+}
+
+// 单个叶子的更新, old_root 和 new_root 用的是同一条path
+#[derive(Clone, Debug)]
+pub struct SparseMerkleUpdate<F: FieldExt> {
+    pub key: u64,
+    pub old_value: F,
+    pub new_value: F,
+    pub siblings: Vec<F>,
+    pub old_root: F,
+    pub new_root: F,
+}
+
+impl<F: FieldExt, H: Hasher<F>> SparseMerkleTree<F, H> {
+    pub fn new(hasher: H, depth: usize) -> Self {
+        assert!(depth > 0 && depth <= 64, "unsupported depth {}", depth);
+        let mut empty = vec![F::zero()];
+        for i in 0..depth {
+            empty.push(hasher.hash(&[empty[i], empty[i]]));
+        }
+        Self {
+            hasher,
+            depth,
+            empty,
+            nodes: HashMap::new(),
+        }
+    }
+
+    pub fn hasher(&self) -> &H {
+        &self.hasher
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    fn node(&self, level: usize, index: u64) -> F {
+        self.nodes
+            .get(&(level, index))
+            .cloned()
+            .unwrap_or(self.empty[level])
+    }
+
+    fn check_key(&self, key: u64) {
+        assert!(
+            self.depth == 64 || key >> self.depth == 0,
+            "key {} out of range",
+            key
+        );
+    }
+
+    pub fn root(&self) -> F {
+        self.node(self.depth, 0)
+    }
+
+    pub fn get(&self, key: u64) -> F {
+        self.check_key(key);
+        self.node(0, key)
+    }
+
+    pub fn contains(&self, key: u64) -> bool {
+        self.get(key) != F::zero()
+    }
+
+    // leaf 是 key 当前的值, 不存在的key对应 leaf = 0
+    pub fn path(&self, key: u64) -> MerklePath<F> {
+        self.check_key(key);
+        let (siblings, directions) = (0..self.depth)
+            .map(|level| {
+                let index = key >> level;
+                (self.node(level, index ^ 1), index & 1 == 1)
+            })
+            .unzip();
+        MerklePath {
+            leaf: self.get(key),
+            siblings,
+            directions,
+        }
+    }
+
+    // 不存在的证明就是 key 对应的叶子为0的 merkle path
+    pub fn verify_non_membership(&self, root: F, path: &MerklePath<F>) -> bool {
+        path.leaf == F::zero() && path.root(&self.hasher) == root
+    }
+
+    pub fn update(&mut self, key: u64, value: F) -> SparseMerkleUpdate<F> {
+        let path = self.path(key);
+        let old_root = self.root();
+
+        let mut cur = value;
+        for level in 0..self.depth {
+            let index = key >> level;
+            if cur == self.empty[level] {
+                self.nodes.remove(&(level, index));
+            } else {
+                self.nodes.insert((level, index), cur);
+            }
+            let sibling = path.siblings[level];
+            cur = if path.directions[level] {
+                self.hasher.hash(&[sibling, cur])
+            } else {
+                self.hasher.hash(&[cur, sibling])
+            };
+        }
+        if cur == self.empty[self.depth] {
+            self.nodes.remove(&(self.depth, 0));
+        } else {
+            self.nodes.insert((self.depth, 0), cur);
+        }
+
+        SparseMerkleUpdate {
+            key,
+            old_value: path.leaf,
+            new_value: value,
+            siblings: path.siblings,
+            old_root,
+            new_root: cur,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mydemo::merkle::sparse_tree::SparseMerkleTree;
+    use crate::mydemo::merkle::tree::MerkleTree;
+    use crate::mydemo::poseidon::permutation::Poseidon;
+    use halo2_proofs::pairing::bn256::Fr;
+
+    #[test]
+    pub fn test_matches_dense_tree() {
+        let mut smt = SparseMerkleTree::new(Poseidon::bn256(3), 4);
+        let mut leaves = vec![Fr::zero(); 16];
+        assert_eq!(
+            smt.root(),
+            MerkleTree::new(Poseidon::bn256(3), 4, &leaves).root()
+        );
+
+        for (key, value) in [(3u64, 10u64), (12, 20), (3, 30), (7, 40)] {
+            let update = smt.update(key, Fr::from(value));
+            leaves[key as usize] = Fr::from(value);
+            let dense = MerkleTree::new(Poseidon::bn256(3), 4, &leaves);
+            assert_eq!(update.new_root, dense.root());
+            assert_eq!(smt.root(), dense.root());
+            assert_eq!(smt.path(key).siblings, dense.path(key as usize).siblings);
+        }
+    }
+
+    #[test]
+    pub fn test_non_membership() {
+        let mut smt = SparseMerkleTree::new(Poseidon::bn256(3), 8);
+        smt.update(5, Fr::from(55u64));
+        smt.update(200, Fr::from(77u64));
+
+        let root = smt.root();
+        assert!(smt.verify_non_membership(root, &smt.path(6)));
+        assert!(!smt.verify_non_membership(root, &smt.path(5)));
+
+        // 删除之后回到空树
+        let update = smt.update(5, Fr::zero());
+        assert_eq!(update.old_value, Fr::from(55u64));
+        smt.update(200, Fr::zero());
+        assert_eq!(
+            smt.root(),
+            SparseMerkleTree::new(Poseidon::bn256(3), 8).root()
+        );
+        assert!(smt.nodes.is_empty());
+    }
+}
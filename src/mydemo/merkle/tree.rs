@@ -0,0 +1,123 @@
+use crate::mydemo::mimc::sc_box::Hasher;
+use halo2_proofs::arithmetic::FieldExt;
+
+// native 的 merkle tree, 深度为 depth, 叶子个数为 2^depth, 不够的用0补齐
+// 父节点 = H(left, right)
+// layers[0] 是叶子, layers[depth] 只有一个元素, 既 root
+pub struct MerkleTree<F: FieldExt, H: Hasher<F>> {
+    hasher: H,
+    layers: Vec<Vec<F>>,
+}
+
+// 从叶子到root的路径
+// directions[i] 为 true 表示第i层的节点是右孩子, 既 sibling 在左边
+#[derive(Clone, Debug)]
+pub struct MerklePath<F: FieldExt> {
+    pub leaf: F,
+    pub siblings: Vec<F>,
+    pub directions: Vec<bool>,
+}
+
+impl<F: FieldExt> MerklePath<F> {
+    pub fn root(&self, hasher: &impl Hasher<F>) -> F {
+        self.siblings.iter().zip(self.directions.iter()).fold(
+            self.leaf,
+            |cur, (sibling, is_right)| {
+                if *is_right {
+                    hasher.hash(&[*sibling, cur])
+                } else {
+                    hasher.hash(&[cur, *sibling])
+                }
+            },
+        )
+    }
+
+    // 在电路里 directions 作为域元素赋值
+    pub fn direction_values(&self) -> Vec<F> {
+        self.directions
+            .iter()
+            .map(|d| if *d { F::one() } else { F::zero() })
+            .collect()
+    }
+}
+
+impl<F: FieldExt, H: Hasher<F>> MerkleTree<F, H> {
+    pub fn new(hasher: H, depth: usize, leaves: &[F]) -> Self {
+        assert!(leaves.len() <= 1 << depth, "too many leaves");
+        let mut layer = leaves.to_vec();
+        layer.resize(1 << depth, F::zero());
+
+        let mut layers = vec![layer];
+        for _ in 0..depth {
+            let next = layers
+                .last()
+                .unwrap()
+                .chunks(2)
+                .map(|pair| hasher.hash(pair))
+                .collect();
+            layers.push(next);
+        }
+        Self { hasher, layers }
+    }
+
+    pub fn hasher(&self) -> &H {
+        &self.hasher
+    }
+
+    pub fn depth(&self) -> usize {
+        self.layers.len() - 1
+    }
+
+    pub fn root(&self) -> F {
+        self.layers[self.depth()][0]
+    }
+
+    pub fn leaf(&self, index: usize) -> F {
+        self.layers[0][index]
+    }
+
+    pub fn path(&self, index: usize) -> MerklePath<F> {
+        assert!(index < self.layers[0].len(), "index out of range");
+        let (siblings, directions) = self.layers[..self.depth()]
+            .iter()
+            .enumerate()
+            .map(|(level, layer)| {
+                let i = index >> level;
+                (layer[i ^ 1], i & 1 == 1)
+            })
+            .unzip();
+        MerklePath {
+            leaf: self.leaf(index),
+            siblings,
+            directions,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mydemo::merkle::tree::MerkleTree;
+    use crate::mydemo::poseidon::permutation::Poseidon;
+    use halo2_proofs::pairing::bn256::Fr;
+
+    #[test]
+    pub fn test_paths() {
+        let leaves: Vec<Fr> = (1..=6u64).map(Fr::from).collect();
+        let tree = MerkleTree::new(Poseidon::bn256(3), 3, &leaves);
+        for i in 0..8 {
+            let path = tree.path(i);
+            assert_eq!(path.siblings.len(), 3);
+            assert_eq!(path.root(tree.hasher()), tree.root());
+        }
+
+        let hasher = tree.hasher();
+        let left = hasher.hash(&[leaves[0], leaves[1]]);
+        let right = hasher.hash(&[leaves[2], leaves[3]]);
+        assert_eq!(tree.path(2).siblings[1], left);
+        assert_eq!(tree.path(0).siblings[1], right);
+
+        let mut path = tree.path(5);
+        path.leaf += Fr::one();
+        assert_ne!(path.root(tree.hasher()), tree.root());
+    }
+}
@@ -0,0 +1,79 @@
+use halo2_proofs::arithmetic::FieldExt;
+
+// 轮常量的生成: c_0 = 0, c_i = blake2b-512(seed || i) mod p
+// 用512bit的hash再mod p,保证分布是均匀的
+pub fn round_constants<F: FieldExt>(seed: &[u8], rounds: usize) -> Vec<F> {
+    (0..rounds)
+        .map(|i| {
+            if i == 0 {
+                return F::zero();
+            }
+            let hash = blake2b_simd::Params::new()
+                .hash_length(64)
+                .to_state()
+                .update(seed)
+                .update(&(i as u64).to_le_bytes())
+                .finalize();
+            let mut bytes = [0u8; 64];
+            bytes.copy_from_slice(hash.as_bytes());
+            F::from_bytes_wide(&bytes)
+        })
+        .collect()
+}
+
+// MiMC 的轮数: r = ceil(log_e(p)), 既 ceil(NUM_BITS / log2(e))
+// 比如 bn256 上 e=7 为91轮, e=5 为110轮
+pub fn number_of_rounds<F: FieldExt>(exponent: u64) -> usize {
+    (F::NUM_BITS as f64 / (exponent as f64).log2()).ceil() as usize
+}
+
+// x -> x^e 是置换的充要条件是 gcd(e, p-1) = 1
+pub fn is_permutation<F: FieldExt>(exponent: u64) -> bool {
+    // 先算 (p-1) mod e, repr 是小端序
+    let p_minus_1 = -F::one();
+    let rem = p_minus_1
+        .to_repr()
+        .as_ref()
+        .iter()
+        .rev()
+        .fold(0u64, |acc, b| ((acc << 8) + *b as u64) % exponent);
+    gcd(exponent, rem) == 1
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mydemo::mimc::constants::{is_permutation, number_of_rounds, round_constants};
+    use halo2_proofs::pairing::bn256::Fr;
+
+    #[test]
+    pub fn test_rounds() {
+        assert_eq!(number_of_rounds::<Fr>(7), 91);
+        assert_eq!(number_of_rounds::<Fr>(5), 110);
+    }
+
+    #[test]
+    pub fn test_is_permutation() {
+        // bn256 的 p-1 能被3整除
+        assert!(!is_permutation::<Fr>(3));
+        assert!(is_permutation::<Fr>(5));
+        assert!(is_permutation::<Fr>(7));
+    }
+
+    #[test]
+    pub fn test_constants_deterministic() {
+        let a = round_constants::<Fr>(b"mimc", 91);
+        let b = round_constants::<Fr>(b"mimc", 91);
+        assert_eq!(a, b);
+        assert_eq!(a[0], Fr::zero());
+        assert_ne!(a[1], a[2]);
+        assert_ne!(a, round_constants::<Fr>(b"mimc-feistel", 91));
+    }
+}
@@ -1 +1,4 @@
-mod sc_box;
+pub(crate) mod constants;
+mod permutation;
+pub(crate) mod sc_box;
+pub(crate) mod sponge;
@@ -0,0 +1,384 @@
+use crate::mydemo::mimc::constants::{is_permutation, number_of_rounds, round_constants};
+use crate::mydemo::mimc::sc_box::{CsSBox, PowerSBox, PowerSBoxChip, PowerSBoxConfig, SBox};
+use halo2_proofs::arithmetic::FieldExt;
+use halo2_proofs::circuit::{AssignedCell, Layouter};
+use halo2_proofs::plonk::{Advice, Column, ConstraintSystem, Error, Fixed, Selector};
+use halo2_proofs::poly::Rotation;
+use std::marker::PhantomData;
+
+// MiMC-n/n:
+// x_0 = x
+// x_{i+1} = (x_i + k + c_i)^e , i in [0, r)
+// E_k(x) = x_r + k
+pub struct MiMC<F: FieldExt, const E: u64> {
+    sbox: PowerSBox<F, E>,
+    constants: Vec<F>,
+}
+
+pub type MiMC5<F> = MiMC<F, 5>;
+pub type MiMC7<F> = MiMC<F, 7>;
+
+impl<F: FieldExt, const E: u64> Default for MiMC<F, E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F: FieldExt, const E: u64> MiMC<F, E> {
+    pub fn new() -> Self {
+        assert!(is_permutation::<F>(E), "x^{} is not a permutation", E);
+        Self {
+            sbox: Default::default(),
+            constants: round_constants(b"mimc", number_of_rounds::<F>(E)),
+        }
+    }
+
+    pub fn constants(&self) -> &[F] {
+        &self.constants
+    }
+
+    pub fn encrypt(&self, x: F, k: F) -> F {
+        let mut state = [x];
+        for c in self.constants.iter() {
+            state[0] += k + c;
+            self.sbox.apply(&mut state);
+        }
+        state[0] + k
+    }
+}
+
+// MiMC-2n/n (Feistel), 轮数是MiMC的两倍:
+// xL, xR <- xR + (xL + k + c_i)^e, xL
+// 最后一轮不交换
+pub struct MiMCFeistel<F: FieldExt, const E: u64> {
+    sbox: PowerSBox<F, E>,
+    constants: Vec<F>,
+}
+
+impl<F: FieldExt, const E: u64> Default for MiMCFeistel<F, E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F: FieldExt, const E: u64> MiMCFeistel<F, E> {
+    pub fn new() -> Self {
+        assert!(is_permutation::<F>(E), "x^{} is not a permutation", E);
+        Self {
+            sbox: Default::default(),
+            constants: round_constants(b"mimc-feistel", 2 * number_of_rounds::<F>(E)),
+        }
+    }
+
+    pub fn constants(&self) -> &[F] {
+        &self.constants
+    }
+
+    pub fn permute(&self, xl: F, xr: F, k: F) -> (F, F) {
+        let (mut xl, mut xr) = (xl, xr);
+        let last = self.constants.len() - 1;
+        for (i, c) in self.constants.iter().enumerate() {
+            let mut t = [xl + k + c];
+            self.sbox.apply(&mut t);
+            if i < last {
+                let new_xl = xr + t[0];
+                xr = xl;
+                xl = new_xl;
+            } else {
+                xr += t[0];
+            }
+        }
+        (xl, xr)
+    }
+}
+
+// a | b | out | c(fixed) | s_add
+// out = a + b + c
+// 轮密钥加和feistel的异或(这里是加法)都用这个gate, sbox 则交给 PowerSBoxChip
+#[derive(Clone, Debug)]
+pub struct MiMCConfig {
+    pub a: Column<Advice>,
+    pub b: Column<Advice>,
+    pub out: Column<Advice>,
+    pub c: Column<Fixed>,
+    // 用于 load_constant
+    pub constant: Column<Fixed>,
+    pub s_add: Selector,
+    pub sbox: PowerSBoxConfig,
+}
+
+// (xL, xR)
+pub type FeistelState<F> = (AssignedCell<F, F>, AssignedCell<F, F>);
+
+pub struct MiMCChip<F: FieldExt, const E: u64> {
+    config: MiMCConfig,
+    sbox: PowerSBoxChip<F, E>,
+    mimc: MiMC<F, E>,
+    feistel: MiMCFeistel<F, E>,
+    _p: PhantomData<F>,
+}
+
+impl<F: FieldExt, const E: u64> MiMCChip<F, E> {
+    pub fn construct(config: MiMCConfig) -> Self {
+        Self {
+            sbox: PowerSBoxChip::construct(config.sbox.clone()),
+            config,
+            mimc: MiMC::new(),
+            feistel: MiMCFeistel::new(),
+            _p: Default::default(),
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> MiMCConfig {
+        let a = meta.advice_column();
+        let b = meta.advice_column();
+        let out = meta.advice_column();
+        let c = meta.fixed_column();
+        let constant = meta.fixed_column();
+        let s_add = meta.selector();
+
+        meta.enable_equality(a);
+        meta.enable_equality(b);
+        meta.enable_equality(out);
+        meta.enable_constant(constant);
+
+        meta.create_gate("mimc add", |meta| {
+            let a = meta.query_advice(a, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::cur());
+            let out = meta.query_advice(out, Rotation::cur());
+            let c = meta.query_fixed(c, Rotation::cur());
+            let s = meta.query_selector(s_add);
+            vec![s * (a + b + c - out)]
+        });
+        MiMCConfig {
+            a,
+            b,
+            out,
+            c,
+            constant,
+            s_add,
+            sbox: PowerSBoxChip::<F, E>::configure(meta),
+        }
+    }
+
+    pub fn load_private(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Option<F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        layouter.assign_region(
+            || "load private",
+            |mut region| {
+                region.assign_advice(
+                    || "private input",
+                    self.config.a,
+                    0,
+                    || value.ok_or(Error::Synthesis),
+                )
+            },
+        )
+    }
+
+    pub fn load_constant(
+        &self,
+        mut layouter: impl Layouter<F>,
+        constant: F,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        layouter.assign_region(
+            || "load constant",
+            |mut region| {
+                region.assign_advice_from_constant(|| "constant", self.config.a, 0, constant)
+            },
+        )
+    }
+
+    // out = a + b + constant
+    pub fn add(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+        constant: F,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        layouter.assign_region(
+            || "add",
+            |mut region| {
+                self.config.s_add.enable(&mut region, 0)?;
+                a.copy_advice(|| "copy a", &mut region, self.config.a, 0)?;
+                b.copy_advice(|| "copy b", &mut region, self.config.b, 0)?;
+                region.assign_fixed(|| "constant", self.config.c, 0, || Ok(constant))?;
+
+                let out = a
+                    .value()
+                    .and_then(|a| b.value().map(|b| *a + *b + constant));
+                region.assign_advice(|| "out", self.config.out, 0, || out.ok_or(Error::Synthesis))
+            },
+        )
+    }
+
+    // (x + k + c)^e
+    pub fn round(
+        &self,
+        mut layouter: impl Layouter<F>,
+        x: &AssignedCell<F, F>,
+        k: &AssignedCell<F, F>,
+        c: F,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let t = self.add(layouter.namespace(|| "add round key"), x, k, c)?;
+        self.sbox
+            .assign_constraints(layouter.namespace(|| "sbox"), &t)
+    }
+
+    pub fn encrypt(
+        &self,
+        mut layouter: impl Layouter<F>,
+        x: &AssignedCell<F, F>,
+        k: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let mut state = x.clone();
+        for (i, c) in self.mimc.constants().iter().enumerate() {
+            state = self.round(layouter.namespace(|| format!("round {}", i)), &state, k, *c)?;
+        }
+        self.add(layouter.namespace(|| "add key"), &state, k, F::zero())
+    }
+
+    pub fn permute_feistel(
+        &self,
+        mut layouter: impl Layouter<F>,
+        xl: &AssignedCell<F, F>,
+        xr: &AssignedCell<F, F>,
+        k: &AssignedCell<F, F>,
+    ) -> Result<FeistelState<F>, Error> {
+        let (mut xl, mut xr) = (xl.clone(), xr.clone());
+        let last = self.feistel.constants().len() - 1;
+        for (i, c) in self.feistel.constants().iter().enumerate() {
+            let mut layouter = layouter.namespace(|| format!("feistel round {}", i));
+            let t = self.round(layouter.namespace(|| "f"), &xl, k, *c)?;
+            let sum = self.add(layouter.namespace(|| "xr + f"), &xr, &t, F::zero())?;
+            if i < last {
+                xr = xl;
+                xl = sum;
+            } else {
+                xr = sum;
+            }
+        }
+        Ok((xl, xr))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mydemo::mimc::permutation::{MiMC7, MiMCChip, MiMCConfig, MiMCFeistel};
+    use crate::zk::wrapper::from_hex;
+    use halo2_proofs::arithmetic::FieldExt;
+    use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner};
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::pairing::bn256::Fr;
+    use halo2_proofs::plonk::{Circuit, Column, ConstraintSystem, Error, Instance};
+
+    #[derive(Clone, Debug)]
+    pub struct TestConfig {
+        mimc: MiMCConfig,
+        instance: Column<Instance>,
+    }
+
+    // feistel=false: instance = [E_k(x)]
+    // feistel=true: instance = [xL', xR'], 其中 (xL, xR) = (x, 0)
+    #[derive(Default)]
+    pub struct MiMCCircuit<F: FieldExt, const FEISTEL: bool> {
+        x: Option<F>,
+        k: Option<F>,
+    }
+
+    impl<F: FieldExt, const FEISTEL: bool> Circuit<F> for MiMCCircuit<F, FEISTEL> {
+        type Config = TestConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            TestConfig {
+                mimc: MiMCChip::<F, 7>::configure(meta),
+                instance,
+            }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let chip = MiMCChip::<F, 7>::construct(config.mimc);
+            let x = chip.load_private(layouter.namespace(|| "x"), self.x)?;
+            let k = chip.load_private(layouter.namespace(|| "k"), self.k)?;
+            if FEISTEL {
+                let zero = chip.load_private(layouter.namespace(|| "zero"), Some(F::zero()))?;
+                let (xl, xr) =
+                    chip.permute_feistel(layouter.namespace(|| "feistel"), &x, &zero, &k)?;
+                layouter.constrain_instance(xl.cell(), config.instance, 0)?;
+                layouter.constrain_instance(xr.cell(), config.instance, 1)
+            } else {
+                let out = chip.encrypt(layouter.namespace(|| "mimc"), &x, &k)?;
+                layouter.constrain_instance(out.cell(), config.instance, 0)
+            }
+        }
+    }
+
+    #[test]
+    pub fn test_native_known_answer() {
+        let mimc = MiMC7::<Fr>::new();
+        assert_eq!(mimc.constants().len(), 91);
+        assert_eq!(
+            mimc.encrypt(Fr::from(1u64), Fr::from(2u64)),
+            from_hex::<Fr>("1a098aa429c79e17b6cc9eb6167a0e78b2617c27b801731ffb8eb93b3ebd5f25")
+        );
+        assert_eq!(
+            mimc.encrypt(Fr::zero(), Fr::zero()),
+            from_hex::<Fr>("2808d9e0d29a48863e421de28a7ed6b58628910765dc4f74fff7abee8bea795d")
+        );
+
+        let feistel = MiMCFeistel::<Fr, 7>::new();
+        assert_eq!(feistel.constants().len(), 182);
+        assert_eq!(
+            feistel.permute(Fr::from(1u64), Fr::zero(), Fr::from(2u64)),
+            (
+                from_hex::<Fr>("2729557d49031e27d71563d77789ec3d2a80b53d8438ce32715dd38e2df50ad1"),
+                from_hex::<Fr>("0a0850793f4e5be337acb331b1f61dd514073caf71d24ba5f61dc0239454c6fb")
+            )
+        );
+    }
+
+    #[test]
+    pub fn test_mimc_chip() {
+        let (x, k) = (Fr::from(1u64), Fr::from(2u64));
+        let out = MiMC7::<Fr>::new().encrypt(x, k);
+        let circuit = MiMCCircuit::<Fr, false> {
+            x: Some(x),
+            k: Some(k),
+        };
+        let prover = MockProver::run(9, &circuit, vec![vec![out]]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        let prover = MockProver::run(9, &circuit, vec![vec![out + Fr::one()]]).unwrap();
+        assert_ne!(prover.verify(), Ok(()));
+    }
+
+    #[test]
+    pub fn test_feistel_chip() {
+        let (x, k) = (Fr::from(1u64), Fr::from(2u64));
+        let (xl, xr) = MiMCFeistel::<Fr, 7>::new().permute(x, Fr::zero(), k);
+        let circuit = MiMCCircuit::<Fr, true> {
+            x: Some(x),
+            k: Some(k),
+        };
+        let prover = MockProver::run(10, &circuit, vec![vec![xl, xr]]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        let prover = MockProver::run(10, &circuit, vec![vec![xr, xl]]).unwrap();
+        assert_ne!(prover.verify(), Ok(()));
+    }
+}
@@ -1,38 +1,448 @@
-use crate::zk::wrapper::PrimeWrapper;
+use crate::mydemo::mimc::constants::is_permutation;
 use halo2_proofs::arithmetic::FieldExt;
-use halo2_proofs::circuit::Layouter;
-use halo2_proofs::pairing::group::ff::PrimeField;
-use halo2_proofs::plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Selector};
+use halo2_proofs::circuit::{AssignedCell, Layouter};
+use halo2_proofs::plonk::{Advice, Column, ConstraintSystem, Error, Selector};
+use halo2_proofs::poly::Rotation;
+use std::fmt::Debug;
+use std::marker::PhantomData;
 
-pub trait Halo2BoxConfig<F: FieldExt> {}
+// S-box: 对每个元素做一次非线性变换, 比如 x -> x^alpha
+// SBox 是native的计算, CsSBox 则是对应的电路实现
 pub trait SBox<F: FieldExt> {
     fn apply(&self, elements: &mut [F]);
 }
 pub trait CsSBox<F: FieldExt>: SBox<F> {
-    type Config;
+    type Config: Clone + Debug;
     fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config;
 
+    fn construct(config: Self::Config) -> Self;
+
     fn assign_constraints(
         &self,
         cs: impl Layouter<F>,
-        element: &PrimeWrapper<F>,
-    ) -> Result<PrimeWrapper<F>, Error>;
-    fn assign_constraints_on_lc_for_set(
+        element: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error>;
+    fn assign_constraints_for_set(
         &self,
         mut cs: impl Layouter<F>,
-        elements: Vec<Num<E>>,
-    ) -> Result<Vec<Num<E>>, SynthesisError> {
+        elements: &[AssignedCell<F, F>],
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
         let mut results = Vec::with_capacity(elements.len());
-        for (i, el) in elements.into_iter().enumerate() {
-            let applied = self.apply_constraints_on_lc(cs.namespace(|| "assign "), el)?;
+        for (i, el) in elements.iter().enumerate() {
+            let applied = self.assign_constraints(cs.namespace(|| format!("assign {}", i)), el)?;
             results.push(applied)
         }
 
         Ok(results)
     }
 }
+
+// native 的 x -> x^ALPHA
+#[derive(Clone, Debug, Default)]
+pub struct PowerSBox<F: FieldExt, const ALPHA: u64> {
+    _p: PhantomData<F>,
+}
+
+impl<F: FieldExt, const ALPHA: u64> SBox<F> for PowerSBox<F, ALPHA> {
+    fn apply(&self, elements: &mut [F]) {
+        for el in elements.iter_mut() {
+            *el = el.pow_vartime([ALPHA]);
+        }
+    }
+}
+
+// x | y | s
+// y = x^ALPHA
+#[derive(Clone, Debug)]
+pub struct PowerSBoxConfig {
+    pub x: Column<Advice>,
+    pub y: Column<Advice>,
+    pub s: Selector,
+}
+
+pub struct PowerSBoxChip<F: FieldExt, const ALPHA: u64> {
+    config: PowerSBoxConfig,
+    _p: PhantomData<F>,
+}
+
+pub type CubicSBoxChip<F> = PowerSBoxChip<F, 3>;
+pub type QuinticSBoxChip<F> = PowerSBoxChip<F, 5>;
+pub type SepticSBoxChip<F> = PowerSBoxChip<F, 7>;
+
+impl<F: FieldExt, const ALPHA: u64> SBox<F> for PowerSBoxChip<F, ALPHA> {
+    fn apply(&self, elements: &mut [F]) {
+        PowerSBox::<F, ALPHA>::default().apply(elements)
+    }
+}
+
+impl<F: FieldExt, const ALPHA: u64> CsSBox<F> for PowerSBoxChip<F, ALPHA> {
+    type Config = PowerSBoxConfig;
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let x = meta.advice_column();
+        let y = meta.advice_column();
+        let s = meta.selector();
+
+        meta.enable_equality(x);
+        meta.enable_equality(y);
+
+        // x^ALPHA - y = 0
+        // 直接用一个高次的gate, degree 为 ALPHA+1
+        meta.create_gate("power sbox", |meta| {
+            let x = meta.query_advice(x, Rotation::cur());
+            let y = meta.query_advice(y, Rotation::cur());
+            let s = meta.query_selector(s);
+            let pow = (1..ALPHA).fold(x.clone(), |acc, _| acc * x.clone());
+            vec![s * (pow - y)]
+        });
+        PowerSBoxConfig { x, y, s }
+    }
+
+    fn construct(config: Self::Config) -> Self {
+        Self {
+            config,
+            _p: Default::default(),
+        }
+    }
+
+    fn assign_constraints(
+        &self,
+        mut cs: impl Layouter<F>,
+        element: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        cs.assign_region(
+            || "power sbox",
+            |mut region| {
+                self.config.s.enable(&mut region, 0)?;
+                element.copy_advice(|| "copy x", &mut region, self.config.x, 0)?;
+
+                let y = element.value().map(|x| {
+                    let mut y = [*x];
+                    self.apply(&mut y);
+                    y[0]
+                });
+                region.assign_advice(
+                    || "assign y",
+                    self.config.y,
+                    0,
+                    || y.ok_or(Error::Synthesis),
+                )
+            },
+        )
+    }
+}
+
+// native 的 x -> x^(1/ALPHA), 既 x^d, 其中 d * ALPHA = 1 mod (p-1)
+// 只有 x^ALPHA 是置换的时候才有逆
+#[derive(Clone, Debug)]
+pub struct InversePowerSBox<F: FieldExt, const ALPHA: u64> {
+    exponent: Vec<u64>,
+    _p: PhantomData<F>,
+}
+
+impl<F: FieldExt, const ALPHA: u64> Default for InversePowerSBox<F, ALPHA> {
+    fn default() -> Self {
+        Self {
+            exponent: inverse_exponent::<F>(ALPHA),
+            _p: Default::default(),
+        }
+    }
+}
+
+impl<F: FieldExt, const ALPHA: u64> SBox<F> for InversePowerSBox<F, ALPHA> {
+    fn apply(&self, elements: &mut [F]) {
+        for el in elements.iter_mut() {
+            *el = el.pow_vartime(&self.exponent);
+        }
+    }
+}
+
+// 求 d = alpha^{-1} mod (p-1), 结果是小端序的u64
+// 找到 k in [1, alpha) 使得 k*(p-1)+1 能被alpha整除, d = (k*(p-1)+1) / alpha
+fn inverse_exponent<F: FieldExt>(alpha: u64) -> Vec<u64> {
+    assert!(
+        is_permutation::<F>(alpha),
+        "x^{} is not a permutation",
+        alpha
+    );
+    let p_minus_1: Vec<u64> = (-F::one())
+        .to_repr()
+        .as_ref()
+        .chunks(8)
+        .map(|chunk| {
+            let mut bytes = [0u8; 8];
+            bytes[..chunk.len()].copy_from_slice(chunk);
+            u64::from_le_bytes(bytes)
+        })
+        .collect();
+    for k in 1..alpha {
+        let mut carry = 1u128;
+        let mut n: Vec<u64> = p_minus_1
+            .iter()
+            .map(|limb| {
+                let v = *limb as u128 * k as u128 + carry;
+                carry = v >> 64;
+                v as u64
+            })
+            .collect();
+        n.push(carry as u64);
+
+        // 从高位往低位做除法
+        let mut rem = 0u128;
+        for limb in n.iter_mut().rev() {
+            let v = (rem << 64) | *limb as u128;
+            *limb = (v / alpha as u128) as u64;
+            rem = v % alpha as u128;
+        }
+        if rem == 0 {
+            return n;
+        }
+    }
+    unreachable!()
+}
+
+// x | y | s
+// y = x^(1/ALPHA), y 由prover直接给出, 电路里只检查 y^ALPHA = x
+// 这样约束的次数和正向的 PowerSBoxChip 一样
+pub struct InversePowerSBoxChip<F: FieldExt, const ALPHA: u64> {
+    config: PowerSBoxConfig,
+    sbox: InversePowerSBox<F, ALPHA>,
+}
+
+pub type QuinticInverseSBoxChip<F> = InversePowerSBoxChip<F, 5>;
+pub type SepticInverseSBoxChip<F> = InversePowerSBoxChip<F, 7>;
+
+impl<F: FieldExt, const ALPHA: u64> SBox<F> for InversePowerSBoxChip<F, ALPHA> {
+    fn apply(&self, elements: &mut [F]) {
+        self.sbox.apply(elements)
+    }
+}
+
+impl<F: FieldExt, const ALPHA: u64> CsSBox<F> for InversePowerSBoxChip<F, ALPHA> {
+    type Config = PowerSBoxConfig;
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let x = meta.advice_column();
+        let y = meta.advice_column();
+        let s = meta.selector();
+
+        meta.enable_equality(x);
+        meta.enable_equality(y);
+
+        // y^ALPHA - x = 0
+        meta.create_gate("inverse power sbox", |meta| {
+            let x = meta.query_advice(x, Rotation::cur());
+            let y = meta.query_advice(y, Rotation::cur());
+            let s = meta.query_selector(s);
+            let pow = (1..ALPHA).fold(y.clone(), |acc, _| acc * y.clone());
+            vec![s * (pow - x)]
+        });
+        PowerSBoxConfig { x, y, s }
+    }
+
+    fn construct(config: Self::Config) -> Self {
+        Self {
+            config,
+            sbox: Default::default(),
+        }
+    }
+
+    fn assign_constraints(
+        &self,
+        mut cs: impl Layouter<F>,
+        element: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        cs.assign_region(
+            || "inverse power sbox",
+            |mut region| {
+                self.config.s.enable(&mut region, 0)?;
+                element.copy_advice(|| "copy x", &mut region, self.config.x, 0)?;
+
+                let y = element.value().map(|x| {
+                    let mut y = [*x];
+                    self.apply(&mut y);
+                    y[0]
+                });
+                region.assign_advice(
+                    || "assign y",
+                    self.config.y,
+                    0,
+                    || y.ok_or(Error::Synthesis),
+                )
+            },
+        )
+    }
+}
+
+// 哈希: Hasher 是native的计算, CsHasher 则是对应的电路实现
+// MiMC sponge, Poseidon, Rescue 都实现了这两个trait, 上层的gadget通过类型参数选择用哪个哈希
+pub trait Hasher<F: FieldExt> {
+    fn hash(&self, inputs: &[F]) -> F;
+}
+pub trait CsHasher<F: FieldExt>: Hasher<F> {
+    type Config: Clone + Debug;
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config;
+
+    fn construct(config: Self::Config) -> Self;
+
+    fn load_private(
+        &self,
+        cs: impl Layouter<F>,
+        value: Option<F>,
+    ) -> Result<AssignedCell<F, F>, Error>;
+    fn assign_hash(
+        &self,
+        cs: impl Layouter<F>,
+        inputs: &[AssignedCell<F, F>],
+    ) -> Result<AssignedCell<F, F>, Error>;
+}
+
 #[cfg(test)]
 mod tests {
+    use crate::mydemo::mimc::sc_box::{
+        CsSBox, CubicSBoxChip, InversePowerSBox, PowerSBox, QuinticInverseSBoxChip,
+        QuinticSBoxChip, SBox, SepticInverseSBoxChip, SepticSBoxChip,
+    };
+    use halo2_proofs::arithmetic::FieldExt;
+    use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner};
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::pairing::bn256::Fr;
+    use halo2_proofs::plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Instance};
+    use std::marker::PhantomData;
+
+    #[derive(Clone, Debug)]
+    pub struct SBoxTestConfig<C> {
+        input: Column<Advice>,
+        out: Column<Instance>,
+        sbox: C,
+    }
+
+    // 把inputs 经过sbox之后的结果暴露为instance
+    pub struct SBoxCircuit<F: FieldExt, S: CsSBox<F>> {
+        inputs: Vec<Option<F>>,
+        _s: PhantomData<S>,
+    }
+
+    impl<F: FieldExt, S: CsSBox<F>> Circuit<F> for SBoxCircuit<F, S> {
+        type Config = SBoxTestConfig<S::Config>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                inputs: vec![None; self.inputs.len()],
+                _s: Default::default(),
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let input = meta.advice_column();
+            let out = meta.instance_column();
+            meta.enable_equality(input);
+            meta.enable_equality(out);
+            SBoxTestConfig {
+                input,
+                out,
+                sbox: S::configure(meta),
+            }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let inputs = layouter.assign_region(
+                || "load inputs",
+                |mut region| {
+                    self.inputs
+                        .iter()
+                        .enumerate()
+                        .map(|(i, v)| {
+                            region.assign_advice(
+                                || "input",
+                                config.input,
+                                i,
+                                || v.ok_or(Error::Synthesis),
+                            )
+                        })
+                        .collect::<Result<Vec<_>, Error>>()
+                },
+            )?;
+            let chip = S::construct(config.sbox);
+            let outputs =
+                chip.assign_constraints_for_set(layouter.namespace(|| "sbox"), &inputs)?;
+            for (i, out) in outputs.iter().enumerate() {
+                layouter.constrain_instance(out.cell(), config.out, i)?;
+            }
+            Ok(())
+        }
+    }
+
+    fn inputs() -> Vec<Fr> {
+        vec![
+            Fr::zero(),
+            Fr::one(),
+            Fr::from(2u64),
+            Fr::from(12345u64),
+            -Fr::one(),
+        ]
+    }
+
+    fn run<S: CsSBox<Fr>>(native: impl SBox<Fr>) {
+        let inputs = inputs();
+        let mut expected = inputs.clone();
+        native.apply(&mut expected);
+
+        let circuit = SBoxCircuit::<Fr, S> {
+            inputs: inputs.iter().map(|v| Some(*v)).collect(),
+            _s: Default::default(),
+        };
+        let prover = MockProver::run(5, &circuit, vec![expected.clone()]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        // 输出错一个就不能通过
+        expected[3] += Fr::one();
+        let prover = MockProver::run(5, &circuit, vec![expected]).unwrap();
+        assert_ne!(prover.verify(), Ok(()));
+    }
+
+    #[test]
+    pub fn test_native_power() {
+        let mut elements = [Fr::from(3u64)];
+        PowerSBox::<Fr, 5>::default().apply(&mut elements);
+        assert_eq!(elements[0], Fr::from(243u64));
+        PowerSBox::<Fr, 3>::default().apply(&mut elements);
+        assert_eq!(elements[0], Fr::from(243u64).square() * Fr::from(243u64));
+    }
+
+    #[test]
+    pub fn test_cubic() {
+        run::<CubicSBoxChip<Fr>>(PowerSBox::<Fr, 3>::default());
+    }
+    #[test]
+    pub fn test_quintic() {
+        run::<QuinticSBoxChip<Fr>>(PowerSBox::<Fr, 5>::default());
+    }
+    #[test]
+    pub fn test_septic() {
+        run::<SepticSBoxChip<Fr>>(PowerSBox::<Fr, 7>::default());
+    }
+
     #[test]
-    pub fn it_works() {}
+    pub fn test_native_inverse() {
+        let mut elements = [Fr::from(243u64)];
+        InversePowerSBox::<Fr, 5>::default().apply(&mut elements);
+        assert_eq!(elements[0], Fr::from(3u64));
+
+        let mut elements = inputs();
+        PowerSBox::<Fr, 7>::default().apply(&mut elements);
+        InversePowerSBox::<Fr, 7>::default().apply(&mut elements);
+        assert_eq!(elements, inputs());
+    }
+
+    #[test]
+    pub fn test_inverse() {
+        run::<QuinticInverseSBoxChip<Fr>>(InversePowerSBox::<Fr, 5>::default());
+        run::<SepticInverseSBoxChip<Fr>>(InversePowerSBox::<Fr, 7>::default());
+    }
 }
@@ -0,0 +1,359 @@
+use crate::mydemo::mimc::constants::{is_permutation, number_of_rounds, round_constants};
+use crate::mydemo::mimc::permutation::{MiMCChip, MiMCConfig};
+use crate::mydemo::mimc::sc_box::{CsHasher, Hasher, PowerSBox, SBox};
+use halo2_proofs::arithmetic::FieldExt;
+use halo2_proofs::circuit::{AssignedCell, Layouter};
+use halo2_proofs::plonk::{ConstraintSystem, Error};
+
+// MiMC sponge
+// state = [rate | capacity], 宽度 t = RATE + CAPACITY
+// 置换用的是推广到t个分支的 MiMC-Feistel, key 固定为0:
+// (x_0, x_1, ..., x_{t-1}) -> (x_1 + (x_0 + c_i)^e, x_2, ..., x_{t-1}, x_0)
+// 最后一轮不轮换, t=2 时就是 MiMCFeistel
+// 轮数为 t * r
+//
+// 为了区分不同长度的输入, capacity 的第一个元素初始化为输入的个数
+// absorb: 每 RATE 个输入加到 state[0..RATE] 上然后置换
+// squeeze: 输出 state[0..RATE], 不够的话再置换
+pub struct MiMCSponge<F: FieldExt, const E: u64, const RATE: usize, const CAPACITY: usize> {
+    sbox: PowerSBox<F, E>,
+    constants: Vec<F>,
+}
+
+impl<F: FieldExt, const E: u64, const RATE: usize, const CAPACITY: usize> Default
+    for MiMCSponge<F, E, RATE, CAPACITY>
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F: FieldExt, const E: u64, const RATE: usize, const CAPACITY: usize>
+    MiMCSponge<F, E, RATE, CAPACITY>
+{
+    pub const WIDTH: usize = RATE + CAPACITY;
+
+    pub fn new() -> Self {
+        assert!(RATE > 0 && CAPACITY > 0);
+        assert!(is_permutation::<F>(E), "x^{} is not a permutation", E);
+        Self {
+            sbox: Default::default(),
+            constants: round_constants(b"mimc-sponge", Self::WIDTH * number_of_rounds::<F>(E)),
+        }
+    }
+
+    pub fn constants(&self) -> &[F] {
+        &self.constants
+    }
+
+    pub fn permute(&self, state: &mut [F]) {
+        assert_eq!(state.len(), Self::WIDTH);
+        let last = self.constants.len() - 1;
+        for (i, c) in self.constants.iter().enumerate() {
+            let mut f = [state[0] + c];
+            self.sbox.apply(&mut f);
+            state[1] += f[0];
+            if i < last {
+                state.rotate_left(1);
+            }
+        }
+    }
+
+    pub fn hash(&self, inputs: &[F], outputs: usize) -> Vec<F> {
+        let mut state = vec![F::zero(); Self::WIDTH];
+        state[RATE] = F::from(inputs.len() as u64);
+
+        let mut chunks: Vec<&[F]> = inputs.chunks(RATE).collect();
+        if chunks.is_empty() {
+            chunks.push(&[]);
+        }
+        for chunk in chunks {
+            for (s, input) in state.iter_mut().zip(chunk.iter()) {
+                *s += input;
+            }
+            self.permute(&mut state);
+        }
+
+        let mut result = Vec::with_capacity(outputs);
+        loop {
+            for s in state[0..RATE].iter() {
+                if result.len() == outputs {
+                    return result;
+                }
+                result.push(*s);
+            }
+            self.permute(&mut state);
+        }
+    }
+}
+
+// 作为 Hasher 时只取第一个输出
+impl<F: FieldExt, const E: u64, const RATE: usize, const CAPACITY: usize> Hasher<F>
+    for MiMCSponge<F, E, RATE, CAPACITY>
+{
+    fn hash(&self, inputs: &[F]) -> F {
+        MiMCSponge::hash(self, inputs, 1)[0]
+    }
+}
+
+pub struct MiMCSpongeChip<F: FieldExt, const E: u64, const RATE: usize, const CAPACITY: usize> {
+    mimc: MiMCChip<F, E>,
+    sponge: MiMCSponge<F, E, RATE, CAPACITY>,
+}
+
+impl<F: FieldExt, const E: u64, const RATE: usize, const CAPACITY: usize>
+    MiMCSpongeChip<F, E, RATE, CAPACITY>
+{
+    pub fn construct(config: MiMCConfig) -> Self {
+        Self {
+            mimc: MiMCChip::construct(config),
+            sponge: MiMCSponge::new(),
+        }
+    }
+
+    // 和 MiMCChip 用同一套gate
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> MiMCConfig {
+        MiMCChip::<F, E>::configure(meta)
+    }
+
+    pub fn load_private(
+        &self,
+        layouter: impl Layouter<F>,
+        value: Option<F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        self.mimc.load_private(layouter, value)
+    }
+
+    pub fn permute(
+        &self,
+        mut layouter: impl Layouter<F>,
+        state: Vec<AssignedCell<F, F>>,
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        let mut state = state;
+        let zero = self
+            .mimc
+            .load_constant(layouter.namespace(|| "zero"), F::zero())?;
+        let last = self.sponge.constants().len() - 1;
+        for (i, c) in self.sponge.constants().iter().enumerate() {
+            let mut layouter = layouter.namespace(|| format!("round {}", i));
+            let f = self
+                .mimc
+                .round(layouter.namespace(|| "f"), &state[0], &zero, *c)?;
+            state[1] = self
+                .mimc
+                .add(layouter.namespace(|| "x1 + f"), &state[1], &f, F::zero())?;
+            if i < last {
+                state.rotate_left(1);
+            }
+        }
+        Ok(state)
+    }
+
+    pub fn hash(
+        &self,
+        mut layouter: impl Layouter<F>,
+        inputs: &[AssignedCell<F, F>],
+        outputs: usize,
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        let width = RATE + CAPACITY;
+        let mut state = (0..width)
+            .map(|i| {
+                // capacity 的第一个元素是输入的个数, 其它都是0
+                let init = if i == RATE {
+                    F::from(inputs.len() as u64)
+                } else {
+                    F::zero()
+                };
+                self.mimc
+                    .load_constant(layouter.namespace(|| format!("init state {}", i)), init)
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        let mut chunks: Vec<&[AssignedCell<F, F>]> = inputs.chunks(RATE).collect();
+        if chunks.is_empty() {
+            chunks.push(&[]);
+        }
+        for (i, chunk) in chunks.into_iter().enumerate() {
+            let mut layouter = layouter.namespace(|| format!("absorb {}", i));
+            for (j, input) in chunk.iter().enumerate() {
+                state[j] = self.mimc.add(
+                    layouter.namespace(|| format!("absorb input {}", j)),
+                    &state[j],
+                    input,
+                    F::zero(),
+                )?;
+            }
+            state = self.permute(layouter.namespace(|| "permute"), state)?;
+        }
+
+        let mut result = Vec::with_capacity(outputs);
+        let mut squeeze = 0;
+        loop {
+            for s in state[0..RATE].iter() {
+                if result.len() == outputs {
+                    return Ok(result);
+                }
+                result.push(s.clone());
+            }
+            squeeze += 1;
+            state = self.permute(layouter.namespace(|| format!("squeeze {}", squeeze)), state)?;
+        }
+    }
+}
+
+impl<F: FieldExt, const E: u64, const RATE: usize, const CAPACITY: usize> Hasher<F>
+    for MiMCSpongeChip<F, E, RATE, CAPACITY>
+{
+    fn hash(&self, inputs: &[F]) -> F {
+        self.sponge.hash(inputs, 1)[0]
+    }
+}
+
+impl<F: FieldExt, const E: u64, const RATE: usize, const CAPACITY: usize> CsHasher<F>
+    for MiMCSpongeChip<F, E, RATE, CAPACITY>
+{
+    type Config = MiMCConfig;
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        MiMCSpongeChip::<F, E, RATE, CAPACITY>::configure(meta)
+    }
+
+    fn construct(config: Self::Config) -> Self {
+        MiMCSpongeChip::<F, E, RATE, CAPACITY>::construct(config)
+    }
+
+    fn load_private(
+        &self,
+        cs: impl Layouter<F>,
+        value: Option<F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        MiMCSpongeChip::<F, E, RATE, CAPACITY>::load_private(self, cs, value)
+    }
+
+    fn assign_hash(
+        &self,
+        cs: impl Layouter<F>,
+        inputs: &[AssignedCell<F, F>],
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let mut outputs = MiMCSpongeChip::<F, E, RATE, CAPACITY>::hash(self, cs, inputs, 1)?;
+        Ok(outputs.remove(0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mydemo::mimc::permutation::MiMCConfig;
+    use crate::mydemo::mimc::sponge::{MiMCSponge, MiMCSpongeChip};
+    use crate::zk::wrapper::from_hex;
+    use halo2_proofs::arithmetic::FieldExt;
+    use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner};
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::pairing::bn256::Fr;
+    use halo2_proofs::plonk::{Circuit, Column, ConstraintSystem, Error, Instance};
+
+    #[derive(Clone, Debug)]
+    pub struct TestConfig {
+        mimc: MiMCConfig,
+        instance: Column<Instance>,
+    }
+
+    pub struct SpongeCircuit<F: FieldExt, const RATE: usize, const CAPACITY: usize> {
+        inputs: Vec<Option<F>>,
+        outputs: usize,
+    }
+
+    impl<F: FieldExt, const RATE: usize, const CAPACITY: usize> Circuit<F>
+        for SpongeCircuit<F, RATE, CAPACITY>
+    {
+        type Config = TestConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                inputs: vec![None; self.inputs.len()],
+                outputs: self.outputs,
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            TestConfig {
+                mimc: MiMCSpongeChip::<F, 7, RATE, CAPACITY>::configure(meta),
+                instance,
+            }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let chip = MiMCSpongeChip::<F, 7, RATE, CAPACITY>::construct(config.mimc);
+            let inputs = self
+                .inputs
+                .iter()
+                .map(|v| chip.load_private(layouter.namespace(|| "input"), *v))
+                .collect::<Result<Vec<_>, Error>>()?;
+            let outputs = chip.hash(layouter.namespace(|| "hash"), &inputs, self.outputs)?;
+            for (i, out) in outputs.iter().enumerate() {
+                layouter.constrain_instance(out.cell(), config.instance, i)?;
+            }
+            Ok(())
+        }
+    }
+
+    fn inputs(n: u64) -> Vec<Fr> {
+        (1..=n).map(Fr::from).collect()
+    }
+
+    #[test]
+    pub fn test_native_known_answer() {
+        let sponge = MiMCSponge::<Fr, 7, 1, 1>::new();
+        assert_eq!(
+            sponge.hash(&inputs(2), 1),
+            vec![from_hex::<Fr>(
+                "0cfea55f5376b5a93c7f215a48651b5731cd549648947953cc881a3c151f5646"
+            )]
+        );
+
+        let sponge = MiMCSponge::<Fr, 7, 2, 1>::new();
+        assert_eq!(
+            sponge.hash(&inputs(3), 3),
+            vec![
+                from_hex::<Fr>("2597e921f1d28ba9654226242d8b4a79d2ced959831b7aa446b2cd87a3688b7b"),
+                from_hex::<Fr>("08b7300d1d6a4e6f9810a3fadde70758d26cea56b14e9a6413eb4567e7cb5705"),
+                from_hex::<Fr>("2a66d39ba72b39058ea28408464ab82e473005d5bc5284389ff149543baf0e9d"),
+            ]
+        );
+    }
+
+    #[test]
+    pub fn test_length_is_absorbed() {
+        let sponge = MiMCSponge::<Fr, 7, 2, 1>::new();
+        let mut padded = inputs(3);
+        padded.push(Fr::zero());
+        assert_ne!(sponge.hash(&inputs(3), 1), sponge.hash(&padded, 1));
+        assert_ne!(sponge.hash(&[], 1), sponge.hash(&[Fr::zero()], 1));
+    }
+
+    #[test]
+    pub fn test_sponge_chip() {
+        let sponge = MiMCSponge::<Fr, 7, 2, 1>::new();
+        for (n, outputs) in [(0, 1), (3, 1), (4, 3)] {
+            let inputs = inputs(n);
+            let expected = sponge.hash(&inputs, outputs);
+            let circuit = SpongeCircuit::<Fr, 2, 1> {
+                inputs: inputs.iter().map(|v| Some(*v)).collect(),
+                outputs,
+            };
+            let prover = MockProver::run(13, &circuit, vec![expected.clone()]).unwrap();
+            assert_eq!(prover.verify(), Ok(()));
+
+            let mut wrong = expected;
+            wrong[0] += Fr::one();
+            let prover = MockProver::run(13, &circuit, vec![wrong]).unwrap();
+            assert_ne!(prover.verify(), Ok(()));
+        }
+    }
+}
@@ -1,4 +1,24 @@
 mod a_equals_b;
 mod a_plus_b_eq_c;
+mod arithmetic;
+mod bigint;
+mod bits;
+mod blake2s;
+mod boolean;
+#[cfg(test)]
+pub(crate) mod differential;
+mod inverse;
+mod int_div;
+mod int_sqrt;
+mod is_zero;
+mod keccak;
+mod main_gate;
+mod merkle;
 mod mimc;
+mod mux;
+mod pedersen;
+mod poseidon;
 mod range_check;
+mod rescue;
+mod sha256;
+mod word;
@@ -0,0 +1,316 @@
+use halo2_proofs::arithmetic::FieldExt;
+use halo2_proofs::circuit::{AssignedCell, Layouter};
+use halo2_proofs::plonk::{
+    Advice, Column, ConstraintSystem, Error, Expression, Fixed, Instance, Selector,
+};
+use halo2_proofs::poly::Rotation;
+use std::marker::PhantomData;
+
+// select: cond | a | b | out
+// cond * (1 - cond) = 0
+// out = cond * (a - b) + b
+//
+// mux: 用 one-hot 的 e_j 表示 index, 每行一个输入
+// e | x | sum | idx | acc | position
+// e_j * (1 - e_j) = 0
+// sum_next = sum + e_j
+// idx_next = idx + j * e_j
+// acc_next = acc + e_j * x_j
+// 第一行 sum = idx = acc = 0, 最后一行 sum = 1, idx 等于输入的 index, acc 就是结果
+#[derive(Clone, Debug)]
+pub struct MuxConfig {
+    pub advice: [Column<Advice>; 5],
+    pub position: Column<Fixed>,
+    pub constant: Column<Fixed>,
+    pub s_select: Selector,
+    pub s_mux: Selector,
+    pub instance: Column<Instance>,
+}
+
+pub struct MuxChip<F: FieldExt> {
+    config: MuxConfig,
+    _p: PhantomData<F>,
+}
+
+impl<F: FieldExt> MuxChip<F> {
+    pub fn construct(config: MuxConfig) -> Self {
+        Self {
+            config,
+            _p: Default::default(),
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> MuxConfig {
+        let advice = [(); 5].map(|_| meta.advice_column());
+        let position = meta.fixed_column();
+        let constant = meta.fixed_column();
+        let s_select = meta.selector();
+        let s_mux = meta.selector();
+        let instance = meta.instance_column();
+
+        for column in advice {
+            meta.enable_equality(column);
+        }
+        meta.enable_equality(instance);
+        meta.enable_constant(constant);
+
+        let one = Expression::Constant(F::one());
+        meta.create_gate("select", |meta| {
+            let s = meta.query_selector(s_select);
+            let cond = meta.query_advice(advice[0], Rotation::cur());
+            let a = meta.query_advice(advice[1], Rotation::cur());
+            let b = meta.query_advice(advice[2], Rotation::cur());
+            let out = meta.query_advice(advice[3], Rotation::cur());
+            vec![
+                s.clone() * cond.clone() * (one.clone() - cond.clone()),
+                s * (cond * (a - b.clone()) + b - out),
+            ]
+        });
+
+        meta.create_gate("mux", |meta| {
+            let s = meta.query_selector(s_mux);
+            let e = meta.query_advice(advice[0], Rotation::cur());
+            let x = meta.query_advice(advice[1], Rotation::cur());
+            let [sum, idx, acc] = [2, 3, 4].map(|i| meta.query_advice(advice[i], Rotation::cur()));
+            let [sum_next, idx_next, acc_next] =
+                [2, 3, 4].map(|i| meta.query_advice(advice[i], Rotation::next()));
+            let position = meta.query_fixed(position, Rotation::cur());
+            vec![
+                s.clone() * e.clone() * (one.clone() - e.clone()),
+                s.clone() * (sum + e.clone() - sum_next),
+                s.clone() * (idx + position * e.clone() - idx_next),
+                s * (acc + e * x - acc_next),
+            ]
+        });
+
+        MuxConfig {
+            advice,
+            position,
+            constant,
+            s_select,
+            s_mux,
+            instance,
+        }
+    }
+
+    pub fn load_private(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Option<F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        layouter.assign_region(
+            || "load private",
+            |mut region| {
+                region.assign_advice(
+                    || "private",
+                    self.config.advice[0],
+                    0,
+                    || value.ok_or(Error::Synthesis),
+                )
+            },
+        )
+    }
+
+    // cond 为1时返回 a, 为0时返回 b, cond 不是bool时约束不满足
+    pub fn select(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cond: &AssignedCell<F, F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        layouter.assign_region(
+            || "select",
+            |mut region| {
+                self.config.s_select.enable(&mut region, 0)?;
+                let cond = cond.copy_advice(|| "cond", &mut region, self.config.advice[0], 0)?;
+                let a = a.copy_advice(|| "a", &mut region, self.config.advice[1], 0)?;
+                let b = b.copy_advice(|| "b", &mut region, self.config.advice[2], 0)?;
+                let out = cond.value().and_then(|c| {
+                    a.value()
+                        .and_then(|a| b.value().map(|b| *c * (*a - *b) + *b))
+                });
+                region.assign_advice(
+                    || "out",
+                    self.config.advice[3],
+                    0,
+                    || out.ok_or(Error::Synthesis),
+                )
+            },
+        )
+    }
+
+    // 返回 inputs[index], index 超出范围时约束不满足
+    pub fn mux(
+        &self,
+        mut layouter: impl Layouter<F>,
+        index: &AssignedCell<F, F>,
+        inputs: &[AssignedCell<F, F>],
+    ) -> Result<AssignedCell<F, F>, Error> {
+        assert!(!inputs.is_empty());
+        layouter.assign_region(
+            || "mux",
+            |mut region| {
+                let [e, x, sum, idx, acc] = self.config.advice;
+                for (column, name) in [(sum, "sum"), (idx, "idx"), (acc, "acc")] {
+                    region.assign_advice_from_constant(|| name, column, 0, F::zero())?;
+                }
+
+                let one_hot = (0..inputs.len())
+                    .map(|j| index.value().map(|i| *i == F::from(j as u64)))
+                    .collect::<Vec<_>>();
+                let mut state = Some((F::zero(), F::zero(), F::zero()));
+                for (j, input) in inputs.iter().enumerate() {
+                    self.config.s_mux.enable(&mut region, j)?;
+                    region.assign_fixed(
+                        || "position",
+                        self.config.position,
+                        j,
+                        || Ok(F::from(j as u64)),
+                    )?;
+                    let flag = one_hot[j].map(|b| F::from(b as u64));
+                    region.assign_advice(|| "e", e, j, || flag.ok_or(Error::Synthesis))?;
+                    input.copy_advice(|| "x", &mut region, x, j)?;
+
+                    state = state.and_then(|(s, i, a)| {
+                        let flag = flag?;
+                        let value = *input.value()?;
+                        Some((s + flag, i + F::from(j as u64) * flag, a + flag * value))
+                    });
+                    let row = j + 1;
+                    // 最后一行的 sum 和 idx 要绑定到 1 和 index
+                    if row < inputs.len() {
+                        region.assign_advice(
+                            || "sum",
+                            sum,
+                            row,
+                            || state.map(|s| s.0).ok_or(Error::Synthesis),
+                        )?;
+                        region.assign_advice(
+                            || "idx",
+                            idx,
+                            row,
+                            || state.map(|s| s.1).ok_or(Error::Synthesis),
+                        )?;
+                        region.assign_advice(
+                            || "acc",
+                            acc,
+                            row,
+                            || state.map(|s| s.2).ok_or(Error::Synthesis),
+                        )?;
+                    }
+                }
+
+                let last = inputs.len();
+                region.assign_advice_from_constant(|| "sum", sum, last, F::one())?;
+                index.copy_advice(|| "idx", &mut region, idx, last)?;
+                region.assign_advice(
+                    || "out",
+                    acc,
+                    last,
+                    || state.map(|s| s.2).ok_or(Error::Synthesis),
+                )
+            },
+        )
+    }
+
+    pub fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: &AssignedCell<F, F>,
+        row: usize,
+    ) -> Result<(), Error> {
+        layouter.constrain_instance(cell.cell(), self.config.instance, row)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mydemo::mux::{MuxChip, MuxConfig};
+    use halo2_proofs::arithmetic::FieldExt;
+    use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner};
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::pairing::bn256::Fr;
+    use halo2_proofs::plonk::{Circuit, ConstraintSystem, Error};
+
+    // 暴露 select(cond, inputs[0], inputs[1]) 和 inputs[index]
+    #[derive(Default)]
+    pub struct MuxCircuit<F: FieldExt> {
+        cond: Option<F>,
+        index: Option<F>,
+        inputs: Vec<Option<F>>,
+    }
+
+    impl<F: FieldExt> Circuit<F> for MuxCircuit<F> {
+        type Config = MuxConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                cond: None,
+                index: None,
+                inputs: vec![None; self.inputs.len()],
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            MuxChip::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let chip = MuxChip::construct(config);
+            let cond = chip.load_private(layouter.namespace(|| "cond"), self.cond)?;
+            let index = chip.load_private(layouter.namespace(|| "index"), self.index)?;
+            let inputs = self
+                .inputs
+                .iter()
+                .map(|v| chip.load_private(layouter.namespace(|| "input"), *v))
+                .collect::<Result<Vec<_>, Error>>()?;
+
+            let selected = chip.select(
+                layouter.namespace(|| "select"),
+                &cond,
+                &inputs[0],
+                &inputs[1],
+            )?;
+            let out = chip.mux(layouter.namespace(|| "mux"), &index, &inputs)?;
+            chip.expose_public(layouter.namespace(|| "selected"), &selected, 0)?;
+            chip.expose_public(layouter.namespace(|| "out"), &out, 1)
+        }
+    }
+
+    fn prove(cond: u64, index: u64, expected: [u64; 2]) -> bool {
+        let inputs = [10u64, 20, 30, 40, 50];
+        let circuit = MuxCircuit {
+            cond: Some(Fr::from(cond)),
+            index: Some(Fr::from(index)),
+            inputs: inputs.iter().map(|v| Some(Fr::from(*v))).collect(),
+        };
+        let instance = expected.iter().map(|v| Fr::from(*v)).collect();
+        let prover = MockProver::run(5, &circuit, vec![instance]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    pub fn test_select() {
+        assert!(prove(1, 0, [10, 10]));
+        assert!(prove(0, 0, [20, 10]));
+        assert!(!prove(1, 0, [20, 10]));
+        // cond 不是bool: 2 * (10 - 20) + 20 = 0
+        assert!(!prove(2, 0, [0, 10]));
+    }
+
+    #[test]
+    pub fn test_mux() {
+        for (index, expected) in [10, 20, 30, 40, 50].into_iter().enumerate() {
+            assert!(prove(1, index as u64, [10, expected]));
+            assert!(!prove(1, index as u64, [10, expected + 10]));
+        }
+        // index 超出范围
+        assert!(!prove(1, 5, [10, 0]));
+    }
+}
@@ -0,0 +1,471 @@
+use crate::mydemo::pedersen::curve::{generator, to_bits, Point, A, D};
+use halo2_proofs::arithmetic::FieldExt;
+use halo2_proofs::circuit::{AssignedCell, Layouter, Region};
+use halo2_proofs::plonk::{
+    Advice, Column, ConstraintSystem, Error, Expression, Fixed, Instance, Selector,
+};
+use halo2_proofs::poly::Rotation;
+
+// 每个窗口3个bit, 窗口 i 的8个候选点是 j * 8^i * B, j in [0, 8)
+pub const WINDOW: usize = 3;
+pub const WINDOW_SIZE: usize = 1 << WINDOW;
+
+// 子群的阶 l 是 251 bit, 标量小于 2^249 时不同的标量对应不同的点
+// commit 里的 v 和 hash 的输入都必须在这个范围内, 否则可以找到碰撞
+pub const MAX_BITS: usize = 249;
+
+// 随机数 r 分成 85 个窗口, 可以放下任意的 Fr
+pub const RANDOMNESS_BITS: usize = 255;
+
+// b0 | b1 | b2 | px | py | x | y | sum
+// window: b 是 bool, (px, py) = sum_j L_j(b) * (X_j, Y_j), L_j 是 b == j 时为1的 lagrange 多项式
+//         sum(next) = sum + (b0 + 2 * b1 + 4 * b2) * pow, pow = 8^i
+// add:    (x, y)(next) = (x, y) + (px, py), twisted Edwards 加法
+#[derive(Clone, Debug)]
+pub struct PedersenConfig {
+    pub bits: [Column<Advice>; WINDOW],
+    pub px: Column<Advice>,
+    pub py: Column<Advice>,
+    pub x: Column<Advice>,
+    pub y: Column<Advice>,
+    pub sum: Column<Advice>,
+    pub table_x: [Column<Fixed>; WINDOW_SIZE],
+    pub table_y: [Column<Fixed>; WINDOW_SIZE],
+    pub pow: Column<Fixed>,
+    pub constant: Column<Fixed>,
+    pub s_window: Selector,
+    pub s_add: Selector,
+    pub instance: Column<Instance>,
+}
+
+#[derive(Clone, Debug)]
+pub struct AssignedPoint<F: FieldExt> {
+    pub x: AssignedCell<F, F>,
+    pub y: AssignedCell<F, F>,
+}
+
+impl<F: FieldExt> AssignedPoint<F> {
+    pub fn value(&self) -> Option<Point<F>> {
+        self.x
+            .value()
+            .and_then(|x| self.y.value().map(|y| Point { x: *x, y: *y }))
+    }
+}
+
+// commit(v, r) = v * G + r * H, hash(m_0, ..., m_n) = (sum m_i * G_{i+2}).x
+// G, H, G_i 都是 generator(i) 生成的固定点
+pub struct PedersenChip<F: FieldExt> {
+    config: PedersenConfig,
+    g: Point<F>,
+    h: Point<F>,
+}
+
+impl<F: FieldExt> PedersenChip<F> {
+    pub fn construct(config: PedersenConfig) -> Self {
+        Self {
+            config,
+            g: generator(0),
+            h: generator(1),
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> PedersenConfig {
+        let bits = [(); WINDOW].map(|_| meta.advice_column());
+        let px = meta.advice_column();
+        let py = meta.advice_column();
+        let x = meta.advice_column();
+        let y = meta.advice_column();
+        let sum = meta.advice_column();
+        let table_x = [(); WINDOW_SIZE].map(|_| meta.fixed_column());
+        let table_y = [(); WINDOW_SIZE].map(|_| meta.fixed_column());
+        let pow = meta.fixed_column();
+        let constant = meta.fixed_column();
+        let s_window = meta.selector();
+        let s_add = meta.selector();
+        let instance = meta.instance_column();
+
+        for column in [px, py, x, y, sum] {
+            meta.enable_equality(column);
+        }
+        meta.enable_equality(instance);
+        meta.enable_constant(constant);
+
+        meta.create_gate("pedersen window", |meta| {
+            let s = meta.query_selector(s_window);
+            let one = Expression::Constant(F::one());
+            let b = bits.map(|b| meta.query_advice(b, Rotation::cur()));
+            let px = meta.query_advice(px, Rotation::cur());
+            let py = meta.query_advice(py, Rotation::cur());
+            let sum_next = meta.query_advice(sum, Rotation::next());
+            let sum = meta.query_advice(sum, Rotation::cur());
+            let pow = meta.query_fixed(pow, Rotation::cur());
+
+            let mut constraints: Vec<Expression<F>> = b
+                .iter()
+                .map(|b| b.clone() * (one.clone() - b.clone()))
+                .collect();
+            let (mut x, mut y) = (
+                Expression::Constant(F::zero()),
+                Expression::Constant(F::zero()),
+            );
+            for j in 0..WINDOW_SIZE {
+                let lagrange = (0..WINDOW).fold(one.clone(), |acc, k| {
+                    if (j >> k) & 1 == 1 {
+                        acc * b[k].clone()
+                    } else {
+                        acc * (one.clone() - b[k].clone())
+                    }
+                });
+                x = x + lagrange.clone() * meta.query_fixed(table_x[j], Rotation::cur());
+                y = y + lagrange * meta.query_fixed(table_y[j], Rotation::cur());
+            }
+            constraints.push(px - x);
+            constraints.push(py - y);
+
+            let window = b[0].clone()
+                + b[1].clone() * Expression::Constant(F::from(2u64))
+                + b[2].clone() * Expression::Constant(F::from(4u64));
+            constraints.push(sum_next - sum - window * pow);
+            constraints
+                .into_iter()
+                .map(|c| s.clone() * c)
+                .collect::<Vec<_>>()
+        });
+
+        meta.create_gate("edwards add", |meta| {
+            let s = meta.query_selector(s_add);
+            let one = Expression::Constant(F::one());
+            let x1 = meta.query_advice(x, Rotation::cur());
+            let y1 = meta.query_advice(y, Rotation::cur());
+            let x2 = meta.query_advice(px, Rotation::cur());
+            let y2 = meta.query_advice(py, Rotation::cur());
+            let x3 = meta.query_advice(x, Rotation::next());
+            let y3 = meta.query_advice(y, Rotation::next());
+            let t = Expression::Constant(F::from(D))
+                * x1.clone()
+                * x2.clone()
+                * y1.clone()
+                * y2.clone();
+            vec![
+                s.clone()
+                    * (x3 * (one.clone() + t.clone())
+                        - x1.clone() * y2.clone()
+                        - y1.clone() * x2.clone()),
+                s * (y3 * (one - t) - y1 * y2 + Expression::Constant(F::from(A)) * x1 * x2),
+            ]
+        });
+
+        PedersenConfig {
+            bits,
+            px,
+            py,
+            x,
+            y,
+            sum,
+            table_x,
+            table_y,
+            pow,
+            constant,
+            s_window,
+            s_add,
+            instance,
+        }
+    }
+
+    pub fn load_private(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Option<F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        layouter.assign_region(
+            || "load private",
+            |mut region| {
+                region.assign_advice(
+                    || "private",
+                    self.config.sum,
+                    0,
+                    || value.ok_or(Error::Synthesis),
+                )
+            },
+        )
+    }
+
+    fn assign_point(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        point: Option<Point<F>>,
+    ) -> Result<AssignedPoint<F>, Error> {
+        let x = region.assign_advice(
+            || "x",
+            self.config.x,
+            offset,
+            || point.map(|p| p.x).ok_or(Error::Synthesis),
+        )?;
+        let y = region.assign_advice(
+            || "y",
+            self.config.y,
+            offset,
+            || point.map(|p| p.y).ok_or(Error::Synthesis),
+        )?;
+        Ok(AssignedPoint { x, y })
+    }
+
+    // 固定点的标量乘法, scalar < 2^num_bits, num_bits 向上取整到3的倍数
+    // 第一行是单位元, 每个窗口加上查出来的点, 最后一行的 sum 必须等于 scalar
+    pub fn mul_fixed(
+        &self,
+        mut layouter: impl Layouter<F>,
+        scalar: &AssignedCell<F, F>,
+        base: Point<F>,
+        num_bits: usize,
+    ) -> Result<AssignedPoint<F>, Error> {
+        let windows = num_bits.div_ceil(WINDOW);
+        layouter.assign_region(
+            || "fixed base mul",
+            |mut region| {
+                let config = &self.config;
+                let bits = scalar.value().map(|s| to_bits(s, windows * WINDOW));
+
+                region.assign_advice_from_constant(|| "x", config.x, 0, F::zero())?;
+                region.assign_advice_from_constant(|| "y", config.y, 0, F::one())?;
+                region.assign_advice_from_constant(|| "sum", config.sum, 0, F::zero())?;
+
+                let mut acc = Some(Point::identity());
+                let mut sum = Some(F::zero());
+                let mut power = base;
+                let mut result = None;
+                for i in 0..windows {
+                    config.s_window.enable(&mut region, i)?;
+                    config.s_add.enable(&mut region, i)?;
+
+                    // 8个候选点 j * 8^i * B
+                    let mut candidate = Point::identity();
+                    let mut table = Vec::with_capacity(WINDOW_SIZE);
+                    for j in 0..WINDOW_SIZE {
+                        region.assign_fixed(|| "x", config.table_x[j], i, || Ok(candidate.x))?;
+                        region.assign_fixed(|| "y", config.table_y[j], i, || Ok(candidate.y))?;
+                        table.push(candidate);
+                        candidate = candidate.add(&power);
+                    }
+                    let pow = F::from(WINDOW_SIZE as u64).pow(&[i as u64, 0, 0, 0]);
+                    region.assign_fixed(|| "pow", config.pow, i, || Ok(pow))?;
+                    power = candidate;
+
+                    let window = bits.as_ref().map(|bits| {
+                        (0..WINDOW).fold(0, |acc, k| acc | (bits[i * WINDOW + k] as usize) << k)
+                    });
+                    for k in 0..WINDOW {
+                        region.assign_advice(
+                            || "bit",
+                            config.bits[k],
+                            i,
+                            || {
+                                window
+                                    .map(|w| F::from(((w >> k) & 1) as u64))
+                                    .ok_or(Error::Synthesis)
+                            },
+                        )?;
+                    }
+                    let point = window.map(|w| table[w]);
+                    region.assign_advice(
+                        || "px",
+                        config.px,
+                        i,
+                        || point.map(|p| p.x).ok_or(Error::Synthesis),
+                    )?;
+                    region.assign_advice(
+                        || "py",
+                        config.py,
+                        i,
+                        || point.map(|p| p.y).ok_or(Error::Synthesis),
+                    )?;
+
+                    acc = acc.and_then(|acc| point.map(|p| acc.add(&p)));
+                    sum = sum.and_then(|s| window.map(|w| s + F::from(w as u64) * pow));
+                    result = Some(self.assign_point(&mut region, i + 1, acc)?);
+                    let sum_cell = region.assign_advice(
+                        || "sum",
+                        config.sum,
+                        i + 1,
+                        || sum.ok_or(Error::Synthesis),
+                    )?;
+                    if i + 1 == windows {
+                        region.constrain_equal(sum_cell.cell(), scalar.cell())?;
+                    }
+                }
+                Ok(result.expect("at least one window"))
+            },
+        )
+    }
+
+    pub fn add(
+        &self,
+        mut layouter: impl Layouter<F>,
+        p: &AssignedPoint<F>,
+        q: &AssignedPoint<F>,
+    ) -> Result<AssignedPoint<F>, Error> {
+        layouter.assign_region(
+            || "point add",
+            |mut region| {
+                self.config.s_add.enable(&mut region, 0)?;
+                p.x.copy_advice(|| "x", &mut region, self.config.x, 0)?;
+                p.y.copy_advice(|| "y", &mut region, self.config.y, 0)?;
+                q.x.copy_advice(|| "px", &mut region, self.config.px, 0)?;
+                q.y.copy_advice(|| "py", &mut region, self.config.py, 0)?;
+                let sum = p.value().and_then(|p| q.value().map(|q| p.add(&q)));
+                self.assign_point(&mut region, 1, sum)
+            },
+        )
+    }
+
+    // v * G + r * H, v 是 num_bits 位的, 比如已经检查过范围的金额
+    pub fn commit(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+        randomness: &AssignedCell<F, F>,
+        num_bits: usize,
+    ) -> Result<AssignedPoint<F>, Error> {
+        assert!(num_bits <= MAX_BITS);
+        let vg = self.mul_fixed(layouter.namespace(|| "v * G"), value, self.g, num_bits)?;
+        let rh = self.mul_fixed(
+            layouter.namespace(|| "r * H"),
+            randomness,
+            self.h,
+            RANDOMNESS_BITS,
+        )?;
+        self.add(layouter.namespace(|| "vG + rH"), &vg, &rh)
+    }
+
+    // 每个输入按3 bit一段拆开, 第i个输入用第 i+2 个生成元
+    pub fn hash(
+        &self,
+        mut layouter: impl Layouter<F>,
+        inputs: &[AssignedCell<F, F>],
+        num_bits: usize,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        assert!(!inputs.is_empty() && num_bits <= MAX_BITS);
+        let mut acc: Option<AssignedPoint<F>> = None;
+        for (i, input) in inputs.iter().enumerate() {
+            let point = self.mul_fixed(
+                layouter.namespace(|| format!("input {}", i)),
+                input,
+                generator(i as u64 + 2),
+                num_bits,
+            )?;
+            acc = Some(match acc {
+                Some(acc) => self.add(layouter.namespace(|| "add"), &acc, &point)?,
+                None => point,
+            });
+        }
+        Ok(acc.unwrap().x)
+    }
+
+    pub fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: &AssignedCell<F, F>,
+        row: usize,
+    ) -> Result<(), Error> {
+        layouter.constrain_instance(cell.cell(), self.config.instance, row)
+    }
+}
+
+// 和电路一样的计算, 用于生成 public input
+pub fn commit<F: FieldExt>(value: F, randomness: F) -> Point<F> {
+    generator(0)
+        .mul(&value)
+        .add(&generator::<F>(1).mul(&randomness))
+}
+
+pub fn hash<F: FieldExt>(inputs: &[F]) -> F {
+    inputs
+        .iter()
+        .enumerate()
+        .fold(Point::identity(), |acc, (i, m)| {
+            acc.add(&generator(i as u64 + 2).mul(m))
+        })
+        .x
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mydemo::pedersen::chip::{commit, hash, PedersenChip, PedersenConfig};
+    use halo2_proofs::arithmetic::FieldExt;
+    use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner};
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::pairing::bn256::Fr;
+    use halo2_proofs::plonk::{Circuit, ConstraintSystem, Error};
+
+    // 暴露 commit(v, r) 的 x, y 和 hash(v, r)
+    #[derive(Default)]
+    pub struct PedersenCircuit<F: FieldExt> {
+        value: Option<F>,
+        randomness: Option<F>,
+    }
+
+    impl<F: FieldExt> Circuit<F> for PedersenCircuit<F> {
+        type Config = PedersenConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                value: None,
+                randomness: None,
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            PedersenChip::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let chip = PedersenChip::construct(config);
+            let v = chip.load_private(layouter.namespace(|| "v"), self.value)?;
+            let r = chip.load_private(layouter.namespace(|| "r"), self.randomness)?;
+            let commitment = chip.commit(layouter.namespace(|| "commit"), &v, &r, 64)?;
+            chip.expose_public(layouter.namespace(|| "x"), &commitment.x, 0)?;
+            chip.expose_public(layouter.namespace(|| "y"), &commitment.y, 1)?;
+
+            let digest = chip.hash(layouter.namespace(|| "hash"), &[v, r], 249)?;
+            chip.expose_public(layouter.namespace(|| "hash"), &digest, 2)
+        }
+    }
+
+    fn run(value: Fr, randomness: Fr, instance: Vec<Fr>) -> bool {
+        let circuit = PedersenCircuit {
+            value: Some(value),
+            randomness: Some(randomness),
+        };
+        let prover = MockProver::run(9, &circuit, vec![instance]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    pub fn test_commit_and_hash() {
+        let v = Fr::from(1_000_000u64);
+        let r = Fr::from(0x1234_5678_9abc_def0u64).square().square();
+        let c = commit(v, r);
+        let h = hash(&[v, r]);
+        assert!(run(v, r, vec![c.x, c.y, h]));
+
+        // 换一个随机数, commitment 就不一样了
+        let c2 = commit(v, r + Fr::one());
+        assert_ne!(c, c2);
+        assert!(!run(v, r, vec![c2.x, c2.y, h]));
+    }
+
+    #[test]
+    pub fn test_value_out_of_range() {
+        // v 超过了 66 bit, 没办法拆成窗口
+        let v = Fr::from_u128(1 << 70);
+        let r = Fr::from(7u64);
+        let c = commit(v, r);
+        assert!(!run(v, r, vec![c.x, c.y, hash(&[v, r])]));
+    }
+}
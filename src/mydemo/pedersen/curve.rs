@@ -0,0 +1,121 @@
+use crate::mydemo::mimc::constants::round_constants;
+use halo2_proofs::arithmetic::FieldExt;
+
+// Baby Jubjub (EIP-2494), 定义在 bn256 的 Fr 上的 twisted Edwards 曲线:
+// a * x^2 + y^2 = 1 + d * x^2 * y^2
+// 加法公式是完备的, 单位元是 (0, 1), 不需要处理特殊情况
+// 群的阶是 8 * l, l 是 251 bit 的素数
+pub const A: u64 = 168700;
+pub const D: u64 = 168696;
+pub const COFACTOR: u64 = 8;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Point<F: FieldExt> {
+    pub x: F,
+    pub y: F,
+}
+
+impl<F: FieldExt> Point<F> {
+    pub fn identity() -> Self {
+        Self {
+            x: F::zero(),
+            y: F::one(),
+        }
+    }
+
+    pub fn is_on_curve(&self) -> bool {
+        let (x2, y2) = (self.x.square(), self.y.square());
+        F::from(A) * x2 + y2 == F::one() + F::from(D) * x2 * y2
+    }
+
+    // x3 = (x1 * y2 + y1 * x2) / (1 + d * x1 * x2 * y1 * y2)
+    // y3 = (y1 * y2 - a * x1 * x2) / (1 - d * x1 * x2 * y1 * y2)
+    pub fn add(&self, other: &Self) -> Self {
+        let t = F::from(D) * self.x * other.x * self.y * other.y;
+        let x = (self.x * other.y + self.y * other.x) * (F::one() + t).invert().unwrap();
+        let y =
+            (self.y * other.y - F::from(A) * self.x * other.x) * (F::one() - t).invert().unwrap();
+        Self { x, y }
+    }
+
+    // bits 是小端序
+    pub fn mul_bits(&self, bits: &[bool]) -> Self {
+        bits.iter().rev().fold(Self::identity(), |acc, bit| {
+            let acc = acc.add(&acc);
+            if *bit {
+                acc.add(self)
+            } else {
+                acc
+            }
+        })
+    }
+
+    pub fn mul(&self, scalar: &F) -> Self {
+        self.mul_bits(&to_bits(scalar, F::NUM_BITS as usize))
+    }
+}
+
+// 小端序的前 num_bits 个bit
+pub fn to_bits<F: FieldExt>(value: &F, num_bits: usize) -> Vec<bool> {
+    let repr = value.to_repr();
+    let bytes = repr.as_ref();
+    (0..num_bits)
+        .map(|i| i < 8 * bytes.len() && (bytes[i / 8] >> (i % 8)) & 1 == 1)
+        .collect()
+}
+
+// 第 index 个生成元: x = blake2b(seed || i) 依次尝试, 直到在曲线上, 再乘 cofactor 进入素数阶子群
+// 这样生成的点之间的离散对数关系没人知道
+pub fn generator<F: FieldExt>(index: u64) -> Point<F> {
+    let seed = [b"pedersen".as_slice(), &index.to_le_bytes()].concat();
+    round_constants::<F>(&seed, 256)
+        .into_iter()
+        .skip(1)
+        .find_map(|x| {
+            let x2 = x.square();
+            let y2 = (F::one() - F::from(A) * x2) * (F::one() - F::from(D) * x2).invert().unwrap();
+            Option::<F>::from(y2.sqrt()).and_then(|y| {
+                let point = Point { x, y }.mul(&F::from(COFACTOR));
+                (point != Point::identity()).then_some(point)
+            })
+        })
+        .expect("no point found")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mydemo::pedersen::curve::{generator, Point};
+    use crate::zk::wrapper::from_hex;
+    use halo2_proofs::pairing::bn256::Fr;
+
+    // 素数阶子群的阶
+    const ORDER: &str = "060c89ce5c263405370a08b6d0302b0bab3eedb83920ee0a677297dc392126f1";
+
+    #[test]
+    pub fn test_base_point() {
+        // EIP-2494 里的 Base8
+        let base = Point {
+            x: from_hex::<Fr>("0bb77a6ad63e739b4eacb2e09d6277c12ab8d8010534e0b62893f3f6bb957051"),
+            y: from_hex::<Fr>("25797203f7a0b24925572e1cd16bf9edfce0051fb9e133774b3c257a872d7d8b"),
+        };
+        assert!(base.is_on_curve());
+        assert_eq!(base.mul(&from_hex(ORDER)), Point::identity());
+        assert_ne!(base.mul(&Fr::from(5u64)), Point::identity());
+
+        let a = base.mul(&Fr::from(3u64));
+        let b = base.mul(&Fr::from(4u64));
+        assert_eq!(a.add(&b), base.mul(&Fr::from(7u64)));
+        assert!(a.add(&b).is_on_curve());
+    }
+
+    #[test]
+    pub fn test_generators() {
+        let g = generator::<Fr>(0);
+        let h = generator::<Fr>(1);
+        assert_ne!(g, h);
+        for p in [g, h] {
+            assert!(p.is_on_curve());
+            assert_eq!(p.mul(&from_hex(ORDER)), Point::identity());
+        }
+    }
+}
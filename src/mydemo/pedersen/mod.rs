@@ -0,0 +1,2 @@
+mod chip;
+pub(crate) mod curve;
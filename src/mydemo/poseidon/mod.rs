@@ -0,0 +1,2 @@
+mod params;
+pub(crate) mod permutation;
@@ -0,0 +1,159 @@
+use halo2_proofs::arithmetic::FieldExt;
+
+// Poseidon 的参数: 轮常量和MDS矩阵
+// 生成方式和 Poseidon 论文的参考实现 (generate_parameters_grain.sage) 一致:
+// 用 Grain LFSR 生成随机bit, 先生成 (R_F + R_P) * t 个轮常量(拒绝采样),
+// 再生成 2t 个元素 x_i, y_j 构造 Cauchy 矩阵 M[i][j] = 1 / (x_i + y_j)
+#[derive(Clone, Debug)]
+pub struct PoseidonParams<F: FieldExt> {
+    pub width: usize,
+    pub full_rounds: usize,
+    pub partial_rounds: usize,
+    // round_constants[r][i]
+    pub round_constants: Vec<Vec<F>>,
+    pub mds: Vec<Vec<F>>,
+}
+
+// bn256 上 x^5, 128bit 安全性对应的部分轮数, 下标为 t-2
+// 来自参考实现, circomlib 也是用的这组参数
+const BN256_PARTIAL_ROUNDS: [usize; 16] = [
+    56, 57, 56, 60, 60, 63, 64, 63, 60, 66, 60, 65, 70, 60, 64, 68,
+];
+
+impl<F: FieldExt> PoseidonParams<F> {
+    pub fn new(width: usize, full_rounds: usize, partial_rounds: usize) -> Self {
+        assert!(width >= 2);
+        assert_eq!(full_rounds % 2, 0, "full rounds must be even");
+        let mut grain = Grain::new(F::NUM_BITS, width, full_rounds, partial_rounds);
+
+        let round_constants = (0..full_rounds + partial_rounds)
+            .map(|_| (0..width).map(|_| grain.next_field_element()).collect())
+            .collect();
+
+        let mds = loop {
+            let xs: Vec<F> = (0..2 * width)
+                .map(|_| grain.next_field_element_without_rejection())
+                .collect();
+            let distinct = xs
+                .iter()
+                .enumerate()
+                .all(|(i, x)| xs[i + 1..].iter().all(|y| x != y));
+            if !distinct {
+                continue;
+            }
+            let (x, y) = xs.split_at(width);
+            let mds: Option<Vec<Vec<F>>> = x
+                .iter()
+                .map(|x| y.iter().map(|y| Option::from((*x + y).invert())).collect())
+                .collect();
+            if let Some(mds) = mds {
+                break mds;
+            }
+        };
+
+        Self {
+            width,
+            full_rounds,
+            partial_rounds,
+            round_constants,
+            mds,
+        }
+    }
+
+    // bn256 的 Fr 上, alpha = 5, R_F = 8
+    pub fn bn256(width: usize) -> Self {
+        assert!(
+            (2..BN256_PARTIAL_ROUNDS.len() + 2).contains(&width),
+            "unsupported width {}",
+            width
+        );
+        Self::new(width, 8, BN256_PARTIAL_ROUNDS[width - 2])
+    }
+
+    pub fn rounds(&self) -> usize {
+        self.full_rounds + self.partial_rounds
+    }
+
+    // 前 R_F/2 和后 R_F/2 轮是 full round
+    pub fn is_full_round(&self, round: usize) -> bool {
+        round < self.full_rounds / 2 || round >= self.full_rounds / 2 + self.partial_rounds
+    }
+}
+
+// 80bit 的 Grain LFSR
+struct Grain {
+    state: Vec<bool>,
+    num_bits: u32,
+}
+
+impl Grain {
+    fn new(num_bits: u32, width: usize, full_rounds: usize, partial_rounds: usize) -> Self {
+        let mut state = Vec::with_capacity(80);
+        let mut push = |value: u64, bits: usize| {
+            for i in (0..bits).rev() {
+                state.push((value >> i) & 1 == 1);
+            }
+        };
+        // field = 1 (素数域), sbox = 0 (x^alpha)
+        push(1, 2);
+        push(0, 4);
+        push(num_bits as u64, 12);
+        push(width as u64, 12);
+        push(full_rounds as u64, 10);
+        push(partial_rounds as u64, 10);
+        push((1 << 30) - 1, 30);
+
+        let mut grain = Self { state, num_bits };
+        for _ in 0..160 {
+            grain.step();
+        }
+        grain
+    }
+
+    fn step(&mut self) -> bool {
+        let s = &self.state;
+        let bit = s[62] ^ s[51] ^ s[38] ^ s[23] ^ s[13] ^ s[0];
+        self.state.remove(0);
+        self.state.push(bit);
+        bit
+    }
+
+    // 每两个bit一组, 第一个为1时输出第二个, 否则丢弃
+    fn next_bit(&mut self) -> bool {
+        loop {
+            let keep = self.step();
+            let bit = self.step();
+            if keep {
+                return bit;
+            }
+        }
+    }
+
+    // 大端序的 num_bits 个bit, 转为小端序的 repr
+    fn next_repr<F: FieldExt>(&mut self) -> F::Repr {
+        let mut repr = F::Repr::default();
+        let bytes = repr.as_mut();
+        for i in (0..self.num_bits as usize).rev() {
+            if self.next_bit() {
+                bytes[i / 8] |= 1 << (i % 8);
+            }
+        }
+        repr
+    }
+
+    fn next_field_element<F: FieldExt>(&mut self) -> F {
+        loop {
+            if let Some(f) = Option::from(F::from_repr(self.next_repr::<F>())) {
+                return f;
+            }
+        }
+    }
+
+    // 不做拒绝采样, 直接 mod p
+    fn next_field_element_without_rejection<F: FieldExt>(&mut self) -> F {
+        let repr = self.next_repr::<F>();
+        let mut bytes = [0u8; 64];
+        bytes[..repr.as_ref().len()].copy_from_slice(repr.as_ref());
+        F::from_bytes_wide(&bytes)
+    }
+}
@@ -0,0 +1,574 @@
+use crate::mydemo::mimc::sc_box::{CsHasher, CsSBox, Hasher, PowerSBox, SBox};
+use crate::mydemo::poseidon::params::PoseidonParams;
+use halo2_proofs::arithmetic::FieldExt;
+use halo2_proofs::circuit::{AssignedCell, Layouter};
+use halo2_proofs::plonk::{Advice, Column, ConstraintSystem, Error, Expression, Fixed, Selector};
+use halo2_proofs::poly::Rotation;
+
+// Poseidon 置换, 每一轮:
+// 1. ARK: state[i] += rc[r][i]
+// 2. S-box: full round 对所有元素, partial round 只对 state[0]
+// 3. MDS: state = M * state
+pub struct Poseidon<F: FieldExt, S: SBox<F>> {
+    params: PoseidonParams<F>,
+    sbox: S,
+}
+
+impl<F: FieldExt> Poseidon<F, PowerSBox<F, 5>> {
+    pub fn bn256(width: usize) -> Self {
+        Self::new(PoseidonParams::bn256(width), PowerSBox::default())
+    }
+}
+
+impl<F: FieldExt, S: SBox<F>> Poseidon<F, S> {
+    pub fn new(params: PoseidonParams<F>, sbox: S) -> Self {
+        Self { params, sbox }
+    }
+
+    pub fn params(&self) -> &PoseidonParams<F> {
+        &self.params
+    }
+
+    pub fn permute(&self, state: &mut [F]) {
+        let params = &self.params;
+        assert_eq!(state.len(), params.width);
+        for round in 0..params.rounds() {
+            for (s, c) in state.iter_mut().zip(params.round_constants[round].iter()) {
+                *s += c;
+            }
+            if params.is_full_round(round) {
+                self.sbox.apply(state);
+            } else {
+                self.sbox.apply(&mut state[0..1]);
+            }
+            let mixed: Vec<F> = params
+                .mds
+                .iter()
+                .map(|row| {
+                    row.iter()
+                        .zip(state.iter())
+                        .fold(F::zero(), |acc, (m, s)| acc + *m * s)
+                })
+                .collect();
+            state.copy_from_slice(&mixed);
+        }
+    }
+
+    // sponge, rate = width - 1, capacity = 1
+    // capacity 在 state[0], 初始化为 len * 2^64 用于区分不同长度的输入
+    // 输出 state[1]
+    pub fn hash(&self, inputs: &[F]) -> F {
+        let mut state = vec![F::zero(); self.params.width];
+        state[0] = F::from_u128((inputs.len() as u128) << 64);
+
+        let rate = self.params.width - 1;
+        let mut chunks: Vec<&[F]> = inputs.chunks(rate).collect();
+        if chunks.is_empty() {
+            chunks.push(&[]);
+        }
+        for chunk in chunks {
+            for (s, input) in state[1..].iter_mut().zip(chunk.iter()) {
+                *s += input;
+            }
+            self.permute(&mut state);
+        }
+        state[1]
+    }
+}
+
+impl<F: FieldExt, S: SBox<F>> Hasher<F> for Poseidon<F, S> {
+    fn hash(&self, inputs: &[F]) -> F {
+        Poseidon::hash(self, inputs)
+    }
+}
+
+// state[0..t] | rc[0..t](fixed) | s_ark | s_mds | s_absorb
+// ark:    state_next[i] = state_cur[i] + rc[i]
+// mds:    state_next[i] = sum_j M[i][j] * state_cur[j]
+// absorb: state[i](rot 2) = state_cur[i] + state_next[i]
+#[derive(Clone, Debug)]
+pub struct PoseidonConfig<F: FieldExt, C> {
+    pub state: Vec<Column<Advice>>,
+    pub rc: Vec<Column<Fixed>>,
+    pub constant: Column<Fixed>,
+    pub s_ark: Selector,
+    pub s_mds: Selector,
+    pub s_absorb: Selector,
+    pub sbox: C,
+    pub params: PoseidonParams<F>,
+}
+
+// 宽度为 T 的 Poseidon, native 的计算和电路共用同一个 sbox
+pub struct PoseidonChip<F: FieldExt, S: CsSBox<F>, const T: usize> {
+    config: PoseidonConfig<F, S::Config>,
+    poseidon: Poseidon<F, S>,
+}
+
+impl<F: FieldExt, S: CsSBox<F>, const T: usize> PoseidonChip<F, S, T> {
+    pub fn construct(config: PoseidonConfig<F, S::Config>) -> Self {
+        let sbox = S::construct(config.sbox.clone());
+        Self {
+            poseidon: Poseidon::new(config.params.clone(), sbox),
+            config,
+        }
+    }
+
+    // bn256 上的参数
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> PoseidonConfig<F, S::Config> {
+        Self::configure_with_params(meta, PoseidonParams::bn256(T))
+    }
+
+    pub fn configure_with_params(
+        meta: &mut ConstraintSystem<F>,
+        params: PoseidonParams<F>,
+    ) -> PoseidonConfig<F, S::Config> {
+        assert_eq!(params.width, T);
+        let width = params.width;
+        let state: Vec<Column<Advice>> = (0..width).map(|_| meta.advice_column()).collect();
+        let rc: Vec<Column<Fixed>> = (0..width).map(|_| meta.fixed_column()).collect();
+        let constant = meta.fixed_column();
+        let s_ark = meta.selector();
+        let s_mds = meta.selector();
+        let s_absorb = meta.selector();
+
+        for column in state.iter() {
+            meta.enable_equality(*column);
+        }
+        meta.enable_constant(constant);
+
+        meta.create_gate("poseidon ark", |meta| {
+            let s = meta.query_selector(s_ark);
+            (0..width)
+                .map(|i| {
+                    let cur = meta.query_advice(state[i], Rotation::cur());
+                    let next = meta.query_advice(state[i], Rotation::next());
+                    let rc = meta.query_fixed(rc[i], Rotation::cur());
+                    s.clone() * (cur + rc - next)
+                })
+                .collect::<Vec<_>>()
+        });
+
+        let mds = params.mds.clone();
+        meta.create_gate("poseidon mds", |meta| {
+            let s = meta.query_selector(s_mds);
+            let cur: Vec<Expression<F>> = state
+                .iter()
+                .map(|column| meta.query_advice(*column, Rotation::cur()))
+                .collect();
+            mds.iter()
+                .enumerate()
+                .map(|(i, row)| {
+                    let next = meta.query_advice(state[i], Rotation::next());
+                    let mixed = row
+                        .iter()
+                        .zip(cur.iter())
+                        .fold(Expression::Constant(F::zero()), |acc, (m, s)| {
+                            acc + Expression::Constant(*m) * s.clone()
+                        });
+                    s.clone() * (mixed - next)
+                })
+                .collect::<Vec<_>>()
+        });
+
+        meta.create_gate("poseidon absorb", |meta| {
+            let s = meta.query_selector(s_absorb);
+            (0..width)
+                .map(|i| {
+                    let cur = meta.query_advice(state[i], Rotation::cur());
+                    let input = meta.query_advice(state[i], Rotation::next());
+                    let out = meta.query_advice(state[i], Rotation(2));
+                    s.clone() * (cur + input - out)
+                })
+                .collect::<Vec<_>>()
+        });
+
+        PoseidonConfig {
+            state,
+            rc,
+            constant,
+            s_ark,
+            s_mds,
+            s_absorb,
+            sbox: S::configure(meta),
+            params,
+        }
+    }
+
+    pub fn load_private(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Option<F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        layouter.assign_region(
+            || "load private",
+            |mut region| {
+                region.assign_advice(
+                    || "private input",
+                    self.config.state[0],
+                    0,
+                    || value.ok_or(Error::Synthesis),
+                )
+            },
+        )
+    }
+
+    pub fn load_constant(
+        &self,
+        mut layouter: impl Layouter<F>,
+        constant: F,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        layouter.assign_region(
+            || "load constant",
+            |mut region| {
+                region.assign_advice_from_constant(|| "constant", self.config.state[0], 0, constant)
+            },
+        )
+    }
+
+    fn ark(
+        &self,
+        mut layouter: impl Layouter<F>,
+        state: &[AssignedCell<F, F>],
+        round: usize,
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        let constants = &self.config.params.round_constants[round];
+        layouter.assign_region(
+            || "ark",
+            |mut region| {
+                self.config.s_ark.enable(&mut region, 0)?;
+                state
+                    .iter()
+                    .enumerate()
+                    .map(|(i, cell)| {
+                        cell.copy_advice(|| "copy state", &mut region, self.config.state[i], 0)?;
+                        region.assign_fixed(
+                            || "round constant",
+                            self.config.rc[i],
+                            0,
+                            || Ok(constants[i]),
+                        )?;
+                        let next = cell.value().map(|v| *v + constants[i]);
+                        region.assign_advice(
+                            || "state + rc",
+                            self.config.state[i],
+                            1,
+                            || next.ok_or(Error::Synthesis),
+                        )
+                    })
+                    .collect()
+            },
+        )
+    }
+
+    fn mds(
+        &self,
+        mut layouter: impl Layouter<F>,
+        state: &[AssignedCell<F, F>],
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        layouter.assign_region(
+            || "mds",
+            |mut region| {
+                self.config.s_mds.enable(&mut region, 0)?;
+                for (i, cell) in state.iter().enumerate() {
+                    cell.copy_advice(|| "copy state", &mut region, self.config.state[i], 0)?;
+                }
+                let values: Option<Vec<F>> =
+                    state.iter().map(|cell| cell.value().cloned()).collect();
+                self.config
+                    .params
+                    .mds
+                    .iter()
+                    .enumerate()
+                    .map(|(i, row)| {
+                        let next = values.as_ref().map(|values| {
+                            row.iter()
+                                .zip(values.iter())
+                                .fold(F::zero(), |acc, (m, v)| acc + *m * v)
+                        });
+                        region.assign_advice(
+                            || "mds",
+                            self.config.state[i],
+                            1,
+                            || next.ok_or(Error::Synthesis),
+                        )
+                    })
+                    .collect()
+            },
+        )
+    }
+
+    pub fn permute(
+        &self,
+        mut layouter: impl Layouter<F>,
+        state: &[AssignedCell<F, F>],
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        assert_eq!(state.len(), self.config.params.width);
+        let mut state = state.to_vec();
+        for round in 0..self.config.params.rounds() {
+            let mut layouter = layouter.namespace(|| format!("round {}", round));
+            state = self.ark(layouter.namespace(|| "ark"), &state, round)?;
+            if self.config.params.is_full_round(round) {
+                state = self
+                    .poseidon
+                    .sbox
+                    .assign_constraints_for_set(layouter.namespace(|| "full sbox"), &state)?;
+            } else {
+                state[0] = self
+                    .poseidon
+                    .sbox
+                    .assign_constraints(layouter.namespace(|| "partial sbox"), &state[0])?;
+            }
+            state = self.mds(layouter.namespace(|| "mds"), &state)?;
+        }
+        Ok(state)
+    }
+
+    // state[i] + inputs[i-1], 没有输入的位置加0
+    fn absorb(
+        &self,
+        mut layouter: impl Layouter<F>,
+        state: &[AssignedCell<F, F>],
+        inputs: &[AssignedCell<F, F>],
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        layouter.assign_region(
+            || "absorb",
+            |mut region| {
+                self.config.s_absorb.enable(&mut region, 0)?;
+                state
+                    .iter()
+                    .enumerate()
+                    .map(|(i, cell)| {
+                        let column = self.config.state[i];
+                        cell.copy_advice(|| "copy state", &mut region, column, 0)?;
+                        let input = match i.checked_sub(1).and_then(|j| inputs.get(j)) {
+                            Some(input) => {
+                                input.copy_advice(|| "copy input", &mut region, column, 1)?
+                            }
+                            None => region.assign_advice_from_constant(
+                                || "zero",
+                                column,
+                                1,
+                                F::zero(),
+                            )?,
+                        };
+                        let out = cell.value().and_then(|s| input.value().map(|v| *s + *v));
+                        region.assign_advice(|| "absorb", column, 2, || out.ok_or(Error::Synthesis))
+                    })
+                    .collect()
+            },
+        )
+    }
+
+    pub fn hash(
+        &self,
+        mut layouter: impl Layouter<F>,
+        inputs: &[AssignedCell<F, F>],
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let width = self.config.params.width;
+        let mut state = (0..width)
+            .map(|i| {
+                let init = if i == 0 {
+                    F::from_u128((inputs.len() as u128) << 64)
+                } else {
+                    F::zero()
+                };
+                self.load_constant(layouter.namespace(|| format!("init state {}", i)), init)
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        let mut chunks: Vec<&[AssignedCell<F, F>]> = inputs.chunks(width - 1).collect();
+        if chunks.is_empty() {
+            chunks.push(&[]);
+        }
+        for (i, chunk) in chunks.into_iter().enumerate() {
+            let mut layouter = layouter.namespace(|| format!("chunk {}", i));
+            state = self.absorb(layouter.namespace(|| "absorb"), &state, chunk)?;
+            state = self.permute(layouter.namespace(|| "permute"), &state)?;
+        }
+        Ok(state[1].clone())
+    }
+}
+
+impl<F: FieldExt, S: CsSBox<F>, const T: usize> Hasher<F> for PoseidonChip<F, S, T> {
+    fn hash(&self, inputs: &[F]) -> F {
+        self.poseidon.hash(inputs)
+    }
+}
+
+impl<F: FieldExt, S: CsSBox<F>, const T: usize> CsHasher<F> for PoseidonChip<F, S, T> {
+    type Config = PoseidonConfig<F, S::Config>;
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        PoseidonChip::<F, S, T>::configure(meta)
+    }
+
+    fn construct(config: Self::Config) -> Self {
+        PoseidonChip::<F, S, T>::construct(config)
+    }
+
+    fn load_private(
+        &self,
+        cs: impl Layouter<F>,
+        value: Option<F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        PoseidonChip::<F, S, T>::load_private(self, cs, value)
+    }
+
+    fn assign_hash(
+        &self,
+        cs: impl Layouter<F>,
+        inputs: &[AssignedCell<F, F>],
+    ) -> Result<AssignedCell<F, F>, Error> {
+        PoseidonChip::<F, S, T>::hash(self, cs, inputs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mydemo::mimc::sc_box::{CsSBox, QuinticSBoxChip};
+    use crate::mydemo::poseidon::permutation::{Poseidon, PoseidonChip, PoseidonConfig};
+    use crate::zk::wrapper::from_hex;
+    use halo2_proofs::arithmetic::FieldExt;
+    use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner};
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::pairing::bn256::Fr;
+    use halo2_proofs::plonk::{Circuit, Column, ConstraintSystem, Error, Instance};
+
+    type Sbox<F> = QuinticSBoxChip<F>;
+
+    #[derive(Clone, Debug)]
+    pub struct TestConfig<F: FieldExt> {
+        poseidon: PoseidonConfig<F, <Sbox<F> as CsSBox<F>>::Config>,
+        instance: Column<Instance>,
+    }
+
+    // PERMUTE=true: 对 inputs (长度为WIDTH) 做置换, 暴露所有输出
+    // PERMUTE=false: 对 inputs 做hash, 暴露hash
+    pub struct PoseidonCircuit<F: FieldExt, const WIDTH: usize, const PERMUTE: bool> {
+        inputs: Vec<Option<F>>,
+    }
+
+    impl<F: FieldExt, const WIDTH: usize, const PERMUTE: bool> Circuit<F>
+        for PoseidonCircuit<F, WIDTH, PERMUTE>
+    {
+        type Config = TestConfig<F>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                inputs: vec![None; self.inputs.len()],
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            TestConfig {
+                poseidon: PoseidonChip::<F, Sbox<F>, WIDTH>::configure(meta),
+                instance,
+            }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let chip = PoseidonChip::<F, Sbox<F>, WIDTH>::construct(config.poseidon);
+            let inputs = self
+                .inputs
+                .iter()
+                .map(|v| chip.load_private(layouter.namespace(|| "input"), *v))
+                .collect::<Result<Vec<_>, Error>>()?;
+            if PERMUTE {
+                let outputs = chip.permute(layouter.namespace(|| "permute"), &inputs)?;
+                for (i, out) in outputs.iter().enumerate() {
+                    layouter.constrain_instance(out.cell(), config.instance, i)?;
+                }
+                Ok(())
+            } else {
+                let out = chip.hash(layouter.namespace(|| "hash"), &inputs)?;
+                layouter.constrain_instance(out.cell(), config.instance, 0)
+            }
+        }
+    }
+
+    fn inputs(n: u64) -> Vec<Fr> {
+        (0..n).map(Fr::from).collect()
+    }
+
+    #[test]
+    pub fn test_native_permutation_known_answer() {
+        // 参考实现的测试向量 poseidonperm_x5_254_3 / poseidonperm_x5_254_5
+        let mut state = inputs(3);
+        Poseidon::bn256(3).permute(&mut state);
+        assert_eq!(
+            state,
+            vec![
+                from_hex::<Fr>("115cc0f5e7d690413df64c6b9662e9cf2a3617f2743245519e19607a4417189a"),
+                from_hex::<Fr>("0fca49b798923ab0239de1c9e7a4a9a2210312b6a2f616d18b5a87f9b628ae29"),
+                from_hex::<Fr>("0e7ae82e40091e63cbd4f16a6d16310b3729d4b6e138fcf54110e2867045a30c"),
+            ]
+        );
+
+        let mut state = inputs(5);
+        Poseidon::bn256(5).permute(&mut state);
+        assert_eq!(
+            state,
+            vec![
+                from_hex::<Fr>("299c867db6c1fdd79dcefa40e4510b9837e60ebb1ce0663dbaa525df65250465"),
+                from_hex::<Fr>("1148aaef609aa338b27dafd89bb98862d8bb2b429aceac47d86206154ffe053d"),
+                from_hex::<Fr>("24febb87fed7462e23f6665ff9a0111f4044c38ee1672c1ac6b0637d34f24907"),
+                from_hex::<Fr>("0eb08f6d809668a981c186beaf6110060707059576406b248e5d9cf6e78b3d3e"),
+                from_hex::<Fr>("07748bc6877c9b82c8b98666ee9d0626ec7f5be4205f79ee8528ef1c4a376fc7"),
+            ]
+        );
+    }
+
+    #[test]
+    pub fn test_native_hash_known_answer() {
+        let poseidon = Poseidon::bn256(3);
+        assert_eq!(
+            poseidon.hash(&[Fr::from(1u64), Fr::from(2u64)]),
+            from_hex::<Fr>("10187423b8cb737fdb60514f71a0c7014b5d184d139109db781dd15e1e6f63cc")
+        );
+        assert_ne!(
+            poseidon.hash(&[Fr::from(1u64)]),
+            poseidon.hash(&[Fr::from(1u64), Fr::zero()])
+        );
+    }
+
+    #[test]
+    pub fn test_permutation_chip() {
+        let input = inputs(3);
+        let mut expected = input.clone();
+        Poseidon::bn256(3).permute(&mut expected);
+
+        let circuit = PoseidonCircuit::<Fr, 3, true> {
+            inputs: input.iter().map(|v| Some(*v)).collect(),
+        };
+        let prover = MockProver::run(10, &circuit, vec![expected.clone()]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        expected.swap(0, 1);
+        let prover = MockProver::run(10, &circuit, vec![expected]).unwrap();
+        assert_ne!(prover.verify(), Ok(()));
+    }
+
+    #[test]
+    pub fn test_hash_chip() {
+        let poseidon = Poseidon::bn256(3);
+        for n in [0, 2, 5] {
+            let input = inputs(n);
+            let expected = poseidon.hash(&input);
+            let circuit = PoseidonCircuit::<Fr, 3, false> {
+                inputs: input.iter().map(|v| Some(*v)).collect(),
+            };
+            let prover = MockProver::run(11, &circuit, vec![vec![expected]]).unwrap();
+            assert_eq!(prover.verify(), Ok(()));
+
+            let prover = MockProver::run(11, &circuit, vec![vec![expected + Fr::one()]]).unwrap();
+            assert_ne!(prover.verify(), Ok(()));
+        }
+    }
+}
@@ -84,6 +84,7 @@ impl<F: FieldExt, const RANGE: u64> Circuit<F> for MyCircuit<F, RANGE> {
 #[cfg(test)]
 mod tests {
     use crate::mydemo::range_check::MyCircuit;
+    use crate::range_check::harness::RangeCheckHarness;
     use halo2_proofs::dev::MockProver;
     use halo2_proofs::pairing::bn256::Fr;
 
@@ -105,4 +106,12 @@ mod tests {
         let prover = MockProver::run(k, &circuit, public_inputs).unwrap();
         assert_ne!(prover.verify(), Ok(()))
     }
+
+    #[test]
+    pub fn test_boundaries() {
+        let harness = RangeCheckHarness::new(5, 6, "value in range [a,b]", |value| {
+            MyCircuit::<Fr, 6> { value: Some(value) }
+        });
+        harness.check_all();
+    }
 }
@@ -0,0 +1,95 @@
+use crate::mydemo::mimc::constants::round_constants;
+use halo2_proofs::arithmetic::FieldExt;
+
+// Rescue-Prime 的参数
+// 轮数按照论文里的公式计算, 见 number_of_rounds
+// 轮常量用 blake2b 生成, 和 MiMC 一样; MDS 用 Cauchy 矩阵 M[i][j] = 1 / (x_i + y_j), x_i = i, y_j = t + j
+// 每一轮有两次 MDS + 加常量, 所以一共 2 * rounds 组常量
+#[derive(Clone, Debug)]
+pub struct RescueParams<F: FieldExt> {
+    pub width: usize,
+    pub capacity: usize,
+    pub rounds: usize,
+    // round_constants[2r] 在 x^alpha 之后, round_constants[2r+1] 在 x^(1/alpha) 之后
+    pub round_constants: Vec<Vec<F>>,
+    pub mds: Vec<Vec<F>>,
+}
+
+impl<F: FieldExt> RescueParams<F> {
+    pub fn new(width: usize, capacity: usize, alpha: u64, security_level: usize) -> Self {
+        assert!(capacity > 0 && capacity < width);
+        let rounds = number_of_rounds(width, capacity, alpha, security_level);
+
+        // round_constants 生成的第一个常量固定为0, 跳过
+        let constants = round_constants::<F>(b"rescue-prime", 2 * rounds * width + 1);
+        let round_constants = constants[1..]
+            .chunks(width)
+            .map(|chunk| chunk.to_vec())
+            .collect();
+
+        let mds = (0..width)
+            .map(|i| {
+                (0..width)
+                    .map(|j| F::from((i + width + j) as u64).invert().unwrap())
+                    .collect()
+            })
+            .collect();
+
+        Self {
+            width,
+            capacity,
+            rounds,
+            round_constants,
+            mds,
+        }
+    }
+
+    // bn256 的 Fr 上, alpha = 5, capacity = 1, 128bit 安全性
+    pub fn bn256(width: usize) -> Self {
+        Self::new(width, 1, 5, 128)
+    }
+
+    pub fn rate(&self) -> usize {
+        self.width - self.capacity
+    }
+}
+
+// 抵抗 Gröbner basis 攻击需要的最少轮数 l1:
+// 最小的 l1 使得 C(v + d, v)^2 > 2^security_level, 其中
+// d = floor((alpha - 1) * m * (l1 - 1) / 2) + 2, v = m * (l1 - 1) + rate
+// 最后再加50%的余量: ceil(1.5 * max(5, l1))
+// 比如 alpha = 5, capacity = 1 时, t = 3 为14轮, t = 5 为9轮
+pub fn number_of_rounds(width: usize, capacity: usize, alpha: u64, security_level: usize) -> usize {
+    let rate = width - capacity;
+    let l1 = (1..)
+        .find(|l1| {
+            let d = (alpha as usize - 1) * width * (l1 - 1) / 2 + 2;
+            let v = width * (l1 - 1) + rate;
+            2.0 * log2_binomial(v + d, v) > security_level as f64
+        })
+        .unwrap();
+    (3 * l1.max(5)).div_ceil(2)
+}
+
+fn log2_binomial(n: usize, k: usize) -> f64 {
+    (1..=k)
+        .map(|i| ((n - k + i) as f64 / i as f64).log2())
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mydemo::rescue::params::{number_of_rounds, RescueParams};
+    use halo2_proofs::pairing::bn256::Fr;
+
+    #[test]
+    pub fn test_rounds() {
+        let rounds: Vec<usize> = (2..=8).map(|t| number_of_rounds(t, 1, 5, 128)).collect();
+        assert_eq!(rounds, vec![20, 14, 11, 9, 8, 8, 8]);
+
+        let params = RescueParams::<Fr>::bn256(3);
+        assert_eq!(params.rounds, 14);
+        assert_eq!(params.round_constants.len(), 28);
+        assert!(params.round_constants.iter().all(|c| c.len() == 3));
+    }
+}
@@ -0,0 +1,459 @@
+use crate::mydemo::mimc::sc_box::{CsHasher, CsSBox, Hasher, InversePowerSBox, PowerSBox, SBox};
+use crate::mydemo::rescue::params::RescueParams;
+use halo2_proofs::arithmetic::FieldExt;
+use halo2_proofs::circuit::{AssignedCell, Layouter};
+use halo2_proofs::plonk::{Advice, Column, ConstraintSystem, Error, Expression, Fixed, Selector};
+use halo2_proofs::poly::Rotation;
+
+// Rescue-Prime 置换, 每一轮:
+// 1. state = S(state), S 是 x^alpha
+// 2. state = M * state + c_{2r}
+// 3. state = S^-1(state), S^-1 是 x^(1/alpha)
+// 4. state = M * state + c_{2r+1}
+// S 和 S^-1 都是类型参数, 在电路里 S^-1 由prover给出再用 x^alpha 检查
+pub struct Rescue<F: FieldExt, S: SBox<F>, I: SBox<F>> {
+    params: RescueParams<F>,
+    sbox: S,
+    inv_sbox: I,
+}
+
+impl<F: FieldExt> Rescue<F, PowerSBox<F, 5>, InversePowerSBox<F, 5>> {
+    pub fn bn256(width: usize) -> Self {
+        Self::new(
+            RescueParams::bn256(width),
+            PowerSBox::default(),
+            InversePowerSBox::default(),
+        )
+    }
+}
+
+impl<F: FieldExt, S: SBox<F>, I: SBox<F>> Rescue<F, S, I> {
+    pub fn new(params: RescueParams<F>, sbox: S, inv_sbox: I) -> Self {
+        Self {
+            params,
+            sbox,
+            inv_sbox,
+        }
+    }
+
+    pub fn params(&self) -> &RescueParams<F> {
+        &self.params
+    }
+
+    // state = M * state + constants
+    fn linear(&self, state: &mut [F], constants: &[F]) {
+        let mixed: Vec<F> = self
+            .params
+            .mds
+            .iter()
+            .zip(constants.iter())
+            .map(|(row, c)| {
+                row.iter()
+                    .zip(state.iter())
+                    .fold(*c, |acc, (m, s)| acc + *m * s)
+            })
+            .collect();
+        state.copy_from_slice(&mixed);
+    }
+
+    pub fn permute(&self, state: &mut [F]) {
+        assert_eq!(state.len(), self.params.width);
+        for round in 0..self.params.rounds {
+            self.sbox.apply(state);
+            self.linear(state, &self.params.round_constants[2 * round]);
+            self.inv_sbox.apply(state);
+            self.linear(state, &self.params.round_constants[2 * round + 1]);
+        }
+    }
+
+    // sponge, rate 在 state[0..rate], capacity 在 state[rate..]
+    // capacity 的第一个元素初始化为输入的个数, 和 MiMCSponge 一样
+    // 输出 state[0]
+    pub fn hash(&self, inputs: &[F]) -> F {
+        let rate = self.params.rate();
+        let mut state = vec![F::zero(); self.params.width];
+        state[rate] = F::from(inputs.len() as u64);
+
+        let mut chunks: Vec<&[F]> = inputs.chunks(rate).collect();
+        if chunks.is_empty() {
+            chunks.push(&[]);
+        }
+        for chunk in chunks {
+            for (s, input) in state.iter_mut().zip(chunk.iter()) {
+                *s += input;
+            }
+            self.permute(&mut state);
+        }
+        state[0]
+    }
+}
+
+impl<F: FieldExt, S: SBox<F>, I: SBox<F>> Hasher<F> for Rescue<F, S, I> {
+    fn hash(&self, inputs: &[F]) -> F {
+        Rescue::hash(self, inputs)
+    }
+}
+
+// state[0..t] | rc[0..t](fixed) | s_linear | s_absorb
+// linear: state_next[i] = sum_j M[i][j] * state_cur[j] + rc[i]
+// absorb: state[i](rot 2) = state_cur[i] + state_next[i]
+#[derive(Clone, Debug)]
+pub struct RescueConfig<F: FieldExt, C, D> {
+    pub state: Vec<Column<Advice>>,
+    pub rc: Vec<Column<Fixed>>,
+    pub constant: Column<Fixed>,
+    pub s_linear: Selector,
+    pub s_absorb: Selector,
+    pub sbox: C,
+    pub inv_sbox: D,
+    pub params: RescueParams<F>,
+}
+
+// 宽度为 T 的 Rescue-Prime, S 是 x^alpha 的chip, I 是 x^(1/alpha) 的chip
+pub struct RescueChip<F: FieldExt, S: CsSBox<F>, I: CsSBox<F>, const T: usize> {
+    config: RescueConfig<F, S::Config, I::Config>,
+    rescue: Rescue<F, S, I>,
+}
+
+impl<F: FieldExt, S: CsSBox<F>, I: CsSBox<F>, const T: usize> RescueChip<F, S, I, T> {
+    pub fn construct(config: RescueConfig<F, S::Config, I::Config>) -> Self {
+        let sbox = S::construct(config.sbox.clone());
+        let inv_sbox = I::construct(config.inv_sbox.clone());
+        Self {
+            rescue: Rescue::new(config.params.clone(), sbox, inv_sbox),
+            config,
+        }
+    }
+
+    // bn256 上的参数
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> RescueConfig<F, S::Config, I::Config> {
+        Self::configure_with_params(meta, RescueParams::bn256(T))
+    }
+
+    pub fn configure_with_params(
+        meta: &mut ConstraintSystem<F>,
+        params: RescueParams<F>,
+    ) -> RescueConfig<F, S::Config, I::Config> {
+        assert_eq!(params.width, T);
+        let state: Vec<Column<Advice>> = (0..T).map(|_| meta.advice_column()).collect();
+        let rc: Vec<Column<Fixed>> = (0..T).map(|_| meta.fixed_column()).collect();
+        let constant = meta.fixed_column();
+        let s_linear = meta.selector();
+        let s_absorb = meta.selector();
+
+        for column in state.iter() {
+            meta.enable_equality(*column);
+        }
+        meta.enable_constant(constant);
+
+        let mds = params.mds.clone();
+        meta.create_gate("rescue linear", |meta| {
+            let s = meta.query_selector(s_linear);
+            let cur: Vec<Expression<F>> = state
+                .iter()
+                .map(|column| meta.query_advice(*column, Rotation::cur()))
+                .collect();
+            mds.iter()
+                .enumerate()
+                .map(|(i, row)| {
+                    let next = meta.query_advice(state[i], Rotation::next());
+                    let rc = meta.query_fixed(rc[i], Rotation::cur());
+                    let mixed = row
+                        .iter()
+                        .zip(cur.iter())
+                        .fold(rc, |acc, (m, s)| acc + Expression::Constant(*m) * s.clone());
+                    s.clone() * (mixed - next)
+                })
+                .collect::<Vec<_>>()
+        });
+
+        meta.create_gate("rescue absorb", |meta| {
+            let s = meta.query_selector(s_absorb);
+            (0..T)
+                .map(|i| {
+                    let cur = meta.query_advice(state[i], Rotation::cur());
+                    let input = meta.query_advice(state[i], Rotation::next());
+                    let out = meta.query_advice(state[i], Rotation(2));
+                    s.clone() * (cur + input - out)
+                })
+                .collect::<Vec<_>>()
+        });
+
+        RescueConfig {
+            state,
+            rc,
+            constant,
+            s_linear,
+            s_absorb,
+            sbox: S::configure(meta),
+            inv_sbox: I::configure(meta),
+            params,
+        }
+    }
+
+    pub fn load_private(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Option<F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        layouter.assign_region(
+            || "load private",
+            |mut region| {
+                region.assign_advice(
+                    || "private input",
+                    self.config.state[0],
+                    0,
+                    || value.ok_or(Error::Synthesis),
+                )
+            },
+        )
+    }
+
+    pub fn load_constant(
+        &self,
+        mut layouter: impl Layouter<F>,
+        constant: F,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        layouter.assign_region(
+            || "load constant",
+            |mut region| {
+                region.assign_advice_from_constant(|| "constant", self.config.state[0], 0, constant)
+            },
+        )
+    }
+
+    fn linear(
+        &self,
+        mut layouter: impl Layouter<F>,
+        state: &[AssignedCell<F, F>],
+        constants: &[F],
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        layouter.assign_region(
+            || "linear",
+            |mut region| {
+                self.config.s_linear.enable(&mut region, 0)?;
+                for (i, cell) in state.iter().enumerate() {
+                    cell.copy_advice(|| "copy state", &mut region, self.config.state[i], 0)?;
+                    region.assign_fixed(
+                        || "round constant",
+                        self.config.rc[i],
+                        0,
+                        || Ok(constants[i]),
+                    )?;
+                }
+                let next: Option<Vec<F>> = state
+                    .iter()
+                    .map(|cell| cell.value().cloned())
+                    .collect::<Option<Vec<F>>>()
+                    .map(|mut values| {
+                        self.rescue.linear(&mut values, constants);
+                        values
+                    });
+                (0..T)
+                    .map(|i| {
+                        let value = next.as_ref().map(|next| next[i]);
+                        region.assign_advice(
+                            || "linear",
+                            self.config.state[i],
+                            1,
+                            || value.ok_or(Error::Synthesis),
+                        )
+                    })
+                    .collect()
+            },
+        )
+    }
+
+    pub fn permute(
+        &self,
+        mut layouter: impl Layouter<F>,
+        state: &[AssignedCell<F, F>],
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        assert_eq!(state.len(), T);
+        let constants = &self.config.params.round_constants;
+        let mut state = state.to_vec();
+        for round in 0..self.config.params.rounds {
+            let mut layouter = layouter.namespace(|| format!("round {}", round));
+            state = self
+                .rescue
+                .sbox
+                .assign_constraints_for_set(layouter.namespace(|| "sbox"), &state)?;
+            state = self.linear(
+                layouter.namespace(|| "linear"),
+                &state,
+                &constants[2 * round],
+            )?;
+            state = self
+                .rescue
+                .inv_sbox
+                .assign_constraints_for_set(layouter.namespace(|| "inverse sbox"), &state)?;
+            state = self.linear(
+                layouter.namespace(|| "linear"),
+                &state,
+                &constants[2 * round + 1],
+            )?;
+        }
+        Ok(state)
+    }
+
+    // state[i] + inputs[i], 没有输入的位置加0
+    fn absorb(
+        &self,
+        mut layouter: impl Layouter<F>,
+        state: &[AssignedCell<F, F>],
+        inputs: &[AssignedCell<F, F>],
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        layouter.assign_region(
+            || "absorb",
+            |mut region| {
+                self.config.s_absorb.enable(&mut region, 0)?;
+                state
+                    .iter()
+                    .enumerate()
+                    .map(|(i, cell)| {
+                        let column = self.config.state[i];
+                        cell.copy_advice(|| "copy state", &mut region, column, 0)?;
+                        let input = match inputs.get(i) {
+                            Some(input) => {
+                                input.copy_advice(|| "copy input", &mut region, column, 1)?
+                            }
+                            None => region.assign_advice_from_constant(
+                                || "zero",
+                                column,
+                                1,
+                                F::zero(),
+                            )?,
+                        };
+                        let out = cell.value().and_then(|s| input.value().map(|v| *s + *v));
+                        region.assign_advice(|| "absorb", column, 2, || out.ok_or(Error::Synthesis))
+                    })
+                    .collect()
+            },
+        )
+    }
+
+    pub fn hash(
+        &self,
+        mut layouter: impl Layouter<F>,
+        inputs: &[AssignedCell<F, F>],
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let rate = self.config.params.rate();
+        let mut state = (0..T)
+            .map(|i| {
+                let init = if i == rate {
+                    F::from(inputs.len() as u64)
+                } else {
+                    F::zero()
+                };
+                self.load_constant(layouter.namespace(|| format!("init state {}", i)), init)
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        let mut chunks: Vec<&[AssignedCell<F, F>]> = inputs.chunks(rate).collect();
+        if chunks.is_empty() {
+            chunks.push(&[]);
+        }
+        for (i, chunk) in chunks.into_iter().enumerate() {
+            let mut layouter = layouter.namespace(|| format!("chunk {}", i));
+            state = self.absorb(layouter.namespace(|| "absorb"), &state, chunk)?;
+            state = self.permute(layouter.namespace(|| "permute"), &state)?;
+        }
+        Ok(state[0].clone())
+    }
+}
+
+impl<F: FieldExt, S: CsSBox<F>, I: CsSBox<F>, const T: usize> Hasher<F> for RescueChip<F, S, I, T> {
+    fn hash(&self, inputs: &[F]) -> F {
+        self.rescue.hash(inputs)
+    }
+}
+
+impl<F: FieldExt, S: CsSBox<F>, I: CsSBox<F>, const T: usize> CsHasher<F>
+    for RescueChip<F, S, I, T>
+{
+    type Config = RescueConfig<F, S::Config, I::Config>;
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        RescueChip::<F, S, I, T>::configure(meta)
+    }
+
+    fn construct(config: Self::Config) -> Self {
+        RescueChip::<F, S, I, T>::construct(config)
+    }
+
+    fn load_private(
+        &self,
+        cs: impl Layouter<F>,
+        value: Option<F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        RescueChip::<F, S, I, T>::load_private(self, cs, value)
+    }
+
+    fn assign_hash(
+        &self,
+        cs: impl Layouter<F>,
+        inputs: &[AssignedCell<F, F>],
+    ) -> Result<AssignedCell<F, F>, Error> {
+        RescueChip::<F, S, I, T>::hash(self, cs, inputs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mydemo::differential::HashCircuit;
+    use crate::mydemo::mimc::sc_box::{
+        CsHasher, Hasher, InversePowerSBox, PowerSBox, QuinticInverseSBoxChip, QuinticSBoxChip,
+        SBox,
+    };
+    use crate::mydemo::mimc::sponge::{MiMCSponge, MiMCSpongeChip};
+    use crate::mydemo::poseidon::permutation::{Poseidon, PoseidonChip};
+    use crate::mydemo::rescue::permutation::{Rescue, RescueChip};
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::pairing::bn256::Fr;
+
+    type RescueChip3<F> = RescueChip<F, QuinticSBoxChip<F>, QuinticInverseSBoxChip<F>, 3>;
+
+    fn inputs(n: u64) -> Vec<Fr> {
+        (1..=n).map(Fr::from).collect()
+    }
+
+    fn run<H: CsHasher<Fr>>(k: u32, native: &impl Hasher<Fr>, inputs: &[Fr]) {
+        let expected = native.hash(inputs);
+        let circuit = HashCircuit::<Fr, H>::new(inputs);
+        let prover = MockProver::run(k, &circuit, vec![vec![expected]]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        let prover = MockProver::run(k, &circuit, vec![vec![expected + Fr::one()]]).unwrap();
+        assert_ne!(prover.verify(), Ok(()));
+    }
+
+    #[test]
+    pub fn test_inverse_rounds() {
+        // x^alpha 和 x^(1/alpha) 互逆
+        let mut state = inputs(3);
+        PowerSBox::<Fr, 5>::default().apply(&mut state);
+        InversePowerSBox::<Fr, 5>::default().apply(&mut state);
+        assert_eq!(state, inputs(3));
+
+        let rescue = Rescue::bn256(3);
+        let mut state = inputs(3);
+        rescue.permute(&mut state);
+        assert_ne!(state, inputs(3));
+        assert_ne!(rescue.hash(&[]), rescue.hash(&[Fr::zero()]));
+    }
+
+    #[test]
+    pub fn test_hash_chip() {
+        let rescue = Rescue::bn256(3);
+        for n in [0, 1, 2, 5] {
+            run::<RescueChip3<Fr>>(10, &rescue, &inputs(n));
+        }
+    }
+
+    #[test]
+    pub fn test_hash_is_type_parameter() {
+        let inputs = inputs(3);
+        run::<PoseidonChip<Fr, QuinticSBoxChip<Fr>, 3>>(11, &Poseidon::bn256(3), &inputs);
+        run::<MiMCSpongeChip<Fr, 7, 2, 1>>(13, &MiMCSponge::<Fr, 7, 2, 1>::new(), &inputs);
+    }
+}
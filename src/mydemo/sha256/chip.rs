@@ -0,0 +1,276 @@
+use crate::mydemo::sha256::native::{padding, BLOCK_BYTES, IV, K};
+use crate::mydemo::word::{AssignedWord, CsByteHasher, U32Chip, WordConfig};
+use halo2_proofs::arithmetic::FieldExt;
+use halo2_proofs::circuit::{AssignedCell, Layouter};
+use halo2_proofs::plonk::{ConstraintSystem, Error};
+
+// SHA-256, 所有的32位运算都由 U32Chip 用byte查表完成
+// 消息长度在电路里是固定的, 所以padding直接用常量
+// 每一轮:
+// T1 = h + Σ1(e) + (e & f) + (!e & g) + K + W
+// a' = T1 + Σ0(a) + (a & b) + (c & (a ^ b)), e' = d + T1
+// Ch 和 Maj 的两部分没有共同的bit, 所以可以用加法代替异或
+pub struct Sha256Chip<F: FieldExt> {
+    word: U32Chip<F>,
+}
+
+impl<F: FieldExt> Sha256Chip<F> {
+    pub fn construct(config: WordConfig<F>) -> Self {
+        Self {
+            word: U32Chip::construct(config),
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> WordConfig<F> {
+        U32Chip::configure(meta)
+    }
+
+    pub fn load_tables(&self, layouter: impl Layouter<F>) -> Result<(), Error> {
+        self.word.load_tables(layouter)
+    }
+
+    // rotr(x, r0) ^ rotr(x, r1) ^ rotr(x, r2), 第三个是 shr 时 shift = true
+    fn sigma(
+        &self,
+        mut layouter: impl Layouter<F>,
+        x: &AssignedWord<F>,
+        r: [usize; 3],
+        shift: bool,
+    ) -> Result<AssignedWord<F>, Error> {
+        let x0 = self.word.rotr(layouter.namespace(|| "rotr 0"), x, r[0])?;
+        let x1 = self.word.rotr(layouter.namespace(|| "rotr 1"), x, r[1])?;
+        let x2 = if shift {
+            self.word.shr(layouter.namespace(|| "shr 2"), x, r[2])?
+        } else {
+            self.word.rotr(layouter.namespace(|| "rotr 2"), x, r[2])?
+        };
+        let x01 = self.word.xor(layouter.namespace(|| "xor 0"), &x0, &x1)?;
+        self.word.xor(layouter.namespace(|| "xor 1"), &x01, &x2)
+    }
+
+    fn compress(
+        &self,
+        mut layouter: impl Layouter<F>,
+        state: &[AssignedWord<F>],
+        block: Vec<AssignedWord<F>>,
+        k: &[AssignedWord<F>],
+    ) -> Result<Vec<AssignedWord<F>>, Error> {
+        let mut w = block;
+        for t in 16..64 {
+            let mut layouter = layouter.namespace(|| format!("schedule {}", t));
+            let s0 = self.sigma(layouter.namespace(|| "σ0"), &w[t - 15], [7, 18, 3], true)?;
+            let s1 = self.sigma(layouter.namespace(|| "σ1"), &w[t - 2], [17, 19, 10], true)?;
+            let next = self.word.add(
+                layouter.namespace(|| "w"),
+                &[&w[t - 16], &s0, &w[t - 7], &s1],
+            )?;
+            w.push(next);
+        }
+
+        let mut v = state.to_vec();
+        for t in 0..64 {
+            let mut layouter = layouter.namespace(|| format!("round {}", t));
+            let (a, b, c, d) = (&v[0], &v[1], &v[2], &v[3]);
+            let (e, f, g, h) = (&v[4], &v[5], &v[6], &v[7]);
+
+            let s1 = self.sigma(layouter.namespace(|| "Σ1"), e, [6, 11, 25], false)?;
+            let ef = self.word.and(layouter.namespace(|| "e & f"), e, f)?;
+            let not_e = self.word.not(layouter.namespace(|| "!e"), e)?;
+            let not_eg = self.word.and(layouter.namespace(|| "!e & g"), &not_e, g)?;
+            let t1 = self.word.add(
+                layouter.namespace(|| "T1"),
+                &[h, &s1, &ef, &not_eg, &k[t], &w[t]],
+            )?;
+
+            let s0 = self.sigma(layouter.namespace(|| "Σ0"), a, [2, 13, 22], false)?;
+            let ab = self.word.and(layouter.namespace(|| "a & b"), a, b)?;
+            let a_xor_b = self.word.xor(layouter.namespace(|| "a ^ b"), a, b)?;
+            let c_ab = self
+                .word
+                .and(layouter.namespace(|| "c & (a ^ b)"), c, &a_xor_b)?;
+            let new_a = self
+                .word
+                .add(layouter.namespace(|| "a"), &[&t1, &s0, &ab, &c_ab])?;
+            let new_e = self.word.add(layouter.namespace(|| "e"), &[d, &t1])?;
+
+            v = vec![
+                new_a,
+                v[0].clone(),
+                v[1].clone(),
+                v[2].clone(),
+                new_e,
+                v[4].clone(),
+                v[5].clone(),
+                v[6].clone(),
+            ];
+        }
+
+        state
+            .iter()
+            .zip(v.iter())
+            .map(|(s, v)| self.word.add(layouter.namespace(|| "state"), &[s, v]))
+            .collect()
+    }
+
+    // 返回8个32位的word, 按大端序拼起来就是digest
+    pub fn digest(
+        &self,
+        mut layouter: impl Layouter<F>,
+        message: &[Option<u8>],
+    ) -> Result<Vec<AssignedWord<F>>, Error> {
+        let mut bytes = self
+            .word
+            .load_bytes(layouter.namespace(|| "message"), message)?;
+        bytes.extend(
+            self.word
+                .load_constant_bytes(layouter.namespace(|| "padding"), &padding(message.len()))?,
+        );
+
+        let k = K
+            .iter()
+            .map(|k| {
+                self.word
+                    .load_constant(layouter.namespace(|| "K"), *k as u64)
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        let mut state = IV
+            .iter()
+            .map(|iv| {
+                self.word
+                    .load_constant(layouter.namespace(|| "IV"), *iv as u64)
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        for (i, block) in bytes.chunks(BLOCK_BYTES).enumerate() {
+            // 消息是大端序的, word 的 bytes 是小端序
+            let words = block
+                .chunks(4)
+                .map(|chunk| {
+                    let le: Vec<AssignedCell<F, F>> = chunk.iter().rev().cloned().collect();
+                    self.word
+                        .compose_bytes(layouter.namespace(|| "block word"), &le)
+                })
+                .collect::<Result<Vec<_>, Error>>()?;
+            state = self.compress(
+                layouter.namespace(|| format!("block {}", i)),
+                &state,
+                words,
+                &k,
+            )?;
+        }
+        Ok(state)
+    }
+}
+
+impl<F: FieldExt> CsByteHasher<F> for Sha256Chip<F> {
+    fn configure(meta: &mut ConstraintSystem<F>) -> WordConfig<F> {
+        Sha256Chip::configure(meta)
+    }
+
+    fn construct(config: WordConfig<F>) -> Self {
+        Sha256Chip::construct(config)
+    }
+
+    fn load_tables(&self, layouter: impl Layouter<F>) -> Result<(), Error> {
+        Sha256Chip::load_tables(self, layouter)
+    }
+
+    fn digest(
+        &self,
+        layouter: impl Layouter<F>,
+        message: &[Option<u8>],
+    ) -> Result<Vec<AssignedWord<F>>, Error> {
+        Sha256Chip::digest(self, layouter, message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mydemo::sha256::chip::Sha256Chip;
+    use crate::mydemo::sha256::native::sha256;
+    use crate::mydemo::word::WordConfig;
+    use halo2_proofs::arithmetic::FieldExt;
+    use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner};
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::pairing::bn256::Fr;
+    use halo2_proofs::plonk::{Circuit, Column, ConstraintSystem, Error, Instance};
+
+    #[derive(Clone, Debug)]
+    pub struct Sha256Config<F: FieldExt> {
+        word: WordConfig<F>,
+        instance: Column<Instance>,
+    }
+
+    // 长度是电路的一部分, 所以 without_witnesses 要保留长度
+    pub struct Sha256Circuit {
+        message: Vec<Option<u8>>,
+    }
+
+    impl<F: FieldExt> Circuit<F> for Sha256Circuit {
+        type Config = Sha256Config<F>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                message: vec![None; self.message.len()],
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            Sha256Config {
+                word: Sha256Chip::configure(meta),
+                instance,
+            }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let chip = Sha256Chip::construct(config.word);
+            chip.load_tables(layouter.namespace(|| "tables"))?;
+            let digest = chip.digest(layouter.namespace(|| "sha256"), &self.message)?;
+            for (i, word) in digest.iter().enumerate() {
+                layouter.constrain_instance(word.value.cell(), config.instance, i)?;
+            }
+            Ok(())
+        }
+    }
+
+    fn prove(message: &[u8], digest: [u32; 8]) -> bool {
+        let circuit = Sha256Circuit {
+            message: message.iter().map(|b| Some(*b)).collect(),
+        };
+        let instance = digest.iter().map(|w| Fr::from(*w as u64)).collect();
+        let prover = MockProver::run(17, &circuit, vec![instance]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    pub fn test_sha256() {
+        for message in [
+            "abc",
+            "abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq",
+        ] {
+            let digest = sha256(message.as_bytes());
+            assert!(prove(message.as_bytes(), digest), "{}", message);
+        }
+        assert!(prove(
+            b"",
+            [
+                0xe3b0c442, 0x98fc1c14, 0x9afbf4c8, 0x996fb924, 0x27ae41e4, 0x649b934c, 0xa495991b,
+                0x7852b855,
+            ]
+        ));
+    }
+
+    #[test]
+    pub fn test_wrong_digest() {
+        let mut digest = sha256(b"abc");
+        digest[7] ^= 1;
+        assert!(!prove(b"abc", digest));
+    }
+}
@@ -0,0 +1,2 @@
+pub(crate) mod chip;
+pub(crate) mod native;
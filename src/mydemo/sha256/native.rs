@@ -0,0 +1,126 @@
+// FIPS 180-4 SHA-256, 用来生成电路的 witness 和测试
+pub const IV: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+pub const K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+pub const BLOCK_BYTES: usize = 64;
+
+// 补一个 0x80, 再补0, 最后8个byte是大端序的比特长度, 总长是64的倍数
+pub fn padding(len: usize) -> Vec<u8> {
+    let zeros = (BLOCK_BYTES * 2 - (len + 9) % BLOCK_BYTES) % BLOCK_BYTES;
+    let mut padding = vec![0x80];
+    padding.extend(vec![0u8; zeros]);
+    padding.extend(((len as u64) * 8).to_be_bytes());
+    padding
+}
+
+// 前16个是消息, 后面的由前面的算出来
+pub fn schedule(block: &[u8]) -> [u32; 64] {
+    assert_eq!(block.len(), BLOCK_BYTES);
+    let mut w = [0u32; 64];
+    for (t, chunk) in block.chunks(4).enumerate() {
+        w[t] = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+    }
+    for t in 16..64 {
+        let s0 = w[t - 15].rotate_right(7) ^ w[t - 15].rotate_right(18) ^ (w[t - 15] >> 3);
+        let s1 = w[t - 2].rotate_right(17) ^ w[t - 2].rotate_right(19) ^ (w[t - 2] >> 10);
+        w[t] = w[t - 16]
+            .wrapping_add(s0)
+            .wrapping_add(w[t - 7])
+            .wrapping_add(s1);
+    }
+    w
+}
+
+pub fn compress(state: [u32; 8], block: &[u8]) -> [u32; 8] {
+    let w = schedule(block);
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = state;
+    for t in 0..64 {
+        let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+        let ch = (e & f) ^ (!e & g);
+        let t1 = h
+            .wrapping_add(s1)
+            .wrapping_add(ch)
+            .wrapping_add(K[t])
+            .wrapping_add(w[t]);
+        let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+        let maj = (a & b) ^ (a & c) ^ (b & c);
+        let t2 = s0.wrapping_add(maj);
+        h = g;
+        g = f;
+        f = e;
+        e = d.wrapping_add(t1);
+        d = c;
+        c = b;
+        b = a;
+        a = t1.wrapping_add(t2);
+    }
+    let mut out = state;
+    for (o, v) in out.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+        *o = o.wrapping_add(v);
+    }
+    out
+}
+
+pub fn sha256(message: &[u8]) -> [u32; 8] {
+    let mut padded = message.to_vec();
+    padded.extend(padding(message.len()));
+    padded.chunks(BLOCK_BYTES).fold(IV, compress)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mydemo::sha256::native::sha256;
+
+    // NIST FIPS 180-4 的测试向量
+    pub const VECTORS: [(&str, [u32; 8]); 3] = [
+        (
+            "",
+            [
+                0xe3b0c442, 0x98fc1c14, 0x9afbf4c8, 0x996fb924, 0x27ae41e4, 0x649b934c, 0xa495991b,
+                0x7852b855,
+            ],
+        ),
+        (
+            "abc",
+            [
+                0xba7816bf, 0x8f01cfea, 0x414140de, 0x5dae2223, 0xb00361a3, 0x96177a9c, 0xb410ff61,
+                0xf20015ad,
+            ],
+        ),
+        (
+            "abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq",
+            [
+                0x248d6a61, 0xd20638b8, 0xe5c02693, 0x0c3e6039, 0xa33ce459, 0x64ff2167, 0xf6ecedd4,
+                0x19db06c1,
+            ],
+        ),
+    ];
+
+    #[test]
+    pub fn test_nist_vectors() {
+        for (message, digest) in VECTORS {
+            assert_eq!(sha256(message.as_bytes()), digest);
+        }
+    }
+
+    #[test]
+    pub fn test_padding_boundaries() {
+        // 55 byte 刚好一个 block, 56 byte 需要两个
+        for (len, blocks) in [(0, 1), (55, 1), (56, 2), (64, 2), (119, 2), (120, 3)] {
+            let padded = len + super::padding(len).len();
+            assert_eq!(padded, blocks * 64, "len {}", len);
+        }
+    }
+}
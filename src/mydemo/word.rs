@@ -0,0 +1,762 @@
+use crate::range_check::table::{RangeCheckTable, SplitTable, XorTable};
+use halo2_proofs::arithmetic::FieldExt;
+use halo2_proofs::circuit::{AssignedCell, Layouter, Region};
+use halo2_proofs::plonk::{Advice, Column, ConstraintSystem, Error, Expression, Fixed, Selector};
+use halo2_proofs::poly::Rotation;
+use std::marker::PhantomData;
+
+// add 最多支持的输入个数
+pub const ADD_INPUTS: usize = 6;
+
+// BYTES 个byte组成的整数, 比如 u32 / u64
+// value = sum bytes[i] * 256^i, 每个byte都查表检查过范围
+#[derive(Clone, Debug)]
+pub struct AssignedWord<F: FieldExt> {
+    pub value: AssignedCell<F, F>,
+    // 小端序
+    pub bytes: Vec<AssignedCell<F, F>>,
+}
+
+impl<F: FieldExt> AssignedWord<F> {
+    pub fn value_u64(&self) -> Option<u64> {
+        self.value.value().map(|v| v.get_lower_128() as u64)
+    }
+}
+
+// 所有的运算都拆成byte, 用查表实现:
+// word:    a[0] = sum a[1+i] * 256^i, a[1..=BYTES] 查 range table
+// add:     sum a[0..ADD_INPUTS] = a[0](next) + carry * 2^(8*BYTES), carry = a[ADD_INPUTS] 查 range table
+//          下一行是结果的 word 行
+// bitwise: a[0] ^ a[1] = a[2] 查 xor table
+//          a[0] & a[1] = a[3], 用 a + b = (a ^ b) + 2 * (a & b) 得到, 不需要额外的表
+//          a[0] | a[1] = a[4], 用 a | b = (a ^ b) + (a & b) 得到
+// split:   a[0] = a[1] * 2^shift + a[2] 查 split table
+// rotate:  a[3] = a[1] + a[2](next) * mul, mul = 2^(8-shift)
+#[derive(Clone, Debug)]
+pub struct WordConfig<F: FieldExt> {
+    pub advice: Vec<Column<Advice>>,
+    pub shift: Column<Fixed>,
+    pub mul: Column<Fixed>,
+    pub constant: Column<Fixed>,
+    pub s_word: Selector,
+    pub s_add: Selector,
+    pub s_bitwise: Selector,
+    pub s_split: Selector,
+    pub s_rotate: Selector,
+    pub range: RangeCheckTable<F, 8>,
+    pub xor: XorTable<F, 8>,
+    pub split: SplitTable<F>,
+}
+
+pub struct WordChip<F: FieldExt, const BYTES: usize> {
+    config: WordConfig<F>,
+    _p: PhantomData<F>,
+}
+
+pub type U8Chip<F> = WordChip<F, 1>;
+pub type U16Chip<F> = WordChip<F, 2>;
+pub type U32Chip<F> = WordChip<F, 4>;
+pub type U64Chip<F> = WordChip<F, 8>;
+
+// 输入是byte的哈希, 比如 SHA-256, Keccak-256, BLAKE2s
+// 消息长度在生成电路时确定, digest 是若干个 word
+pub trait CsByteHasher<F: FieldExt> {
+    fn configure(meta: &mut ConstraintSystem<F>) -> WordConfig<F>;
+    fn construct(config: WordConfig<F>) -> Self;
+    fn load_tables(&self, layouter: impl Layouter<F>) -> Result<(), Error>;
+    fn digest(
+        &self,
+        layouter: impl Layouter<F>,
+        message: &[Option<u8>],
+    ) -> Result<Vec<AssignedWord<F>>, Error>;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BitwiseOp {
+    Xor,
+    And,
+    Or,
+}
+
+fn byte<F: FieldExt>(value: &F) -> u64 {
+    value.get_lower_128() as u64
+}
+
+impl<F: FieldExt, const BYTES: usize> WordChip<F, BYTES> {
+    pub const BITS: usize = BYTES * 8;
+
+    pub fn construct(config: WordConfig<F>) -> Self {
+        Self {
+            config,
+            _p: Default::default(),
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> WordConfig<F> {
+        assert!(BYTES > 0 && BYTES <= 8);
+        let advice: Vec<Column<Advice>> = (0..(BYTES + 1).max(ADD_INPUTS + 1))
+            .map(|_| meta.advice_column())
+            .collect();
+        let shift = meta.fixed_column();
+        let mul = meta.fixed_column();
+        let constant = meta.fixed_column();
+        // 用在lookup里的selector必须是 complex selector
+        let s_word = meta.complex_selector();
+        let s_add = meta.complex_selector();
+        let s_bitwise = meta.complex_selector();
+        let s_split = meta.complex_selector();
+        let s_rotate = meta.selector();
+
+        for column in advice.iter() {
+            meta.enable_equality(*column);
+        }
+        meta.enable_constant(constant);
+
+        let range = RangeCheckTable::configure(meta);
+        let xor = XorTable::configure(meta);
+        let split = SplitTable::configure(meta);
+
+        meta.create_gate("word bytes", |meta| {
+            let s = meta.query_selector(s_word);
+            let value = meta.query_advice(advice[0], Rotation::cur());
+            let bytes = (0..BYTES).fold(Expression::Constant(F::zero()), |acc, i| {
+                let byte = meta.query_advice(advice[1 + i], Rotation::cur());
+                acc + byte * Expression::Constant(F::from_u128(1 << (8 * i)))
+            });
+            vec![s * (value - bytes)]
+        });
+        for i in 0..BYTES {
+            meta.lookup(|meta| {
+                let s = meta.query_selector(s_word);
+                let byte = meta.query_advice(advice[1 + i], Rotation::cur());
+                vec![(s * byte, range.value)]
+            });
+        }
+
+        meta.create_gate("word add", |meta| {
+            let s = meta.query_selector(s_add);
+            let sum = (0..ADD_INPUTS).fold(Expression::Constant(F::zero()), |acc, j| {
+                acc + meta.query_advice(advice[j], Rotation::cur())
+            });
+            let carry = meta.query_advice(advice[ADD_INPUTS], Rotation::cur());
+            let out = meta.query_advice(advice[0], Rotation::next());
+            let modulus = Expression::Constant(F::from_u128(1 << (8 * BYTES)));
+            vec![s * (sum - carry * modulus - out)]
+        });
+        meta.lookup(|meta| {
+            let s = meta.query_selector(s_add);
+            let carry = meta.query_advice(advice[ADD_INPUTS], Rotation::cur());
+            vec![(s * carry, range.value)]
+        });
+
+        meta.create_gate("word bitwise", |meta| {
+            let s = meta.query_selector(s_bitwise);
+            let a = meta.query_advice(advice[0], Rotation::cur());
+            let b = meta.query_advice(advice[1], Rotation::cur());
+            let xor = meta.query_advice(advice[2], Rotation::cur());
+            let and = meta.query_advice(advice[3], Rotation::cur());
+            let or = meta.query_advice(advice[4], Rotation::cur());
+            vec![
+                s.clone()
+                    * (a + b - xor.clone() - and.clone() * Expression::Constant(F::from(2u64))),
+                s * (or - xor - and),
+            ]
+        });
+        meta.lookup(|meta| {
+            let s = meta.query_selector(s_bitwise);
+            let a = meta.query_advice(advice[0], Rotation::cur());
+            let b = meta.query_advice(advice[1], Rotation::cur());
+            let out = meta.query_advice(advice[2], Rotation::cur());
+            vec![
+                (s.clone() * a, xor.a),
+                (s.clone() * b, xor.b),
+                (s * out, xor.out),
+            ]
+        });
+
+        meta.lookup(|meta| {
+            let s = meta.query_selector(s_split);
+            let shift = meta.query_fixed(shift, Rotation::cur());
+            let value = meta.query_advice(advice[0], Rotation::cur());
+            let hi = meta.query_advice(advice[1], Rotation::cur());
+            let lo = meta.query_advice(advice[2], Rotation::cur());
+            vec![
+                (s.clone() * shift, split.shift),
+                (s.clone() * value, split.value),
+                (s.clone() * hi, split.hi),
+                (s * lo, split.lo),
+            ]
+        });
+        meta.create_gate("word rotate", |meta| {
+            let s = meta.query_selector(s_rotate);
+            let hi = meta.query_advice(advice[1], Rotation::cur());
+            let lo_next = meta.query_advice(advice[2], Rotation::next());
+            let out = meta.query_advice(advice[3], Rotation::cur());
+            let mul = meta.query_fixed(mul, Rotation::cur());
+            vec![s * (out - hi - lo_next * mul)]
+        });
+
+        WordConfig {
+            advice,
+            shift,
+            mul,
+            constant,
+            s_word,
+            s_add,
+            s_bitwise,
+            s_split,
+            s_rotate,
+            range,
+            xor,
+            split,
+        }
+    }
+
+    pub fn load_tables(&self, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        self.config.range.load(layouter.namespace(|| "range"))?;
+        self.config.xor.load(layouter.namespace(|| "xor"))?;
+        self.config.split.load(layouter.namespace(|| "split"))
+    }
+
+    // 新的 word 行, bytes 由 value 拆出来
+    fn word_row(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        value: Option<u64>,
+    ) -> Result<AssignedWord<F>, Error> {
+        self.config.s_word.enable(region, offset)?;
+        let bytes = (0..BYTES)
+            .map(|i| {
+                let byte = value.map(|v| F::from((v >> (8 * i)) & 0xff));
+                region.assign_advice(
+                    || "byte",
+                    self.config.advice[1 + i],
+                    offset,
+                    || byte.ok_or(Error::Synthesis),
+                )
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        let value = region.assign_advice(
+            || "word",
+            self.config.advice[0],
+            offset,
+            || value.map(F::from).ok_or(Error::Synthesis),
+        )?;
+        Ok(AssignedWord { value, bytes })
+    }
+
+    // 把已有的 bytes 组合成 word
+    fn compose(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        bytes: &[AssignedCell<F, F>],
+    ) -> Result<AssignedWord<F>, Error> {
+        assert_eq!(bytes.len(), BYTES);
+        self.config.s_word.enable(region, offset)?;
+        let bytes = bytes
+            .iter()
+            .enumerate()
+            .map(|(i, b)| b.copy_advice(|| "copy byte", region, self.config.advice[1 + i], offset))
+            .collect::<Result<Vec<_>, Error>>()?;
+        let value = bytes
+            .iter()
+            .rev()
+            .try_fold(0u64, |acc, b| b.value().map(|b| (acc << 8) | byte(b)));
+        let value = region.assign_advice(
+            || "word",
+            self.config.advice[0],
+            offset,
+            || value.map(F::from).ok_or(Error::Synthesis),
+        )?;
+        Ok(AssignedWord { value, bytes })
+    }
+
+    pub fn load_private(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Option<u64>,
+    ) -> Result<AssignedWord<F>, Error> {
+        layouter.assign_region(
+            || "load private word",
+            |mut region| self.word_row(&mut region, 0, value),
+        )
+    }
+
+    pub fn load_constant(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: u64,
+    ) -> Result<AssignedWord<F>, Error> {
+        let bytes = value.to_le_bytes();
+        let bytes = self.load_constant_bytes(layouter.namespace(|| "bytes"), &bytes[..BYTES])?;
+        self.compose_bytes(layouter.namespace(|| "compose"), &bytes)
+    }
+
+    // 只是赋值, 范围在 compose_bytes 的时候检查
+    pub fn load_bytes(
+        &self,
+        mut layouter: impl Layouter<F>,
+        bytes: &[Option<u8>],
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        // 空的 region 会让 MockProver 报错时 panic
+        if bytes.is_empty() {
+            return Ok(vec![]);
+        }
+        layouter.assign_region(
+            || "load bytes",
+            |mut region| {
+                bytes
+                    .iter()
+                    .enumerate()
+                    .map(|(i, b)| {
+                        region.assign_advice(
+                            || "byte",
+                            self.config.advice[0],
+                            i,
+                            || b.map(|b| F::from(b as u64)).ok_or(Error::Synthesis),
+                        )
+                    })
+                    .collect()
+            },
+        )
+    }
+
+    pub fn load_constant_bytes(
+        &self,
+        mut layouter: impl Layouter<F>,
+        bytes: &[u8],
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        // 空的 region 会让 MockProver 报错时 panic
+        if bytes.is_empty() {
+            return Ok(vec![]);
+        }
+        layouter.assign_region(
+            || "load constant bytes",
+            |mut region| {
+                bytes
+                    .iter()
+                    .enumerate()
+                    .map(|(i, b)| {
+                        region.assign_advice_from_constant(
+                            || "byte",
+                            self.config.advice[0],
+                            i,
+                            F::from(*b as u64),
+                        )
+                    })
+                    .collect()
+            },
+        )
+    }
+
+    // bytes 是小端序
+    pub fn compose_bytes(
+        &self,
+        mut layouter: impl Layouter<F>,
+        bytes: &[AssignedCell<F, F>],
+    ) -> Result<AssignedWord<F>, Error> {
+        layouter.assign_region(
+            || "compose bytes",
+            |mut region| self.compose(&mut region, 0, bytes),
+        )
+    }
+
+    // b 为 None 时和 0xff 运算, 既 not
+    fn bitwise(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &AssignedWord<F>,
+        b: Option<&AssignedWord<F>>,
+        op: BitwiseOp,
+    ) -> Result<AssignedWord<F>, Error> {
+        layouter.assign_region(
+            || format!("{:?}", op),
+            |mut region| {
+                let advice = &self.config.advice;
+                let outputs = (0..BYTES)
+                    .map(|i| {
+                        self.config.s_bitwise.enable(&mut region, i)?;
+                        let x = a.bytes[i].copy_advice(|| "copy a", &mut region, advice[0], i)?;
+                        let y = match b {
+                            Some(b) => {
+                                b.bytes[i].copy_advice(|| "copy b", &mut region, advice[1], i)?
+                            }
+                            None => region.assign_advice_from_constant(
+                                || "0xff",
+                                advice[1],
+                                i,
+                                F::from(0xffu64),
+                            )?,
+                        };
+                        let values = x
+                            .value()
+                            .and_then(|x| y.value().map(|y| (byte(x), byte(y))));
+                        let xor = region.assign_advice(
+                            || "a ^ b",
+                            advice[2],
+                            i,
+                            || values.map(|(x, y)| F::from(x ^ y)).ok_or(Error::Synthesis),
+                        )?;
+                        let and = region.assign_advice(
+                            || "a & b",
+                            advice[3],
+                            i,
+                            || values.map(|(x, y)| F::from(x & y)).ok_or(Error::Synthesis),
+                        )?;
+                        let or = region.assign_advice(
+                            || "a | b",
+                            advice[4],
+                            i,
+                            || values.map(|(x, y)| F::from(x | y)).ok_or(Error::Synthesis),
+                        )?;
+                        Ok(match op {
+                            BitwiseOp::Xor => xor,
+                            BitwiseOp::And => and,
+                            BitwiseOp::Or => or,
+                        })
+                    })
+                    .collect::<Result<Vec<_>, Error>>()?;
+                self.compose(&mut region, BYTES, &outputs)
+            },
+        )
+    }
+
+    pub fn xor(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedWord<F>,
+        b: &AssignedWord<F>,
+    ) -> Result<AssignedWord<F>, Error> {
+        self.bitwise(layouter, a, Some(b), BitwiseOp::Xor)
+    }
+
+    pub fn and(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedWord<F>,
+        b: &AssignedWord<F>,
+    ) -> Result<AssignedWord<F>, Error> {
+        self.bitwise(layouter, a, Some(b), BitwiseOp::And)
+    }
+
+    pub fn or(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedWord<F>,
+        b: &AssignedWord<F>,
+    ) -> Result<AssignedWord<F>, Error> {
+        self.bitwise(layouter, a, Some(b), BitwiseOp::Or)
+    }
+
+    pub fn not(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedWord<F>,
+    ) -> Result<AssignedWord<F>, Error> {
+        self.bitwise(layouter, a, None, BitwiseOp::Xor)
+    }
+
+    // 模 2^(8*BYTES) 的加法
+    pub fn add(
+        &self,
+        mut layouter: impl Layouter<F>,
+        words: &[&AssignedWord<F>],
+    ) -> Result<AssignedWord<F>, Error> {
+        assert!(!words.is_empty() && words.len() <= ADD_INPUTS);
+        layouter.assign_region(
+            || "add",
+            |mut region| {
+                self.config.s_add.enable(&mut region, 0)?;
+                let mut sum = Some(0u128);
+                for j in 0..ADD_INPUTS {
+                    let column = self.config.advice[j];
+                    match words.get(j) {
+                        Some(word) => {
+                            word.value
+                                .copy_advice(|| "copy input", &mut region, column, 0)?;
+                            sum = sum.and_then(|s| word.value_u64().map(|v| s + v as u128));
+                        }
+                        None => {
+                            region.assign_advice_from_constant(|| "zero", column, 0, F::zero())?;
+                        }
+                    }
+                }
+                let carry = sum.map(|s| (s >> Self::BITS) as u64);
+                region.assign_advice(
+                    || "carry",
+                    self.config.advice[ADD_INPUTS],
+                    0,
+                    || carry.map(F::from).ok_or(Error::Synthesis),
+                )?;
+                let out = sum.map(|s| (s & ((1u128 << Self::BITS) - 1)) as u64);
+                self.word_row(&mut region, 1, out)
+            },
+        )
+    }
+
+    // 右移 r 位, r = 8q + s
+    // 第i行拆开第 q+i 个byte: byte = hi * 2^s + lo
+    // 结果的第i个byte = hi_{q+i} + lo_{q+i+1} * 2^(8-s)
+    // 循环移位时下标模 BYTES, 否则超出的byte为0
+    fn shift(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &AssignedWord<F>,
+        r: usize,
+        rotate: bool,
+    ) -> Result<AssignedWord<F>, Error> {
+        let r = r % Self::BITS;
+        let (q, s) = (r / 8, r % 8);
+        let mul = F::from(1u64 << (8 - s));
+        layouter.assign_region(
+            || {
+                if rotate {
+                    "rotate right"
+                } else {
+                    "shift right"
+                }
+            },
+            |mut region| {
+                let advice = &self.config.advice;
+                let mut parts = Vec::with_capacity(BYTES + 1);
+                for i in 0..=BYTES {
+                    self.config.s_split.enable(&mut region, i)?;
+                    region.assign_fixed(
+                        || "shift",
+                        self.config.shift,
+                        i,
+                        || Ok(F::from(s as u64)),
+                    )?;
+                    let j = q + i;
+                    let value = if rotate || j < BYTES {
+                        a.bytes[j % BYTES].copy_advice(|| "copy byte", &mut region, advice[0], i)?
+                    } else {
+                        region.assign_advice_from_constant(|| "zero", advice[0], i, F::zero())?
+                    };
+                    let value = value.value().map(byte);
+                    let hi = value.map(|v| v >> s);
+                    let lo = value.map(|v| v & ((1 << s) - 1));
+                    region.assign_advice(
+                        || "hi",
+                        advice[1],
+                        i,
+                        || hi.map(F::from).ok_or(Error::Synthesis),
+                    )?;
+                    region.assign_advice(
+                        || "lo",
+                        advice[2],
+                        i,
+                        || lo.map(F::from).ok_or(Error::Synthesis),
+                    )?;
+                    parts.push((hi, lo));
+                }
+
+                let outputs = (0..BYTES)
+                    .map(|i| {
+                        self.config.s_rotate.enable(&mut region, i)?;
+                        region.assign_fixed(|| "mul", self.config.mul, i, || Ok(mul))?;
+                        let out = parts[i]
+                            .0
+                            .and_then(|hi| parts[i + 1].1.map(|lo| hi + (lo << (8 - s))));
+                        region.assign_advice(
+                            || "out",
+                            advice[3],
+                            i,
+                            || out.map(F::from).ok_or(Error::Synthesis),
+                        )
+                    })
+                    .collect::<Result<Vec<_>, Error>>()?;
+                self.compose(&mut region, BYTES + 1, &outputs)
+            },
+        )
+    }
+
+    pub fn rotr(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedWord<F>,
+        r: usize,
+    ) -> Result<AssignedWord<F>, Error> {
+        self.shift(layouter, a, r, true)
+    }
+
+    pub fn rotl(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedWord<F>,
+        r: usize,
+    ) -> Result<AssignedWord<F>, Error> {
+        self.shift(layouter, a, Self::BITS - r % Self::BITS, true)
+    }
+
+    pub fn shr(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedWord<F>,
+        r: usize,
+    ) -> Result<AssignedWord<F>, Error> {
+        assert!(r < Self::BITS);
+        self.shift(layouter, a, r, false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mydemo::word::{WordChip, WordConfig};
+    use halo2_proofs::arithmetic::FieldExt;
+    use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner};
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::pairing::bn256::Fr;
+    use halo2_proofs::plonk::{Circuit, Column, ConstraintSystem, Error, Instance};
+
+    #[derive(Clone, Debug)]
+    pub struct TestConfig<F: FieldExt> {
+        word: WordConfig<F>,
+        instance: Column<Instance>,
+    }
+
+    // 暴露: a ^ b, a & b, a | b, !a, a + b + a, rotr(a, 13), rotl(a, 7), a >> 11
+    // 位移的位数对 BITS 取模
+    pub struct WordCircuit<F: FieldExt, const BYTES: usize> {
+        a: Option<u64>,
+        b: Option<u64>,
+        _p: std::marker::PhantomData<F>,
+    }
+
+    impl<F: FieldExt, const BYTES: usize> Circuit<F> for WordCircuit<F, BYTES> {
+        type Config = TestConfig<F>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                a: None,
+                b: None,
+                _p: Default::default(),
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            TestConfig {
+                word: WordChip::<F, BYTES>::configure(meta),
+                instance,
+            }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let chip = WordChip::<F, BYTES>::construct(config.word);
+            chip.load_tables(layouter.namespace(|| "tables"))?;
+            let a = chip.load_private(layouter.namespace(|| "a"), self.a)?;
+            let b = chip.load_private(layouter.namespace(|| "b"), self.b)?;
+            let outputs = [
+                chip.xor(layouter.namespace(|| "xor"), &a, &b)?,
+                chip.and(layouter.namespace(|| "and"), &a, &b)?,
+                chip.or(layouter.namespace(|| "or"), &a, &b)?,
+                chip.not(layouter.namespace(|| "not"), &a)?,
+                chip.add(layouter.namespace(|| "add"), &[&a, &b, &a])?,
+                chip.rotr(layouter.namespace(|| "rotr"), &a, 13)?,
+                chip.rotl(layouter.namespace(|| "rotl"), &a, 7)?,
+                chip.shr(layouter.namespace(|| "shr"), &a, 11 % (8 * BYTES))?,
+            ];
+            for (i, out) in outputs.iter().enumerate() {
+                layouter.constrain_instance(out.value.cell(), config.instance, i)?;
+            }
+            Ok(())
+        }
+    }
+
+    fn run<const BYTES: usize>(a: u64, b: u64, expected: Vec<u64>) {
+        let circuit = WordCircuit::<Fr, BYTES> {
+            a: Some(a),
+            b: Some(b),
+            _p: Default::default(),
+        };
+        let mut instance: Vec<Fr> = expected.into_iter().map(Fr::from).collect();
+        let prover = MockProver::run(17, &circuit, vec![instance.clone()]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        instance[4] += Fr::one();
+        let prover = MockProver::run(17, &circuit, vec![instance]).unwrap();
+        assert_ne!(prover.verify(), Ok(()));
+    }
+
+    #[test]
+    pub fn test_u32() {
+        let (a, b) = (0xdeadbeefu32, 0x8badf00du32);
+        let expected = vec![
+            a ^ b,
+            a & b,
+            a | b,
+            !a,
+            a.wrapping_add(b).wrapping_add(a),
+            a.rotate_right(13),
+            a.rotate_left(7),
+            a >> 11,
+        ];
+        run::<4>(
+            a as u64,
+            b as u64,
+            expected.into_iter().map(|v| v as u64).collect(),
+        );
+    }
+
+    #[test]
+    pub fn test_u64() {
+        let (a, b) = (0x0123456789abcdefu64, 0xfedcba9876543210u64);
+        let expected = vec![
+            a ^ b,
+            a & b,
+            a | b,
+            !a,
+            a.wrapping_add(b).wrapping_add(a),
+            a.rotate_right(13),
+            a.rotate_left(7),
+            a >> 11,
+        ];
+        run::<8>(a, b, expected);
+    }
+
+    #[test]
+    pub fn test_u8_u16() {
+        let (a, b) = (0xa5u8, 0x3cu8);
+        let expected = vec![
+            a ^ b,
+            a & b,
+            a | b,
+            !a,
+            a.wrapping_add(b).wrapping_add(a),
+            a.rotate_right(13),
+            a.rotate_left(7),
+            a >> 3,
+        ];
+        run::<1>(
+            a as u64,
+            b as u64,
+            expected.into_iter().map(|v| v as u64).collect(),
+        );
+
+        let (a, b) = (0xbeefu16, 0xf00du16);
+        let expected = vec![
+            a ^ b,
+            a & b,
+            a | b,
+            !a,
+            a.wrapping_add(b).wrapping_add(a),
+            a.rotate_right(13),
+            a.rotate_left(7),
+            a >> 11,
+        ];
+        run::<2>(
+            a as u64,
+            b as u64,
+            expected.into_iter().map(|v| v as u64).collect(),
+        );
+    }
+}
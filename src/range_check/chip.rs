@@ -0,0 +1,190 @@
+use crate::range_check::table::RangeCheckTable;
+use halo2_proofs::arithmetic::FieldExt;
+use halo2_proofs::circuit::{AssignedCell, Layouter};
+use halo2_proofs::plonk::{Advice, Column, ConstraintSystem, Error, Fixed, Selector, VirtualCells};
+use halo2_proofs::poly::Rotation;
+use std::marker::PhantomData;
+
+// 检查 value < 2^num_bits, 按字节查表
+// z | shift | s_range
+// z_0 = value, z_{i+1} = (z_i - limb_i) / 256, 最后一行 z = 0
+// limb_i = z_i - 256 * z_{i+1} 和 limb_i * shift 都要在 [0, 256) 里
+// 最后一个字节不满8位时 shift = 2^(8 - r), 这样 limb < 2^r; 其它行 shift = 1
+#[derive(Clone, Debug)]
+pub struct RangeConfig<F: FieldExt> {
+    pub z: Column<Advice>,
+    pub shift: Column<Fixed>,
+    pub constant: Column<Fixed>,
+    pub s_range: Selector,
+    pub table: RangeCheckTable<F, 8>,
+}
+
+pub struct RangeChip<F: FieldExt> {
+    config: RangeConfig<F>,
+    _p: PhantomData<F>,
+}
+
+impl<F: FieldExt> RangeChip<F> {
+    pub fn construct(config: RangeConfig<F>) -> Self {
+        Self {
+            config,
+            _p: Default::default(),
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> RangeConfig<F> {
+        let z = meta.advice_column();
+        let shift = meta.fixed_column();
+        let constant = meta.fixed_column();
+        let s_range = meta.complex_selector();
+        let table = RangeCheckTable::configure(meta);
+
+        meta.enable_equality(z);
+        meta.enable_constant(constant);
+
+        let limb = |meta: &mut VirtualCells<F>| {
+            let z_cur = meta.query_advice(z, Rotation::cur());
+            let z_next = meta.query_advice(z, Rotation::next());
+            z_cur - z_next * F::from(256u64)
+        };
+        meta.lookup(|meta| {
+            let s = meta.query_selector(s_range);
+            vec![(s * limb(meta), table.value)]
+        });
+        meta.lookup(|meta| {
+            let s = meta.query_selector(s_range);
+            let shift = meta.query_fixed(shift, Rotation::cur());
+            vec![(s * limb(meta) * shift, table.value)]
+        });
+
+        RangeConfig {
+            z,
+            shift,
+            constant,
+            s_range,
+            table,
+        }
+    }
+
+    pub fn load_table(&self, layouter: impl Layouter<F>) -> Result<(), Error> {
+        self.config.table.load(layouter)
+    }
+
+    pub fn range_check(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+        num_bits: usize,
+    ) -> Result<(), Error> {
+        assert!(num_bits > 0 && num_bits < F::NUM_BITS as usize);
+        let num_limbs = num_bits.div_ceil(8);
+        let partial = num_bits % 8;
+        layouter.assign_region(
+            || "range check",
+            |mut region| {
+                let config = &self.config;
+                value.copy_advice(|| "z", &mut region, config.z, 0)?;
+
+                // 小端序的字节, 从高位往低位算 z
+                let bytes = value.value().map(|v| v.to_repr());
+                let mut z = vec![Some(F::zero()); num_limbs + 1];
+                for i in (0..num_limbs).rev() {
+                    z[i] = z[i + 1].and_then(|next| {
+                        let byte = bytes.as_ref()?.as_ref()[i];
+                        Some(next * F::from(256u64) + F::from(byte as u64))
+                    });
+                }
+
+                for (i, z) in z.iter().enumerate().take(num_limbs) {
+                    config.s_range.enable(&mut region, i)?;
+                    let shift = if i == num_limbs - 1 && partial != 0 {
+                        1u64 << (8 - partial)
+                    } else {
+                        1
+                    };
+                    region.assign_fixed(|| "shift", config.shift, i, || Ok(F::from(shift)))?;
+                    if i > 0 {
+                        region.assign_advice(|| "z", config.z, i, || z.ok_or(Error::Synthesis))?;
+                    }
+                }
+                region.assign_advice_from_constant(|| "z", config.z, num_limbs, F::zero())?;
+                Ok(())
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::range_check::chip::{RangeChip, RangeConfig};
+    use halo2_proofs::arithmetic::FieldExt;
+    use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner};
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::pairing::bn256::Fr;
+    use halo2_proofs::plonk::{Advice, Circuit, Column, ConstraintSystem, Error};
+
+    #[derive(Default)]
+    pub struct RangeCircuit<F: FieldExt> {
+        value: Option<F>,
+        num_bits: usize,
+    }
+
+    impl<F: FieldExt> Circuit<F> for RangeCircuit<F> {
+        type Config = (RangeConfig<F>, Column<Advice>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                value: None,
+                num_bits: self.num_bits,
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let advice = meta.advice_column();
+            meta.enable_equality(advice);
+            (RangeChip::configure(meta), advice)
+        }
+
+        fn synthesize(
+            &self,
+            (config, advice): Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let chip = RangeChip::construct(config);
+            chip.load_table(layouter.namespace(|| "table"))?;
+            let value = layouter.assign_region(
+                || "value",
+                |mut region| {
+                    region.assign_advice(
+                        || "value",
+                        advice,
+                        0,
+                        || self.value.ok_or(Error::Synthesis),
+                    )
+                },
+            )?;
+            chip.range_check(layouter.namespace(|| "range"), &value, self.num_bits)
+        }
+    }
+
+    fn prove(value: Fr, num_bits: usize) -> bool {
+        let circuit = RangeCircuit {
+            value: Some(value),
+            num_bits,
+        };
+        let prover = MockProver::run(9, &circuit, vec![]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    #[test]
+    pub fn test_range_chip() {
+        for num_bits in [1, 5, 8, 12, 16, 33, 64, 100] {
+            let max = Fr::from_u128((1u128 << num_bits) - 1);
+            assert!(prove(Fr::zero(), num_bits));
+            assert!(prove(max, num_bits));
+            assert!(!prove(max + Fr::one(), num_bits));
+            assert!(!prove(-Fr::one(), num_bits));
+        }
+    }
+}
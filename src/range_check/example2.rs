@@ -69,6 +69,7 @@ impl<F: FieldExt, const RANGE: usize> RangeCheckConfig<F, RANGE> {
 #[cfg(test)]
 mod tests {
     use crate::range_check::example2::RangeCheckConfig;
+    use crate::range_check::harness::RangeCheckHarness;
     use halo2_proofs::arithmetic::FieldExt;
     use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner};
     use halo2_proofs::dev::MockProver;
@@ -111,4 +112,13 @@ mod tests {
             assert_eq!(prover.verify(), Ok(()));
         }
     }
+
+    #[test]
+    pub fn test_range_check_out_of_range() {
+        const RANGE: usize = 8;
+        let harness = RangeCheckHarness::new(4, RANGE as u64, "range check gate", |value| {
+            MyCircuit::<Fr, RANGE> { value: Some(value) }
+        });
+        harness.check_all();
+    }
 }
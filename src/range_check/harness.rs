@@ -0,0 +1,147 @@
+use halo2_proofs::arithmetic::{Field, FieldExt};
+use halo2_proofs::dev::{MockProver, VerifyFailure};
+use halo2_proofs::pairing::bn256::Fr;
+use halo2_proofs::pairing::group::ff::PrimeField;
+use halo2_proofs::plonk::Circuit;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+// range check 的测试工具
+// 之前的测试只验证了 [0,RANGE) 能通过, 这里把反例也补上:
+// 1. 穷举 [0,RANGE) 必须通过
+// 2. 边界值 RANGE, RANGE+1, 2^64, 2^128 必须失败
+// 3. 随机的域元素 (几乎一定越界) 必须失败
+// 4. 域上回绕的值 -1, -2, ..., -RANGE (既 p-1, p-2 ...) 必须失败
+// 失败时要求是 `gate` 这个约束不满足,而不是别的原因(比如cell没有赋值)
+pub struct RangeCheckHarness<C, B>
+where
+    C: Circuit<Fr>,
+    B: Fn(Fr) -> C,
+{
+    pub k: u32,
+    pub range: u64,
+    // 期望失败的gate名称, 既 create_gate 的第一个参数
+    pub gate: &'static str,
+    // 根据value构造电路
+    pub build: B,
+}
+
+impl<C, B> RangeCheckHarness<C, B>
+where
+    C: Circuit<Fr>,
+    B: Fn(Fr) -> C,
+{
+    pub fn new(k: u32, range: u64, gate: &'static str, build: B) -> Self {
+        Self {
+            k,
+            range,
+            gate,
+            build,
+        }
+    }
+
+    pub fn verify(&self, value: Fr) -> Result<(), Vec<VerifyFailure>> {
+        let circuit = (self.build)(value);
+        let prover = MockProver::run(self.k, &circuit, vec![]).unwrap();
+        prover.verify()
+    }
+
+    pub fn assert_in_range(&self, value: Fr) {
+        if let Err(failures) = self.verify(value) {
+            panic!(
+                "value {:?} should be in range [0,{}), but failed:\n{}",
+                value,
+                self.range,
+                describe(&failures)
+            );
+        }
+    }
+
+    // 返回失败的约束,方便调用方继续检查
+    pub fn assert_out_of_range(&self, value: Fr) -> Vec<VerifyFailure> {
+        let failures = match self.verify(value) {
+            Ok(()) => panic!(
+                "value {:?} is out of range [0,{}), but passed the range check",
+                value, self.range
+            ),
+            Err(failures) => failures,
+        };
+        let gate_failed = failures.iter().any(|failure| {
+            matches!(failure, VerifyFailure::ConstraintNotSatisfied { .. })
+                && format!("{:?}", failure).contains(self.gate)
+        });
+        assert!(
+            gate_failed,
+            "value {:?} was rejected, but not by gate \"{}\":\n{}",
+            value,
+            self.gate,
+            describe(&failures)
+        );
+        failures
+    }
+
+    pub fn check_exhaustive(&self) {
+        for i in 0..self.range {
+            self.assert_in_range(Fr::from(i));
+        }
+    }
+
+    pub fn check_boundaries(&self) {
+        let boundaries = [
+            Fr::from(self.range),
+            Fr::from(self.range + 1),
+            Fr::from(self.range * 2),
+            Fr::from(u64::MAX),
+            Fr::from_u128(u128::MAX),
+        ];
+        for value in boundaries {
+            self.assert_out_of_range(value);
+        }
+    }
+
+    pub fn check_random_out_of_range(&self, samples: usize) {
+        // 固定种子,失败时可以复现
+        let mut rng = StdRng::seed_from_u64(0x5eed);
+        let mut checked = 0;
+        while checked < samples {
+            let value = Fr::random(&mut rng);
+            if as_u128(value).is_some_and(|v| v < self.range as u128) {
+                continue;
+            }
+            self.assert_out_of_range(value);
+            checked += 1;
+        }
+    }
+
+    pub fn check_wraparound(&self) {
+        // -i = p - i, 在整数意义下是一个很大的数
+        for i in 1..=self.range {
+            self.assert_out_of_range(-Fr::from(i));
+        }
+    }
+
+    pub fn check_all(&self) {
+        self.check_exhaustive();
+        self.check_boundaries();
+        self.check_random_out_of_range(16);
+        self.check_wraparound();
+    }
+}
+
+// 如果value < 2^128 则返回对应的整数
+fn as_u128(value: Fr) -> Option<u128> {
+    let repr = value.to_repr();
+    if repr.as_ref()[16..].iter().all(|b| *b == 0) {
+        Some(value.get_lower_128())
+    } else {
+        None
+    }
+}
+
+pub fn describe(failures: &[VerifyFailure]) -> String {
+    failures
+        .iter()
+        .map(|failure| format!("  - {:?}", failure))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -1,3 +1,6 @@
+pub(crate) mod chip;
 mod example1;
 mod example2;
-mod table;
+#[cfg(test)]
+pub(crate) mod harness;
+pub(crate) mod table;
@@ -1,7 +1,116 @@
-use halo2_proofs::plonk::TableColumn;
+use halo2_proofs::arithmetic::FieldExt;
+use halo2_proofs::circuit::Layouter;
+use halo2_proofs::plonk::{ConstraintSystem, Error, TableColumn};
+use std::marker::PhantomData;
 
 // 这是一个look up table,用于判断是否在num_bits,比如说 NUM_BITS=8,则这个table可以判断[0,255]
 #[derive(Debug, Clone)]
-pub struct RangeCheckTable {
-    value: TableColumn,
+pub struct RangeCheckTable<F: FieldExt, const NUM_BITS: usize> {
+    pub value: TableColumn,
+    _p: PhantomData<F>,
+}
+
+impl<F: FieldExt, const NUM_BITS: usize> RangeCheckTable<F, NUM_BITS> {
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+        Self {
+            value: meta.lookup_table_column(),
+            _p: Default::default(),
+        }
+    }
+
+    pub fn load(&self, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_table(
+            || "load range check table",
+            |mut table| {
+                for i in 0..1usize << NUM_BITS {
+                    table.assign_cell(|| "value", self.value, i, || Ok(F::from(i as u64)))?;
+                }
+                Ok(())
+            },
+        )
+    }
+}
+
+// a | b | a ^ b, a 和 b 都是 NUM_BITS 位, 一共 2^(2*NUM_BITS) 行
+// 查表的同时也检查了 a, b 的范围
+// and / or 可以由 xor 推出来, 不需要单独的表; NUM_BITS = 16 时要 2^32 行, 所以都按byte查
+#[derive(Debug, Clone)]
+pub struct XorTable<F: FieldExt, const NUM_BITS: usize> {
+    pub a: TableColumn,
+    pub b: TableColumn,
+    pub out: TableColumn,
+    _p: PhantomData<F>,
+}
+
+impl<F: FieldExt, const NUM_BITS: usize> XorTable<F, NUM_BITS> {
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+        Self {
+            a: meta.lookup_table_column(),
+            b: meta.lookup_table_column(),
+            out: meta.lookup_table_column(),
+            _p: Default::default(),
+        }
+    }
+
+    pub fn load(&self, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_table(
+            || "load xor table",
+            |mut table| {
+                let size = 1u64 << NUM_BITS;
+                for a in 0..size {
+                    for b in 0..size {
+                        let offset = (a * size + b) as usize;
+                        table.assign_cell(|| "a", self.a, offset, || Ok(F::from(a)))?;
+                        table.assign_cell(|| "b", self.b, offset, || Ok(F::from(b)))?;
+                        table.assign_cell(|| "a ^ b", self.out, offset, || Ok(F::from(a ^ b)))?;
+                    }
+                }
+                Ok(())
+            },
+        )
+    }
+}
+
+// 把一个byte在第 shift 位拆开, 用于移位和循环移位:
+// shift | value | hi | lo
+// value = hi * 2^shift + lo, lo < 2^shift, hi < 2^(8-shift), shift in [0, 8)
+#[derive(Debug, Clone)]
+pub struct SplitTable<F: FieldExt> {
+    pub shift: TableColumn,
+    pub value: TableColumn,
+    pub hi: TableColumn,
+    pub lo: TableColumn,
+    _p: PhantomData<F>,
+}
+
+impl<F: FieldExt> SplitTable<F> {
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+        Self {
+            shift: meta.lookup_table_column(),
+            value: meta.lookup_table_column(),
+            hi: meta.lookup_table_column(),
+            lo: meta.lookup_table_column(),
+            _p: Default::default(),
+        }
+    }
+
+    pub fn load(&self, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_table(
+            || "load split table",
+            |mut table| {
+                for shift in 0..8u64 {
+                    for value in 0..256u64 {
+                        let offset = (shift * 256 + value) as usize;
+                        let hi = value >> shift;
+                        let lo = value & ((1 << shift) - 1);
+                        table.assign_cell(|| "shift", self.shift, offset, || Ok(F::from(shift)))?;
+                        table.assign_cell(|| "value", self.value, offset, || Ok(F::from(value)))?;
+                        table.assign_cell(|| "hi", self.hi, offset, || Ok(F::from(hi)))?;
+                        table.assign_cell(|| "lo", self.lo, offset, || Ok(F::from(lo)))?;
+                    }
+                }
+                Ok(())
+            },
+        )
+    }
 }
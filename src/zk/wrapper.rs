@@ -3,3 +3,18 @@ use halo2_proofs::pairing::group::ff::PrimeField;
 pub struct PrimeWrapper<F: PrimeField>(F);
 
 impl<F: PrimeField> PrimeWrapper<F> {}
+
+// 大端序的16进制字符串转为域元素, 主要用于测试向量
+// 要求 repr 是小端序的 (bn256 的 Fr 就是)
+pub fn from_hex<F: PrimeField>(hex: &str) -> F {
+    let hex = hex.trim_start_matches("0x");
+    let mut repr = F::Repr::default();
+    let bytes = repr.as_mut();
+    assert!(hex.len() <= 2 * bytes.len(), "hex string too long");
+    let padded = format!("{:0>width$}", hex, width = 2 * bytes.len());
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        let pos = padded.len() - 2 * (i + 1);
+        *byte = u8::from_str_radix(&padded[pos..pos + 2], 16).unwrap();
+    }
+    Option::from(F::from_repr(repr)).expect("not a canonical field element")
+}